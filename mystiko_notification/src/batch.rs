@@ -0,0 +1,269 @@
+use crate::Notification;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::time::Instant;
+use typed_builder::TypedBuilder;
+
+pub trait DedupKey {
+    fn dedup_key(&self) -> String;
+}
+
+#[async_trait]
+pub trait BatchNotification<M>: Notification<M>
+where
+    M: Clone + Send + 'static,
+{
+    async fn push_batch(&self, messages: Vec<M>) -> Result<(), BatchPushError<M, Self::Error>> {
+        let mut messages = messages.into_iter();
+        while let Some(message) = messages.next() {
+            if let Err(error) = self.push(message.clone()).await {
+                let undelivered = std::iter::once(message).chain(messages).collect();
+                return Err(BatchPushError::new(error, undelivered));
+            }
+        }
+        Ok(())
+    }
+}
+
+// the messages of a batch that were not delivered, so callers resend only those.
+#[derive(Debug, Error)]
+#[error("{error}")]
+pub struct BatchPushError<M, E> {
+    pub error: E,
+    pub undelivered: Vec<M>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct BatchOptions {
+    #[builder(default = Duration::from_secs(1))]
+    pub window: Duration,
+    #[builder(default = 10)]
+    pub max_batch_size: usize,
+    #[builder(default, setter(strip_option))]
+    pub max_messages_per_second: Option<u32>,
+}
+
+pub struct BatchingNotification<M, N> {
+    inner: Arc<BatchingInner<M, N>>,
+}
+
+struct BatchingInner<M, N> {
+    backend: N,
+    options: BatchOptions,
+    buffer: Mutex<BatchBuffer<M>>,
+    next_send: tokio::sync::Mutex<Option<Instant>>,
+}
+
+struct BatchBuffer<M> {
+    messages: Vec<M>,
+    keys: HashMap<String, usize>,
+    coalesced: u64,
+    flush_scheduled: bool,
+    closed: bool,
+}
+
+#[async_trait]
+impl<M, N> Notification<M> for BatchingNotification<M, N>
+where
+    M: DedupKey + Clone + Send + Sync + 'static,
+    N: BatchNotification<M> + 'static,
+    N::Error: Display + Send,
+{
+    type Error = N::Error;
+
+    async fn push(&self, message: M) -> Result<(), Self::Error> {
+        let (message, schedule_flush) = {
+            let mut buffer = self.inner.buffer.lock().unwrap();
+            // after shutdown nothing would flush the buffer again, so deliver directly.
+            if buffer.closed {
+                (Some(message), false)
+            } else {
+                buffer.insert(message);
+                (None, !std::mem::replace(&mut buffer.flush_scheduled, true))
+            }
+        };
+        if let Some(message) = message {
+            return self.inner.backend.push(message).await;
+        }
+        if schedule_flush {
+            BatchingInner::schedule_flush(self.inner.clone());
+        }
+        Ok(())
+    }
+}
+
+impl<M, N> BatchingNotification<M, N>
+where
+    M: DedupKey + Clone + Send + Sync + 'static,
+    N: BatchNotification<M> + 'static,
+{
+    pub fn new(backend: N, options: BatchOptions) -> Self {
+        Self {
+            inner: Arc::new(BatchingInner {
+                backend,
+                options,
+                buffer: Mutex::new(BatchBuffer::default()),
+                next_send: tokio::sync::Mutex::new(None),
+            }),
+        }
+    }
+
+    pub async fn flush(&self) -> Result<usize, N::Error> {
+        self.inner.flush().await
+    }
+
+    pub async fn shutdown(&self) -> Result<usize, N::Error> {
+        self.inner.buffer.lock().unwrap().closed = true;
+        self.inner.flush().await
+    }
+
+    pub fn buffered(&self) -> usize {
+        self.inner.buffer.lock().unwrap().messages.len()
+    }
+
+    pub fn coalesced(&self) -> u64 {
+        self.inner.buffer.lock().unwrap().coalesced
+    }
+
+    pub fn inner(&self) -> &N {
+        &self.inner.backend
+    }
+}
+
+impl<M, N> BatchingInner<M, N>
+where
+    M: DedupKey + Clone + Send + Sync + 'static,
+    N: BatchNotification<M> + 'static,
+    N::Error: Display,
+{
+    fn schedule_flush(inner: Arc<Self>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(inner.options.window).await;
+                match inner.flush().await {
+                    Ok(_) => break,
+                    Err(error) => {
+                        log::warn!("failed to flush batched notifications, will retry: {}", error);
+                        let mut buffer = inner.buffer.lock().unwrap();
+                        if buffer.closed || buffer.messages.is_empty() || buffer.flush_scheduled {
+                            break;
+                        }
+                        buffer.flush_scheduled = true;
+                    }
+                }
+            }
+        });
+    }
+}
+
+impl<M, N> BatchingInner<M, N>
+where
+    M: DedupKey + Clone + Send + Sync + 'static,
+    N: BatchNotification<M>,
+{
+    async fn flush(&self) -> Result<usize, N::Error> {
+        let mut next_send = self.next_send.lock().await;
+        let messages = {
+            let mut buffer = self.buffer.lock().unwrap();
+            buffer.keys.clear();
+            buffer.flush_scheduled = false;
+            std::mem::take(&mut buffer.messages)
+        };
+        let mut batch_size = self.options.max_batch_size.max(1);
+        if let Some(rate) = self.options.max_messages_per_second {
+            batch_size = batch_size.min(rate.max(1) as usize);
+        }
+        let mut delivered = 0;
+        let mut messages = messages.into_iter().peekable();
+        while messages.peek().is_some() {
+            let batch = messages.by_ref().take(batch_size).collect::<Vec<_>>();
+            let size = batch.len();
+            if let Some(rate) = self.options.max_messages_per_second {
+                if let Some(next) = *next_send {
+                    tokio::time::sleep_until(next).await;
+                }
+                let interval = Duration::from_secs_f64(size as f64 / rate.max(1) as f64);
+                *next_send = Some(Instant::now() + interval);
+            }
+            if let Err(error) = self.backend.push_batch(batch).await {
+                self.buffer
+                    .lock()
+                    .unwrap()
+                    .requeue(error.undelivered.into_iter().chain(messages));
+                return Err(error.error);
+            }
+            delivered += size;
+        }
+        Ok(delivered)
+    }
+}
+
+impl<M> BatchBuffer<M>
+where
+    M: DedupKey,
+{
+    fn insert(&mut self, message: M) {
+        let key = message.dedup_key();
+        if let Some(index) = self.keys.get(&key).copied() {
+            self.messages[index] = message;
+            self.coalesced += 1;
+        } else {
+            self.keys.insert(key, self.messages.len());
+            self.messages.push(message);
+        }
+    }
+
+    // undelivered messages go back ahead of anything pushed during the flush,
+    // and a newer message with the same key still replaces the older one.
+    fn requeue<I>(&mut self, undelivered: I)
+    where
+        I: IntoIterator<Item = M>,
+    {
+        let pushed = std::mem::take(&mut self.messages);
+        self.keys.clear();
+        for message in undelivered.into_iter().chain(pushed) {
+            let key = message.dedup_key();
+            if let Some(index) = self.keys.get(&key).copied() {
+                self.messages[index] = message;
+            } else {
+                self.keys.insert(key, self.messages.len());
+                self.messages.push(message);
+            }
+        }
+    }
+}
+
+impl<M> Default for BatchBuffer<M> {
+    fn default() -> Self {
+        Self {
+            messages: vec![],
+            keys: HashMap::new(),
+            coalesced: 0,
+            flush_scheduled: false,
+            closed: false,
+        }
+    }
+}
+
+impl<M, E> BatchPushError<M, E> {
+    pub fn new(error: E, undelivered: Vec<M>) -> Self {
+        Self { error, undelivered }
+    }
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl DedupKey for String {
+    fn dedup_key(&self) -> String {
+        self.clone()
+    }
+}
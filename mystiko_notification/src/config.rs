@@ -0,0 +1,426 @@
+use crate::{
+    DispatchMode, Notification, NotificationFilter, NotificationMessage, NotificationRoute, NotificationRouter,
+    NotificationRouterError, NotificationSeverity, RetryOptions,
+};
+#[cfg(feature = "smtp")]
+use crate::{EmailNotification, SmtpConfig};
+use mystiko_utils::config::{load_config, ConfigFile, ConfigLoadOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
+use typed_builder::TypedBuilder;
+
+const NOTIFICATION_ENV_CONFIG_PREFIX: &str = "MYSTIKO_NOTIFICATION";
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct NotificationConfig {
+    #[serde(default)]
+    #[builder(default)]
+    pub mode: DispatchMode,
+
+    #[serde(default)]
+    #[builder(default)]
+    pub retry: Option<NotificationRetryConfig>,
+
+    #[serde(default)]
+    #[builder(default)]
+    pub routes: HashMap<String, NotificationRouteConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct NotificationRouteConfig {
+    #[serde(default = "default_enabled")]
+    #[builder(default = default_enabled())]
+    pub enabled: bool,
+
+    #[serde(default)]
+    #[builder(default)]
+    pub min_severity: Option<NotificationSeverity>,
+
+    #[serde(default)]
+    #[builder(default)]
+    pub topics: Vec<String>,
+
+    pub backend: NotificationBackendConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationBackendConfig {
+    Sns {
+        region: String,
+        topic_arn: String,
+    },
+    Sqs {
+        region: String,
+        queue_url: String,
+    },
+    EventBridge {
+        region: String,
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(default)]
+        event_bus_name: Option<String>,
+    },
+    Webhook {
+        url: String,
+        #[serde(default)]
+        format: WebhookFormatConfig,
+        #[serde(default)]
+        chat_id: Option<String>,
+        #[serde(default)]
+        template: Option<String>,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    #[cfg(feature = "smtp")]
+    Smtp(SmtpConfig),
+    File {
+        path: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormatConfig {
+    #[default]
+    Slack,
+    Discord,
+    Telegram,
+    Template,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct NotificationRetryConfig {
+    #[serde(default = "default_max_retries")]
+    #[builder(default = default_max_retries())]
+    pub max_retries: u32,
+
+    #[serde(default = "default_initial_backoff_ms")]
+    #[builder(default = default_initial_backoff_ms())]
+    pub initial_backoff_ms: u64,
+
+    #[serde(default = "default_max_backoff_ms")]
+    #[builder(default = default_max_backoff_ms())]
+    pub max_backoff_ms: u64,
+
+    #[serde(default = "default_backoff_multiplier")]
+    #[builder(default = default_backoff_multiplier())]
+    pub backoff_multiplier: f64,
+
+    #[serde(default)]
+    #[builder(default)]
+    pub timeout_ms: Option<u64>,
+
+    #[serde(default)]
+    #[builder(default)]
+    pub circuit_breaker_threshold: Option<u32>,
+
+    #[serde(default = "default_circuit_breaker_cooldown_secs")]
+    #[builder(default = default_circuit_breaker_cooldown_secs())]
+    pub circuit_breaker_cooldown_secs: u64,
+}
+
+#[derive(Debug, Error)]
+pub enum NotificationConfigError {
+    #[error("notification backend {0} is not enabled in this build")]
+    UnsupportedBackendError(String),
+    #[error("invalid notification route {0}: {1}")]
+    InvalidRouteError(String, String),
+    #[error("invalid notification retry config: {0}")]
+    InvalidRetryError(String),
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl NotificationConfig {
+    pub fn new(config_path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let config_file: Option<ConfigFile<PathBuf>> = config_path
+            .map(|p| {
+                if p.join("notification.json").exists() {
+                    Some(p.join("notification").into())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(None);
+        let options = if let Some(file) = config_file {
+            ConfigLoadOptions::<PathBuf>::builder()
+                .paths(file)
+                .env_prefix(NOTIFICATION_ENV_CONFIG_PREFIX.to_string())
+                .build()
+        } else {
+            ConfigLoadOptions::<PathBuf>::builder()
+                .env_prefix(NOTIFICATION_ENV_CONFIG_PREFIX.to_string())
+                .build()
+        };
+        load_config::<PathBuf, Self>(&options)
+    }
+
+    pub fn create_router(&self) -> Result<NotificationRouter<NotificationMessage>, NotificationConfigError> {
+        if let Some(retry) = self.retry.as_ref() {
+            retry.validate()?;
+        }
+        let retry = self.retry.as_ref().map(RetryOptions::from);
+        let mut names = self
+            .routes
+            .iter()
+            .filter(|(_, route)| route.enabled)
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        names.sort();
+        let mut router = NotificationRouter::builder().mode(self.mode.clone()).build();
+        for name in names {
+            let route = &self.routes[name];
+            let filter = NotificationFilter {
+                min_severity: route.min_severity,
+                topics: route.topics.clone(),
+            };
+            router.add_route(create_route(name, &route.backend, retry.as_ref())?.with_filter(filter));
+        }
+        Ok(router)
+    }
+
+    pub fn create_notification(
+        &self,
+    ) -> Result<Box<dyn Notification<NotificationMessage, Error = NotificationRouterError>>, NotificationConfigError>
+    {
+        Ok(Box::new(self.create_router()?))
+    }
+}
+
+impl From<&NotificationRetryConfig> for RetryOptions {
+    fn from(config: &NotificationRetryConfig) -> Self {
+        RetryOptions {
+            max_retries: config.max_retries,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+            backoff_multiplier: config.backoff_multiplier,
+            timeout: config.timeout_ms.map(Duration::from_millis),
+            circuit_breaker_threshold: config.circuit_breaker_threshold,
+            circuit_breaker_cooldown: Duration::from_secs(config.circuit_breaker_cooldown_secs),
+        }
+    }
+}
+
+impl NotificationRetryConfig {
+    pub fn validate(&self) -> Result<(), NotificationConfigError> {
+        if !self.backoff_multiplier.is_finite() || self.backoff_multiplier < 1.0 {
+            return Err(NotificationConfigError::InvalidRetryError(format!(
+                "backoff_multiplier must be a finite number of at least 1, got {}",
+                self.backoff_multiplier
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for NotificationRetryConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl NotificationBackendConfig {
+    pub fn backend_type(&self) -> &'static str {
+        match self {
+            NotificationBackendConfig::Sns { .. } => "sns",
+            NotificationBackendConfig::Sqs { .. } => "sqs",
+            NotificationBackendConfig::EventBridge { .. } => "event_bridge",
+            NotificationBackendConfig::Webhook { .. } => "webhook",
+            #[cfg(feature = "smtp")]
+            NotificationBackendConfig::Smtp(_) => "smtp",
+            NotificationBackendConfig::File { .. } => "file",
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "eventbridge",
+    feature = "file",
+    feature = "smtp",
+    feature = "sns",
+    feature = "sqs",
+    feature = "webhook"
+))]
+fn create_route(
+    name: &str,
+    backend: &NotificationBackendConfig,
+    retry: Option<&RetryOptions>,
+) -> Result<NotificationRoute<NotificationMessage>, NotificationConfigError> {
+    match backend {
+        #[cfg(feature = "sns")]
+        NotificationBackendConfig::Sns { region, topic_arn } => Ok(wrap_route(
+            name,
+            crate::SnsNotification::from_region(parse_region(name, region)?).with_topic_arn(topic_arn),
+            retry,
+        )),
+        #[cfg(feature = "sqs")]
+        NotificationBackendConfig::Sqs { region, queue_url } => Ok(wrap_route(
+            name,
+            crate::SqsNotification::from_region(parse_region(name, region)?).with_queue_url(queue_url),
+            retry,
+        )),
+        #[cfg(feature = "eventbridge")]
+        NotificationBackendConfig::EventBridge {
+            region,
+            source,
+            event_bus_name,
+        } => {
+            let mut notification = crate::EventBridgeNotification::from_region(parse_region(name, region)?);
+            if let Some(source) = source {
+                notification = notification.with_source(source);
+            }
+            if let Some(event_bus_name) = event_bus_name {
+                notification = notification.with_event_bus_name(event_bus_name);
+            }
+            Ok(wrap_route(name, notification, retry))
+        }
+        #[cfg(feature = "webhook")]
+        NotificationBackendConfig::Webhook {
+            url,
+            format,
+            chat_id,
+            template,
+            headers,
+        } => {
+            let format = match format {
+                WebhookFormatConfig::Slack => crate::WebhookFormat::Slack,
+                WebhookFormatConfig::Discord => crate::WebhookFormat::Discord,
+                WebhookFormatConfig::Telegram => crate::WebhookFormat::Telegram {
+                    chat_id: chat_id
+                        .clone()
+                        .ok_or_else(|| invalid_route(name, "telegram chat_id is missing"))?,
+                },
+                WebhookFormatConfig::Template => crate::WebhookFormat::Template(
+                    template
+                        .clone()
+                        .ok_or_else(|| invalid_route(name, "webhook template is missing"))?,
+                ),
+            };
+            let notification = crate::WebhookNotification::builder()
+                .url(url)
+                .format(format)
+                .headers(headers.clone())
+                .build();
+            Ok(wrap_route(name, notification, retry))
+        }
+        #[cfg(feature = "smtp")]
+        NotificationBackendConfig::Smtp(config) => {
+            let notification =
+                EmailNotification::from_config(config).map_err(|error| invalid_route(name, &error.to_string()))?;
+            Ok(wrap_route(name, notification, retry))
+        }
+        #[cfg(feature = "file")]
+        NotificationBackendConfig::File { path } => {
+            Ok(wrap_route(name, crate::FileNotification::new(path.clone()), retry))
+        }
+        #[cfg(not(feature = "sns"))]
+        NotificationBackendConfig::Sns { .. } => Err(unsupported_backend(backend)),
+        #[cfg(not(feature = "sqs"))]
+        NotificationBackendConfig::Sqs { .. } => Err(unsupported_backend(backend)),
+        #[cfg(not(feature = "eventbridge"))]
+        NotificationBackendConfig::EventBridge { .. } => Err(unsupported_backend(backend)),
+        #[cfg(not(feature = "webhook"))]
+        NotificationBackendConfig::Webhook { .. } => Err(unsupported_backend(backend)),
+        #[cfg(not(feature = "file"))]
+        NotificationBackendConfig::File { .. } => Err(unsupported_backend(backend)),
+    }
+}
+
+#[cfg(not(any(
+    feature = "eventbridge",
+    feature = "file",
+    feature = "smtp",
+    feature = "sns",
+    feature = "sqs",
+    feature = "webhook"
+)))]
+fn create_route(
+    _name: &str,
+    backend: &NotificationBackendConfig,
+    _retry: Option<&RetryOptions>,
+) -> Result<NotificationRoute<NotificationMessage>, NotificationConfigError> {
+    Err(unsupported_backend(backend))
+}
+
+#[cfg(any(
+    feature = "eventbridge",
+    feature = "file",
+    feature = "smtp",
+    feature = "sns",
+    feature = "sqs",
+    feature = "webhook"
+))]
+fn wrap_route<N>(name: &str, backend: N, retry: Option<&RetryOptions>) -> NotificationRoute<NotificationMessage>
+where
+    N: Notification<NotificationMessage> + 'static,
+    N::Error: std::error::Error + crate::RetryableError + Send + Sync + 'static,
+{
+    match retry {
+        Some(options) => NotificationRoute::new(name, crate::RetryingNotification::new(backend, options.clone())),
+        None => NotificationRoute::new(name, backend),
+    }
+}
+
+#[cfg(any(feature = "sns", feature = "sqs", feature = "eventbridge"))]
+fn parse_region(name: &str, region: &str) -> Result<rusoto_core::Region, NotificationConfigError> {
+    region
+        .parse::<rusoto_core::Region>()
+        .map_err(|error| invalid_route(name, &error.to_string()))
+}
+
+#[cfg(any(
+    feature = "eventbridge",
+    feature = "smtp",
+    feature = "sns",
+    feature = "sqs",
+    feature = "webhook"
+))]
+fn invalid_route(name: &str, message: &str) -> NotificationConfigError {
+    NotificationConfigError::InvalidRouteError(name.to_string(), message.to_string())
+}
+
+#[cfg(not(all(
+    feature = "eventbridge",
+    feature = "file",
+    feature = "sns",
+    feature = "sqs",
+    feature = "webhook"
+)))]
+fn unsupported_backend(backend: &NotificationBackendConfig) -> NotificationConfigError {
+    NotificationConfigError::UnsupportedBackendError(backend.backend_type().to_string())
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    200
+}
+
+fn default_max_backoff_ms() -> u64 {
+    10000
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_circuit_breaker_cooldown_secs() -> u64 {
+    30
+}
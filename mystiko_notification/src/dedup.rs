@@ -0,0 +1,192 @@
+use crate::{DedupKey, Notification, NotificationMessage, NotificationSeverity};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+use typed_builder::TypedBuilder;
+
+pub trait DedupMessage: DedupKey + Sized {
+    fn resolved(&self) -> Self;
+
+    fn with_suppressed(self, suppressed: u64) -> Self;
+}
+
+#[derive(Debug, TypedBuilder)]
+pub struct DedupNotification<N, M> {
+    inner: N,
+    #[builder(default = Duration::from_secs(300))]
+    window: Duration,
+    #[builder(default = Duration::from_secs(3600))]
+    retention: Duration,
+    #[builder(default = 10_000)]
+    max_alerts: usize,
+    #[builder(default, setter(skip))]
+    alerts: Mutex<HashMap<String, DedupState<M>>>,
+}
+
+#[derive(Debug)]
+struct DedupState<M> {
+    message: M,
+    last_seen: Instant,
+    last_sent: Option<Instant>,
+    suppressed: u64,
+}
+
+#[async_trait]
+impl<N, M> Notification<M> for DedupNotification<N, M>
+where
+    N: Notification<M>,
+    M: DedupMessage + Clone + Send + Sync + 'static,
+{
+    type Error = N::Error;
+
+    async fn push(&self, message: M) -> Result<(), Self::Error> {
+        let key = message.dedup_key();
+        let suppressed = {
+            let mut alerts = self.alerts.lock().unwrap();
+            let now = Instant::now();
+            if !alerts.contains_key(&key) {
+                self.evict(&mut alerts, now);
+            }
+            let state = alerts.entry(key.clone()).or_insert_with(|| DedupState {
+                message: message.clone(),
+                last_seen: now,
+                last_sent: None,
+                suppressed: 0,
+            });
+            state.message = message.clone();
+            state.last_seen = now;
+            if state
+                .last_sent
+                .map(|last_sent| now.duration_since(last_sent) < self.window)
+                .unwrap_or(false)
+            {
+                state.suppressed += 1;
+                return Ok(());
+            }
+            state.last_sent = Some(now);
+            std::mem::take(&mut state.suppressed)
+        };
+        let message = if suppressed > 0 {
+            message.with_suppressed(suppressed)
+        } else {
+            message
+        };
+        let result = self.inner.push(message).await;
+        if result.is_err() {
+            if let Some(state) = self.alerts.lock().unwrap().get_mut(&key) {
+                state.last_sent = None;
+                state.suppressed += suppressed;
+            }
+        }
+        result
+    }
+}
+
+impl<N, M> DedupNotification<N, M>
+where
+    N: Notification<M>,
+    M: DedupMessage + Clone + Send + Sync + 'static,
+{
+    pub fn new(inner: N, window: Duration) -> Self {
+        Self::builder().inner(inner).window(window).build()
+    }
+
+    pub async fn resolve(&self, key: &str) -> Result<bool, N::Error> {
+        let resolved = self.alerts.lock().unwrap().get(key).map(|state| {
+            let resolved = state.message.resolved();
+            if state.suppressed > 0 {
+                resolved.with_suppressed(state.suppressed)
+            } else {
+                resolved
+            }
+        });
+        match resolved {
+            Some(resolved) => {
+                self.inner.push(resolved).await?;
+                self.alerts.lock().unwrap().remove(key);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.alerts.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.lock().unwrap().is_empty()
+    }
+
+    pub fn inner(&self) -> &N {
+        &self.inner
+    }
+
+    pub fn is_active(&self, key: &str) -> bool {
+        self.alerts.lock().unwrap().contains_key(key)
+    }
+
+    pub fn suppressed(&self, key: &str) -> u64 {
+        self.alerts
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|state| state.suppressed)
+            .unwrap_or_default()
+    }
+
+    pub fn summary(&self) -> HashMap<String, u64> {
+        self.alerts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, state)| state.suppressed > 0)
+            .map(|(key, state)| (key.clone(), state.suppressed))
+            .collect()
+    }
+
+    // alerts that were never resolved are dropped once idle for the retention period,
+    // and the least recently seen ones make room when the map is full.
+    fn evict(&self, alerts: &mut HashMap<String, DedupState<M>>, now: Instant) {
+        let retention = self.retention.max(self.window);
+        alerts.retain(|_, state| now.duration_since(state.last_seen) < retention);
+        while !alerts.is_empty() && alerts.len() >= self.max_alerts.max(1) {
+            let oldest = alerts
+                .iter()
+                .min_by_key(|(_, state)| state.last_seen)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                alerts.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl DedupMessage for String {
+    fn resolved(&self) -> Self {
+        format!("[resolved] {}", self)
+    }
+
+    fn with_suppressed(self, suppressed: u64) -> Self {
+        format!("{} ({} duplicates suppressed)", self, suppressed)
+    }
+}
+
+impl DedupMessage for NotificationMessage {
+    fn resolved(&self) -> Self {
+        let mut resolved = self.clone();
+        resolved.title = Some(format!("[resolved] {}", self.subject()));
+        resolved.severity = NotificationSeverity::Info;
+        resolved.dedup_id = self.dedup_id.as_ref().map(|dedup_id| format!("{}-resolved", dedup_id));
+        resolved.attributes.insert("status".to_string(), "resolved".to_string());
+        resolved
+    }
+
+    fn with_suppressed(mut self, suppressed: u64) -> Self {
+        self.body = format!("{}\n\n({} duplicates suppressed)", self.body, suppressed);
+        self.attributes.insert("suppressed".to_string(), suppressed.to_string());
+        self
+    }
+}
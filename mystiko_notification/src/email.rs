@@ -0,0 +1,233 @@
+use crate::{BatchNotification, DedupKey, Notification, NotificationMessage, RetryableError, RoutableMessage};
+use async_trait::async_trait;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error;
+use typed_builder::TypedBuilder;
+
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct EmailMessage {
+    #[builder(default, setter(strip_option))]
+    pub from: Option<String>,
+    #[builder(default)]
+    pub to: Vec<String>,
+    #[builder(default)]
+    pub cc: Vec<String>,
+    #[builder(default)]
+    pub bcc: Vec<String>,
+    pub subject: String,
+    #[builder(default)]
+    pub text: String,
+    #[builder(default, setter(strip_option))]
+    pub html: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum SmtpSecurity {
+    #[default]
+    Tls,
+    StartTls,
+    None,
+}
+
+#[derive(Clone, PartialEq, Eq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[builder(field_defaults(setter(into)))]
+pub struct SmtpConfig {
+    pub host: String,
+    #[builder(default, setter(strip_option))]
+    pub port: Option<u16>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[builder(default)]
+    pub security: SmtpSecurity,
+    #[builder(default, setter(strip_option))]
+    pub username: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub password: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub timeout_secs: Option<u64>,
+    pub from: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[builder(default)]
+    pub to: Vec<String>,
+}
+
+impl std::fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("security", &self.security)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("timeout_secs", &self.timeout_secs)
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish()
+    }
+}
+
+#[derive(Debug, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct EmailNotification<T: AsyncTransport = AsyncSmtpTransport<Tokio1Executor>> {
+    transport: T,
+    from: String,
+    #[builder(default)]
+    to: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum EmailNotificationError {
+    #[error(transparent)]
+    AddressError(#[from] lettre::address::AddressError),
+    #[error(transparent)]
+    MessageError(#[from] lettre::error::Error),
+    #[error(transparent)]
+    SmtpError(#[from] lettre::transport::smtp::Error),
+    #[error("email transport error: {0}")]
+    TransportError(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("email message has no recipients")]
+    NoRecipientsError,
+}
+
+#[async_trait]
+impl<T> Notification<EmailMessage> for EmailNotification<T>
+where
+    T: AsyncTransport + Send + Sync,
+    T::Error: std::error::Error + Send + Sync + 'static,
+{
+    type Error = EmailNotificationError;
+
+    async fn push(&self, message: EmailMessage) -> Result<(), Self::Error> {
+        let email = self.build_message(message)?;
+        self.transport
+            .send(email)
+            .await
+            .map_err(EmailNotificationError::from_transport)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T> Notification<NotificationMessage> for EmailNotification<T>
+where
+    T: AsyncTransport + Send + Sync,
+    T::Error: std::error::Error + Send + Sync + 'static,
+{
+    type Error = EmailNotificationError;
+
+    async fn push(&self, message: NotificationMessage) -> Result<(), Self::Error> {
+        Notification::<EmailMessage>::push(self, message.into()).await
+    }
+}
+
+impl<T> BatchNotification<EmailMessage> for EmailNotification<T>
+where
+    T: AsyncTransport + Send + Sync,
+    T::Error: std::error::Error + Send + Sync + 'static,
+{
+}
+
+impl<T> BatchNotification<NotificationMessage> for EmailNotification<T>
+where
+    T: AsyncTransport + Send + Sync,
+    T::Error: std::error::Error + Send + Sync + 'static,
+{
+}
+
+impl<T> EmailNotification<T>
+where
+    T: AsyncTransport,
+{
+    pub fn build_message(&self, message: EmailMessage) -> Result<Message, EmailNotificationError> {
+        let from = message.from.as_ref().unwrap_or(&self.from);
+        let to = if message.to.is_empty() { &self.to } else { &message.to };
+        if to.is_empty() && message.cc.is_empty() && message.bcc.is_empty() {
+            return Err(EmailNotificationError::NoRecipientsError);
+        }
+        let mut builder = Message::builder()
+            .from(from.parse::<Mailbox>()?)
+            .subject(message.subject);
+        for address in to.iter() {
+            builder = builder.to(address.parse::<Mailbox>()?);
+        }
+        for address in message.cc.iter() {
+            builder = builder.cc(address.parse::<Mailbox>()?);
+        }
+        for address in message.bcc.iter() {
+            builder = builder.bcc(address.parse::<Mailbox>()?);
+        }
+        let email = match message.html {
+            Some(html) => builder.multipart(MultiPart::alternative_plain_html(message.text, html))?,
+            None => builder.body(message.text)?,
+        };
+        Ok(email)
+    }
+}
+
+impl EmailNotification<AsyncSmtpTransport<Tokio1Executor>> {
+    pub fn new(transport: AsyncSmtpTransport<Tokio1Executor>, from: String, to: Vec<String>) -> Self {
+        Self { transport, from, to }
+    }
+
+    pub fn from_config(config: &SmtpConfig) -> Result<Self, EmailNotificationError> {
+        let mut builder = match config.security {
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        };
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        if let Some(timeout_secs) = config.timeout_secs {
+            builder = builder.timeout(Some(Duration::from_secs(timeout_secs)));
+        }
+        Ok(Self::new(builder.build(), config.from.clone(), config.to.clone()))
+    }
+}
+
+impl RoutableMessage for EmailMessage {}
+
+impl DedupKey for EmailMessage {
+    fn dedup_key(&self) -> String {
+        format!("{}\n{}\n{}", self.to.join(","), self.subject, self.text)
+    }
+}
+
+impl From<NotificationMessage> for EmailMessage {
+    fn from(message: NotificationMessage) -> Self {
+        Self::builder().subject(message.subject()).text(message.body).build()
+    }
+}
+
+impl EmailNotificationError {
+    fn from_transport<E>(error: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let error: Box<dyn std::error::Error + Send + Sync> = Box::new(error);
+        match error.downcast::<lettre::transport::smtp::Error>() {
+            Ok(error) => EmailNotificationError::SmtpError(*error),
+            Err(error) => EmailNotificationError::TransportError(error),
+        }
+    }
+}
+
+impl RetryableError for EmailNotificationError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            EmailNotificationError::SmtpError(error) => !error.is_permanent(),
+            EmailNotificationError::TransportError(_) => true,
+            _ => false,
+        }
+    }
+}
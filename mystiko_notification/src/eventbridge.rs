@@ -0,0 +1,243 @@
+use crate::{BatchNotification, BatchPushError, Notification, NotificationMessage, RetryableError};
+use async_trait::async_trait;
+use rusoto_core::{Region, RusotoError};
+use rusoto_events::{
+    EventBridge, EventBridgeClient, PutEventsError, PutEventsRequest, PutEventsRequestEntry, PutEventsResponse,
+};
+use serde_json::json;
+use thiserror::Error;
+use typed_builder::TypedBuilder;
+
+const DEFAULT_EVENT_SOURCE: &str = "mystiko.notification";
+const MAX_PUT_EVENTS_ENTRIES: usize = 10;
+const RETRYABLE_ENTRY_ERRORS: [&str; 2] = ["ThrottlingException", "InternalFailure"];
+
+#[derive(Debug, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct EventBridgeNotification<C: EventBridge = EventBridgeClient> {
+    client: C,
+    #[builder(default = DEFAULT_EVENT_SOURCE.to_string())]
+    source: String,
+    #[builder(default, setter(strip_option))]
+    event_bus_name: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum EventBridgeNotificationError {
+    #[error(transparent)]
+    PushError(#[from] RusotoError<PutEventsError>),
+    #[error("eventbridge rejected {} event(s): {}", .0.len(), .0.join("; "))]
+    FailedEntriesError(Vec<String>),
+}
+
+#[async_trait]
+impl<C> Notification<PutEventsRequestEntry> for EventBridgeNotification<C>
+where
+    C: EventBridge + Send + Sync,
+{
+    type Error = EventBridgeNotificationError;
+
+    async fn push(&self, message: PutEventsRequestEntry) -> Result<(), Self::Error> {
+        self.put_events(vec![message]).await.map_err(|error| error.error)
+    }
+}
+
+#[async_trait]
+impl<C> Notification<NotificationMessage> for EventBridgeNotification<C>
+where
+    C: EventBridge + Send + Sync,
+{
+    type Error = EventBridgeNotificationError;
+
+    async fn push(&self, message: NotificationMessage) -> Result<(), Self::Error> {
+        self.put_events(vec![message.into()]).await.map_err(|error| error.error)
+    }
+}
+
+#[async_trait]
+impl<C> BatchNotification<PutEventsRequestEntry> for EventBridgeNotification<C>
+where
+    C: EventBridge + Send + Sync,
+{
+    async fn push_batch(
+        &self,
+        messages: Vec<PutEventsRequestEntry>,
+    ) -> Result<(), BatchPushError<PutEventsRequestEntry, Self::Error>> {
+        self.put_events(messages.clone())
+            .await
+            .map_err(|error| BatchPushError::new(error.error, undelivered(messages, &error.undelivered)))
+    }
+}
+
+#[async_trait]
+impl<C> BatchNotification<NotificationMessage> for EventBridgeNotification<C>
+where
+    C: EventBridge + Send + Sync,
+{
+    async fn push_batch(
+        &self,
+        messages: Vec<NotificationMessage>,
+    ) -> Result<(), BatchPushError<NotificationMessage, Self::Error>> {
+        self.put_events(messages.iter().cloned().map(PutEventsRequestEntry::from).collect())
+            .await
+            .map_err(|error| BatchPushError::new(error.error, undelivered(messages, &error.undelivered)))
+    }
+}
+
+impl<C> EventBridgeNotification<C>
+where
+    C: EventBridge,
+{
+    pub fn with_source<S>(mut self, source: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.source = source.into();
+        self
+    }
+
+    pub fn with_event_bus_name<B>(mut self, event_bus_name: B) -> Self
+    where
+        B: Into<String>,
+    {
+        self.event_bus_name = Some(event_bus_name.into());
+        self
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn event_bus_name(&self) -> Option<&str> {
+        self.event_bus_name.as_deref()
+    }
+
+    // failures carry the positions of the entries eventbridge did not accept.
+    async fn put_events(
+        &self,
+        entries: Vec<PutEventsRequestEntry>,
+    ) -> Result<(), BatchPushError<usize, EventBridgeNotificationError>> {
+        let entries = entries
+            .into_iter()
+            .map(|mut entry| {
+                if entry.source.is_none() {
+                    entry.source = Some(self.source.clone());
+                }
+                if entry.event_bus_name.is_none() {
+                    entry.event_bus_name = self.event_bus_name.clone();
+                }
+                entry
+            })
+            .collect::<Vec<_>>();
+        let mut failures = vec![];
+        let mut failed = vec![];
+        for (chunk_index, chunk) in entries.chunks(MAX_PUT_EVENTS_ENTRIES).enumerate() {
+            let offset = chunk_index * MAX_PUT_EVENTS_ENTRIES;
+            let request = PutEventsRequest {
+                entries: chunk.to_vec(),
+            };
+            let response = match self.client.put_events(request).await {
+                Ok(response) => response,
+                Err(error) => {
+                    failed.extend(offset..entries.len());
+                    return Err(BatchPushError::new(error.into(), failed));
+                }
+            };
+            for (index, failure) in failed_entries(response, chunk.len()) {
+                failed.push(offset + index);
+                failures.push(failure);
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(BatchPushError::new(
+                EventBridgeNotificationError::FailedEntriesError(failures),
+                failed,
+            ))
+        }
+    }
+}
+
+impl EventBridgeNotification<EventBridgeClient> {
+    pub fn new(client: EventBridgeClient) -> Self {
+        Self::builder().client(client).build()
+    }
+
+    pub fn from_region<R>(region: R) -> Self
+    where
+        R: Into<Region>,
+    {
+        Self::new(EventBridgeClient::new(region.into()))
+    }
+}
+
+impl From<NotificationMessage> for PutEventsRequestEntry {
+    fn from(message: NotificationMessage) -> Self {
+        let detail_type = message.subject();
+        let detail = json!({
+            "title": message.title,
+            "body": message.body,
+            "severity": message.severity.to_string(),
+            "topic": message.topic,
+            "tags": message.tags,
+            "attributes": message.attributes,
+            "dedup_id": message.dedup_id,
+            "group_id": message.group_id,
+        });
+        PutEventsRequestEntry {
+            detail: Some(detail.to_string()),
+            detail_type: Some(detail_type),
+            ..Default::default()
+        }
+    }
+}
+
+impl RetryableError for EventBridgeNotificationError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            EventBridgeNotificationError::PushError(RusotoError::Service(PutEventsError::Internal(_))) => true,
+            EventBridgeNotificationError::PushError(RusotoError::HttpDispatch(_)) => true,
+            EventBridgeNotificationError::PushError(RusotoError::Unknown(response)) => {
+                response.status.is_server_error() || response.status.as_u16() == 429
+            }
+            EventBridgeNotificationError::FailedEntriesError(errors) => errors
+                .iter()
+                .all(|error| RETRYABLE_ENTRY_ERRORS.iter().any(|code| error.starts_with(code))),
+            _ => false,
+        }
+    }
+}
+
+fn failed_entries(response: PutEventsResponse, count: usize) -> Vec<(usize, String)> {
+    if response.failed_entry_count.unwrap_or_default() <= 0 {
+        return vec![];
+    }
+    let failed = response
+        .entries
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            let error_code = entry.error_code?;
+            Some((
+                index,
+                format!("{}: {}", error_code, entry.error_message.unwrap_or_default()),
+            ))
+        })
+        .collect::<Vec<_>>();
+    if failed.is_empty() {
+        (0..count).map(|index| (index, "unknown error".to_string())).collect()
+    } else {
+        failed
+    }
+}
+
+fn undelivered<M>(messages: Vec<M>, failed: &[usize]) -> Vec<M> {
+    messages
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| failed.contains(index))
+        .map(|(_, message)| message)
+        .collect()
+}
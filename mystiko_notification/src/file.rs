@@ -0,0 +1,109 @@
+use crate::{BatchNotification, BatchPushError, Notification, RetryableError};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+#[derive(Debug)]
+pub struct FileNotification<M> {
+    path: PathBuf,
+    lock: Mutex<()>,
+    _message: PhantomData<fn() -> M>,
+}
+
+#[derive(Debug, Error)]
+pub enum FileNotificationError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+}
+
+#[async_trait]
+impl<M> Notification<M> for FileNotification<M>
+where
+    M: Serialize + Send + Sync + 'static,
+{
+    type Error = FileNotificationError;
+
+    async fn push(&self, message: M) -> Result<(), Self::Error> {
+        self.append(&[message]).await
+    }
+}
+
+#[async_trait]
+impl<M> BatchNotification<M> for FileNotification<M>
+where
+    M: Serialize + Clone + Send + Sync + 'static,
+{
+    async fn push_batch(&self, messages: Vec<M>) -> Result<(), BatchPushError<M, Self::Error>> {
+        self.append(&messages)
+            .await
+            .map_err(|error| BatchPushError::new(error, messages))
+    }
+}
+
+impl<M> FileNotification<M> {
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+            _message: PhantomData,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<M> FileNotification<M>
+where
+    M: Serialize + Send + Sync + 'static,
+{
+    pub async fn read(&self) -> Result<Vec<M>, FileNotificationError>
+    where
+        M: DeserializeOwned,
+    {
+        let _guard = self.lock.lock().await;
+        if !fs::try_exists(&self.path).await? {
+            return Ok(vec![]);
+        }
+        let content = fs::read_to_string(&self.path).await?;
+        let mut messages = vec![];
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            messages.push(serde_json::from_str(line)?);
+        }
+        Ok(messages)
+    }
+
+    async fn append(&self, messages: &[M]) -> Result<(), FileNotificationError> {
+        let mut lines = vec![];
+        for message in messages.iter() {
+            lines.extend(serde_json::to_vec(message)?);
+            lines.push(b'\n');
+        }
+        let _guard = self.lock.lock().await;
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        file.write_all(&lines).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+impl RetryableError for FileNotificationError {
+    fn is_retryable(&self) -> bool {
+        matches!(self, FileNotificationError::IoError(_))
+    }
+}
@@ -1,7 +1,49 @@
+mod batch;
+#[cfg(feature = "config")]
+mod config;
+mod dedup;
+#[cfg(feature = "smtp")]
+mod email;
+#[cfg(feature = "eventbridge")]
+mod eventbridge;
+#[cfg(feature = "file")]
+mod file;
+mod memory;
+mod message;
 mod notification;
+#[cfg(feature = "outbox")]
+mod outbox;
+mod retry;
+mod router;
 #[cfg(feature = "sns")]
 mod sns;
+#[cfg(feature = "sqs")]
+mod sqs;
+mod template;
+#[cfg(feature = "webhook")]
+mod webhook;
 
+pub use batch::*;
+#[cfg(feature = "config")]
+pub use config::*;
+pub use dedup::*;
+#[cfg(feature = "smtp")]
+pub use email::*;
+#[cfg(feature = "eventbridge")]
+pub use eventbridge::*;
+#[cfg(feature = "file")]
+pub use file::*;
+pub use memory::*;
+pub use message::*;
 pub use notification::*;
+#[cfg(feature = "outbox")]
+pub use outbox::*;
+pub use retry::*;
+pub use router::*;
 #[cfg(feature = "sns")]
 pub use sns::*;
+#[cfg(feature = "sqs")]
+pub use sqs::*;
+pub use template::*;
+#[cfg(feature = "webhook")]
+pub use webhook::*;
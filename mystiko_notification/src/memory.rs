@@ -0,0 +1,130 @@
+use crate::{BatchNotification, Notification, RetryableError};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+#[derive(Debug)]
+pub struct MemoryNotification<M> {
+    inner: Arc<Mutex<MemoryState<M>>>,
+}
+
+#[derive(Debug, Error)]
+pub enum MemoryNotificationError {
+    #[error("injected notification failure: {0}")]
+    InjectedError(String),
+}
+
+#[derive(Debug)]
+struct MemoryState<M> {
+    messages: Vec<M>,
+    failures: VecDeque<String>,
+    failing: Option<String>,
+    attempts: usize,
+}
+
+#[async_trait]
+impl<M> Notification<M> for MemoryNotification<M>
+where
+    M: Send + 'static,
+{
+    type Error = MemoryNotificationError;
+
+    async fn push(&self, message: M) -> Result<(), Self::Error> {
+        let mut state = self.inner.lock().unwrap();
+        state.attempts += 1;
+        if let Some(error) = state.failures.pop_front().or_else(|| state.failing.clone()) {
+            return Err(MemoryNotificationError::InjectedError(error));
+        }
+        state.messages.push(message);
+        Ok(())
+    }
+}
+
+impl<M> BatchNotification<M> for MemoryNotification<M> where M: Clone + Send + 'static {}
+
+impl<M> MemoryNotification<M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn attempts(&self) -> usize {
+        self.inner.lock().unwrap().attempts
+    }
+
+    pub fn take(&self) -> Vec<M> {
+        std::mem::take(&mut self.inner.lock().unwrap().messages)
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.inner.lock().unwrap();
+        state.messages.clear();
+        state.failures.clear();
+        state.failing = None;
+        state.attempts = 0;
+    }
+
+    pub fn fail_next<E>(&self, error: E)
+    where
+        E: Into<String>,
+    {
+        self.inner.lock().unwrap().failures.push_back(error.into());
+    }
+
+    pub fn fail_always<E>(&self, error: E)
+    where
+        E: Into<String>,
+    {
+        self.inner.lock().unwrap().failing = Some(error.into());
+    }
+
+    pub fn recover(&self) {
+        let mut state = self.inner.lock().unwrap();
+        state.failures.clear();
+        state.failing = None;
+    }
+}
+
+impl<M> MemoryNotification<M>
+where
+    M: Clone,
+{
+    pub fn messages(&self) -> Vec<M> {
+        self.inner.lock().unwrap().messages.clone()
+    }
+
+    pub fn last(&self) -> Option<M> {
+        self.inner.lock().unwrap().messages.last().cloned()
+    }
+}
+
+impl<M> Clone for MemoryNotification<M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<M> Default for MemoryNotification<M> {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(MemoryState {
+                messages: vec![],
+                failures: VecDeque::new(),
+                failing: None,
+                attempts: 0,
+            })),
+        }
+    }
+}
+
+impl RetryableError for MemoryNotificationError {}
@@ -0,0 +1,71 @@
+use crate::{DedupKey, NotificationSeverity, RoutableMessage};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use typed_builder::TypedBuilder;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[builder(field_defaults(setter(into)))]
+pub struct NotificationMessage {
+    #[builder(default, setter(strip_option))]
+    pub title: Option<String>,
+    pub body: String,
+    #[builder(default)]
+    pub severity: NotificationSeverity,
+    #[builder(default, setter(strip_option))]
+    pub topic: Option<String>,
+    #[builder(default)]
+    pub tags: Vec<String>,
+    #[builder(default)]
+    pub attributes: HashMap<String, String>,
+    #[builder(default, setter(strip_option))]
+    pub dedup_id: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub group_id: Option<String>,
+}
+
+impl NotificationMessage {
+    pub fn subject(&self) -> String {
+        match &self.title {
+            Some(title) => title.clone(),
+            None => format!("[{}] notification", self.severity),
+        }
+    }
+}
+
+impl RoutableMessage for NotificationMessage {
+    fn severity(&self) -> NotificationSeverity {
+        self.severity
+    }
+
+    fn topic(&self) -> Option<&str> {
+        self.topic.as_deref()
+    }
+}
+
+impl DedupKey for NotificationMessage {
+    fn dedup_key(&self) -> String {
+        match &self.dedup_id {
+            Some(dedup_id) => dedup_id.clone(),
+            None => format!(
+                "{}\n{}\n{}",
+                self.topic.as_deref().unwrap_or_default(),
+                self.title.as_deref().unwrap_or_default(),
+                self.body
+            ),
+        }
+    }
+}
+
+impl From<String> for NotificationMessage {
+    fn from(body: String) -> Self {
+        Self::builder().body(body).build()
+    }
+}
+
+impl From<&str> for NotificationMessage {
+    fn from(body: &str) -> Self {
+        Self::builder().body(body).build()
+    }
+}
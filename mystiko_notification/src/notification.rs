@@ -6,3 +6,15 @@ pub trait Notification<M>: Send + Sync {
 
     async fn push(&self, message: M) -> Result<(), Self::Error>;
 }
+
+#[async_trait]
+impl<M, E> Notification<M> for Box<dyn Notification<M, Error = E>>
+where
+    M: Send + 'static,
+{
+    type Error = E;
+
+    async fn push(&self, message: M) -> Result<(), Self::Error> {
+        self.as_ref().push(message).await
+    }
+}
@@ -0,0 +1,318 @@
+use crate::Notification;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{watch, Mutex, Notify};
+use tokio::task::JoinHandle;
+use typed_builder::TypedBuilder;
+
+const QUEUE_FOLDER: &str = "queue";
+const DEAD_LETTER_FOLDER: &str = "dead";
+const ENTRY_EXTENSION: &str = "json";
+const TEMP_EXTENSION: &str = "tmp";
+
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct OutboxOptions {
+    pub folder: PathBuf,
+    #[builder(default = 10)]
+    pub max_attempts: u32,
+    #[builder(default = Duration::from_secs(1))]
+    pub poll_interval: Duration,
+    #[builder(default = Duration::from_secs(1))]
+    pub retry_backoff: Duration,
+    #[builder(default = Duration::from_secs(60))]
+    pub max_retry_backoff: Duration,
+}
+
+#[derive(Debug)]
+pub struct NotificationOutbox<M> {
+    options: OutboxOptions,
+    queue_folder: PathBuf,
+    dead_folder: PathBuf,
+    sequence: AtomicU64,
+    notify: Notify,
+    drain_lock: Mutex<()>,
+    _message: PhantomData<fn() -> M>,
+}
+
+#[derive(Debug)]
+pub struct OutboxWorker {
+    handle: JoinHandle<()>,
+    shutdown: watch::Sender<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutboxDrainResult {
+    pub delivered: usize,
+    pub dead: usize,
+    pub failed: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum OutboxError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OutboxEntry<M> {
+    enqueued_at: u64,
+    attempts: u32,
+    message: M,
+}
+
+#[async_trait]
+impl<M> Notification<M> for NotificationOutbox<M>
+where
+    M: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    type Error = OutboxError;
+
+    async fn push(&self, message: M) -> Result<(), Self::Error> {
+        self.enqueue(message).await
+    }
+}
+
+impl<M> NotificationOutbox<M>
+where
+    M: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    pub async fn new<O>(options: O) -> Result<Self, OutboxError>
+    where
+        O: Into<OutboxOptions>,
+    {
+        let options = options.into();
+        let queue_folder = options.folder.join(QUEUE_FOLDER);
+        let dead_folder = options.folder.join(DEAD_LETTER_FOLDER);
+        fs::create_dir_all(&queue_folder).await?;
+        fs::create_dir_all(&dead_folder).await?;
+        for path in list_entries(&queue_folder, TEMP_EXTENSION).await? {
+            fs::remove_file(path).await?;
+        }
+        Ok(Self {
+            options,
+            queue_folder,
+            dead_folder,
+            sequence: AtomicU64::new(0),
+            notify: Notify::new(),
+            drain_lock: Mutex::new(()),
+            _message: PhantomData,
+        })
+    }
+
+    pub async fn enqueue(&self, message: M) -> Result<(), OutboxError> {
+        let enqueued_at = now_millis();
+        let sequence = self.sequence.fetch_add(1, Ordering::SeqCst);
+        let path = self
+            .queue_folder
+            .join(format!("{:020}-{:020}.{}", enqueued_at, sequence, ENTRY_EXTENSION));
+        let entry = OutboxEntry {
+            enqueued_at,
+            attempts: 0,
+            message,
+        };
+        write_entry(&path, &entry).await?;
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    pub async fn depth(&self) -> Result<usize, OutboxError> {
+        Ok(list_entries(&self.queue_folder, ENTRY_EXTENSION).await?.len())
+    }
+
+    pub async fn dead_letters(&self) -> Result<usize, OutboxError> {
+        Ok(list_entries(&self.dead_folder, ENTRY_EXTENSION).await?.len())
+    }
+
+    pub async fn oldest_age(&self) -> Result<Option<Duration>, OutboxError> {
+        let entries = list_entries(&self.queue_folder, ENTRY_EXTENSION).await?;
+        let oldest = entries.first().and_then(|path| enqueued_at(path));
+        Ok(oldest.map(|enqueued_at| Duration::from_millis(now_millis().saturating_sub(enqueued_at))))
+    }
+
+    pub async fn drain<N>(&self, backend: &N) -> Result<OutboxDrainResult, OutboxError>
+    where
+        N: Notification<M>,
+        N::Error: Display,
+    {
+        let _guard = self.drain_lock.lock().await;
+        let mut result = OutboxDrainResult::default();
+        for path in list_entries(&self.queue_folder, ENTRY_EXTENSION).await? {
+            let (mut entry, message) = match read_entry::<M>(&path).await {
+                Ok(decoded) => decoded,
+                Err(OutboxError::SerdeJsonError(error)) => {
+                    log::error!(
+                        "moving undecodable outbox notification {:?} to dead letters: {}",
+                        path,
+                        error
+                    );
+                    self.move_to_dead(&path).await?;
+                    result.dead += 1;
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            entry.attempts += 1;
+            let failure = backend.push(message).await.err().map(|error| error.to_string());
+            match failure {
+                None => {
+                    fs::remove_file(&path).await?;
+                    result.delivered += 1;
+                }
+                Some(error) => {
+                    log::warn!(
+                        "failed to deliver outbox notification {:?} on attempt {}: {}",
+                        path,
+                        entry.attempts,
+                        error
+                    );
+                    if entry.attempts >= self.options.max_attempts {
+                        write_entry(&self.dead_path(&path), &entry).await?;
+                        fs::remove_file(&path).await?;
+                        result.dead += 1;
+                    } else {
+                        write_entry(&path, &entry).await?;
+                        result.failed = true;
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn spawn<N>(self: &Arc<Self>, backend: N) -> OutboxWorker
+    where
+        N: Notification<M> + 'static,
+        N::Error: Display,
+    {
+        let outbox = self.clone();
+        let (shutdown, mut shutdown_receiver) = watch::channel(false);
+        let handle = tokio::spawn(async move {
+            let mut failures = 0_u32;
+            loop {
+                let wait = match outbox.drain(&backend).await {
+                    Ok(result) if !result.failed => {
+                        failures = 0;
+                        outbox.options.poll_interval
+                    }
+                    Ok(_) => {
+                        failures += 1;
+                        outbox.retry_backoff(failures)
+                    }
+                    Err(error) => {
+                        log::error!("failed to drain notification outbox: {}", error);
+                        failures += 1;
+                        outbox.retry_backoff(failures)
+                    }
+                };
+                tokio::select! {
+                    _ = shutdown_receiver.changed() => break,
+                    _ = outbox.notify.notified(), if failures == 0 => {},
+                    _ = tokio::time::sleep(wait) => {},
+                }
+            }
+        });
+        OutboxWorker { handle, shutdown }
+    }
+
+    async fn move_to_dead(&self, path: &Path) -> Result<(), OutboxError> {
+        fs::rename(path, self.dead_path(path)).await?;
+        sync_folder(&self.dead_folder).await
+    }
+
+    fn dead_path(&self, path: &Path) -> PathBuf {
+        let file_name = path.file_name().map(PathBuf::from).unwrap_or_default();
+        self.dead_folder.join(file_name)
+    }
+
+    fn retry_backoff(&self, failures: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(failures.saturating_sub(1));
+        self.options
+            .retry_backoff
+            .saturating_mul(factor)
+            .min(self.options.max_retry_backoff)
+    }
+}
+
+impl OutboxWorker {
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        let _ = self.handle.await;
+    }
+}
+
+async fn list_entries(folder: &Path, extension: &str) -> Result<Vec<PathBuf>, OutboxError> {
+    let mut paths = vec![];
+    let mut entries = fs::read_dir(folder).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().map(|e| e == extension).unwrap_or(false) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+async fn write_entry<M>(path: &Path, entry: &OutboxEntry<M>) -> Result<(), OutboxError>
+where
+    M: Serialize,
+{
+    let temp_path = path.with_extension(TEMP_EXTENSION);
+    let mut file = fs::File::create(&temp_path).await?;
+    file.write_all(&serde_json::to_vec(entry)?).await?;
+    file.sync_all().await?;
+    fs::rename(&temp_path, path).await?;
+    match path.parent() {
+        Some(folder) => sync_folder(folder).await,
+        None => Ok(()),
+    }
+}
+
+async fn read_entry<M>(path: &Path) -> Result<(OutboxEntry<Value>, M), OutboxError>
+where
+    M: DeserializeOwned,
+{
+    let entry: OutboxEntry<Value> = serde_json::from_slice(&fs::read(path).await?)?;
+    let message = serde_json::from_value(entry.message.clone())?;
+    Ok((entry, message))
+}
+
+#[cfg(unix)]
+async fn sync_folder(folder: &Path) -> Result<(), OutboxError> {
+    fs::File::open(folder).await?.sync_all().await?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn sync_folder(_folder: &Path) -> Result<(), OutboxError> {
+    Ok(())
+}
+
+fn enqueued_at(path: &Path) -> Option<u64> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.split('-').next())
+        .and_then(|millis| millis.parse::<u64>().ok())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,228 @@
+use crate::{BatchNotification, BatchPushError, Notification};
+use async_trait::async_trait;
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::time::Instant;
+use typed_builder::TypedBuilder;
+
+pub trait RetryableError {
+    fn is_retryable(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct RetryOptions {
+    #[builder(default = 3)]
+    pub max_retries: u32,
+    #[builder(default = Duration::from_millis(200))]
+    pub initial_backoff: Duration,
+    #[builder(default = Duration::from_secs(10))]
+    pub max_backoff: Duration,
+    #[builder(default = 2.0)]
+    pub backoff_multiplier: f64,
+    #[builder(default, setter(strip_option))]
+    pub timeout: Option<Duration>,
+    #[builder(default, setter(strip_option))]
+    pub circuit_breaker_threshold: Option<u32>,
+    #[builder(default = Duration::from_secs(30))]
+    pub circuit_breaker_cooldown: Duration,
+}
+
+#[derive(Debug, TypedBuilder)]
+pub struct RetryingNotification<N> {
+    inner: N,
+    #[builder(default)]
+    options: RetryOptions,
+    #[builder(default, setter(skip))]
+    breaker: Mutex<CircuitBreaker>,
+}
+
+#[derive(Debug, Error)]
+pub enum RetryingNotificationError<E> {
+    #[error("{0}")]
+    PushError(E),
+    #[error("notification push timed out after {0:?}")]
+    TimeoutError(Duration),
+    #[error("notification circuit breaker is open")]
+    CircuitOpenError,
+}
+
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+#[async_trait]
+impl<M, N> Notification<M> for RetryingNotification<N>
+where
+    M: Clone + Send + Sync + 'static,
+    N: Notification<M>,
+    N::Error: RetryableError + Send,
+{
+    type Error = RetryingNotificationError<N::Error>;
+
+    async fn push(&self, message: M) -> Result<(), Self::Error> {
+        let mut attempt = 0;
+        loop {
+            if self.is_circuit_open() {
+                return Err(RetryingNotificationError::CircuitOpenError);
+            }
+            let error = match self.push_once(message.clone()).await {
+                Ok(()) => {
+                    self.record_success();
+                    return Ok(());
+                }
+                Err(error) => error,
+            };
+            self.record_failure();
+            if !error.is_retryable() || attempt >= self.options.max_retries {
+                return Err(error);
+            }
+            tokio::time::sleep(self.backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl<M, N> BatchNotification<M> for RetryingNotification<N>
+where
+    M: Clone + Send + Sync + 'static,
+    N: BatchNotification<M>,
+    N::Error: RetryableError + Send,
+{
+    // every attempt resends only the messages the previous one left undelivered.
+    async fn push_batch(&self, messages: Vec<M>) -> Result<(), BatchPushError<M, Self::Error>> {
+        let mut messages = messages;
+        let mut attempt = 0;
+        loop {
+            if self.is_circuit_open() {
+                return Err(BatchPushError::new(
+                    RetryingNotificationError::CircuitOpenError,
+                    messages,
+                ));
+            }
+            let error = match self.push_batch_once(messages).await {
+                Ok(()) => {
+                    self.record_success();
+                    return Ok(());
+                }
+                Err(error) => error,
+            };
+            self.record_failure();
+            if !error.error.is_retryable() || attempt >= self.options.max_retries {
+                return Err(error);
+            }
+            messages = error.undelivered;
+            tokio::time::sleep(self.backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+impl<N> RetryingNotification<N> {
+    pub fn new(inner: N, options: RetryOptions) -> Self {
+        Self::builder().inner(inner).options(options).build()
+    }
+
+    pub fn inner(&self) -> &N {
+        &self.inner
+    }
+
+    pub fn is_circuit_open(&self) -> bool {
+        let breaker = self.breaker.lock().unwrap();
+        breaker
+            .open_until
+            .map(|open_until| Instant::now() < open_until)
+            .unwrap_or(false)
+    }
+
+    async fn push_once<M>(&self, message: M) -> Result<(), RetryingNotificationError<<N as Notification<M>>::Error>>
+    where
+        N: Notification<M>,
+    {
+        match self.options.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, self.inner.push(message)).await {
+                Ok(result) => result.map_err(RetryingNotificationError::PushError),
+                Err(_) => Err(RetryingNotificationError::TimeoutError(timeout)),
+            },
+            None => self
+                .inner
+                .push(message)
+                .await
+                .map_err(RetryingNotificationError::PushError),
+        }
+    }
+
+    async fn push_batch_once<M>(
+        &self,
+        messages: Vec<M>,
+    ) -> Result<(), BatchPushError<M, RetryingNotificationError<<N as Notification<M>>::Error>>>
+    where
+        M: Clone + Send + 'static,
+        N: BatchNotification<M>,
+    {
+        let result = match self.options.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, self.inner.push_batch(messages.clone())).await {
+                Ok(result) => result,
+                Err(_) => {
+                    return Err(BatchPushError::new(
+                        RetryingNotificationError::TimeoutError(timeout),
+                        messages,
+                    ))
+                }
+            },
+            None => self.inner.push_batch(messages).await,
+        };
+        result
+            .map_err(|error| BatchPushError::new(RetryingNotificationError::PushError(error.error), error.undelivered))
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        // a negative or NaN multiplier would make the duration invalid, so it never shrinks the backoff.
+        let factor = self.options.backoff_multiplier.max(1.0).powi(attempt as i32);
+        let backoff = self.options.initial_backoff.as_secs_f64() * factor;
+        Duration::from_secs_f64(backoff.min(self.options.max_backoff.as_secs_f64()))
+    }
+
+    fn record_success(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures = 0;
+        breaker.open_until = None;
+    }
+
+    fn record_failure(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures += 1;
+        if let Some(threshold) = self.options.circuit_breaker_threshold {
+            if breaker.consecutive_failures >= threshold {
+                breaker.open_until = Some(Instant::now() + self.options.circuit_breaker_cooldown);
+            }
+        }
+    }
+}
+
+impl Default for RetryOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl<E> RetryableError for RetryingNotificationError<E>
+where
+    E: RetryableError,
+{
+    fn is_retryable(&self) -> bool {
+        match self {
+            RetryingNotificationError::PushError(error) => error.is_retryable(),
+            RetryingNotificationError::TimeoutError(_) => true,
+            RetryingNotificationError::CircuitOpenError => false,
+        }
+    }
+}
+
+impl RetryableError for anyhow::Error {}
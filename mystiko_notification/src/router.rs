@@ -0,0 +1,254 @@
+use crate::{BatchNotification, Notification};
+use async_trait::async_trait;
+use futures::future::join_all;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use typed_builder::TypedBuilder;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum NotificationSeverity {
+    Debug,
+    #[default]
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+pub trait RoutableMessage {
+    fn severity(&self) -> NotificationSeverity {
+        NotificationSeverity::default()
+    }
+
+    fn topic(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum DispatchMode {
+    FailFast,
+    #[default]
+    BestEffort,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct NotificationFilter {
+    #[builder(default, setter(strip_option))]
+    pub min_severity: Option<NotificationSeverity>,
+    #[builder(default)]
+    pub topics: Vec<String>,
+}
+
+pub struct NotificationRoute<M> {
+    name: String,
+    filter: NotificationFilter,
+    backend: Box<dyn Notification<M, Error = anyhow::Error>>,
+}
+
+#[derive(TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct NotificationRouter<M> {
+    #[builder(default)]
+    routes: Vec<NotificationRoute<M>>,
+    #[builder(default)]
+    mode: DispatchMode,
+}
+
+#[derive(Debug, Error)]
+#[error("notification route {route} failed: {error}")]
+pub struct NotificationRouteFailure {
+    pub route: String,
+    pub error: anyhow::Error,
+}
+
+#[derive(Debug, Error)]
+#[error("{}", format_failures(.failures))]
+pub struct NotificationRouterError {
+    pub failures: Vec<NotificationRouteFailure>,
+}
+
+struct AnyhowNotification<N> {
+    inner: N,
+}
+
+#[async_trait]
+impl<M, N> Notification<M> for AnyhowNotification<N>
+where
+    M: Send + 'static,
+    N: Notification<M>,
+    N::Error: Into<anyhow::Error>,
+{
+    type Error = anyhow::Error;
+
+    async fn push(&self, message: M) -> Result<(), Self::Error> {
+        self.inner.push(message).await.map_err(Into::into)
+    }
+}
+
+impl<M> NotificationRoute<M>
+where
+    M: Send + 'static,
+{
+    pub fn new<S, N>(name: S, backend: N) -> Self
+    where
+        S: Into<String>,
+        N: Notification<M> + 'static,
+        N::Error: Into<anyhow::Error>,
+    {
+        Self {
+            name: name.into(),
+            filter: NotificationFilter::default(),
+            backend: Box::new(AnyhowNotification { inner: backend }),
+        }
+    }
+
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Into<NotificationFilter>,
+    {
+        self.filter = filter.into();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn filter(&self) -> &NotificationFilter {
+        &self.filter
+    }
+}
+
+impl<M> NotificationRouter<M> {
+    pub fn add_route(&mut self, route: NotificationRoute<M>) {
+        self.routes.push(route);
+    }
+
+    pub fn route(mut self, route: NotificationRoute<M>) -> Self {
+        self.add_route(route);
+        self
+    }
+
+    pub fn routes(&self) -> &[NotificationRoute<M>] {
+        &self.routes
+    }
+
+    pub fn mode(&self) -> &DispatchMode {
+        &self.mode
+    }
+}
+
+#[async_trait]
+impl<M> Notification<M> for NotificationRouter<M>
+where
+    M: RoutableMessage + Clone + Send + Sync + 'static,
+{
+    type Error = NotificationRouterError;
+
+    async fn push(&self, message: M) -> Result<(), Self::Error> {
+        let routes = self
+            .routes
+            .iter()
+            .filter(|route| route.filter.matches(&message))
+            .collect::<Vec<_>>();
+        let mut failures = vec![];
+        match self.mode {
+            DispatchMode::FailFast => {
+                for route in routes {
+                    if let Err(error) = route.backend.push(message.clone()).await {
+                        failures.push(NotificationRouteFailure {
+                            route: route.name.clone(),
+                            error,
+                        });
+                        break;
+                    }
+                }
+            }
+            DispatchMode::BestEffort => {
+                let results = join_all(routes.iter().map(|route| route.backend.push(message.clone()))).await;
+                for (route, result) in routes.into_iter().zip(results) {
+                    if let Err(error) = result {
+                        failures.push(NotificationRouteFailure {
+                            route: route.name.clone(),
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(NotificationRouterError { failures })
+        }
+    }
+}
+
+impl<M> BatchNotification<M> for NotificationRouter<M> where M: RoutableMessage + Clone + Send + Sync + 'static {}
+
+impl NotificationFilter {
+    pub fn matches<M>(&self, message: &M) -> bool
+    where
+        M: RoutableMessage,
+    {
+        if let Some(min_severity) = self.min_severity {
+            if message.severity() < min_severity {
+                return false;
+            }
+        }
+        if self.topics.is_empty() {
+            return true;
+        }
+        message
+            .topic()
+            .map(|topic| self.topics.iter().any(|t| t == topic))
+            .unwrap_or(false)
+    }
+}
+
+impl RoutableMessage for String {}
+
+impl Display for NotificationSeverity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            NotificationSeverity::Debug => "debug",
+            NotificationSeverity::Info => "info",
+            NotificationSeverity::Warning => "warning",
+            NotificationSeverity::Error => "error",
+            NotificationSeverity::Critical => "critical",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for NotificationSeverity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "debug" => Ok(NotificationSeverity::Debug),
+            "info" => Ok(NotificationSeverity::Info),
+            "warn" | "warning" => Ok(NotificationSeverity::Warning),
+            "error" => Ok(NotificationSeverity::Error),
+            "critical" => Ok(NotificationSeverity::Critical),
+            _ => Err(anyhow::anyhow!("unsupported notification severity {}", s)),
+        }
+    }
+}
+
+impl NotificationRouterError {
+    pub fn routes(&self) -> Vec<&str> {
+        self.failures.iter().map(|f| f.route.as_str()).collect()
+    }
+}
+
+fn format_failures(failures: &[NotificationRouteFailure]) -> String {
+    failures.iter().map(|f| f.to_string()).collect::<Vec<_>>().join("; ")
+}
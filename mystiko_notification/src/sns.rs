@@ -1,7 +1,12 @@
-use crate::Notification;
+use crate::{
+    BatchNotification, DedupKey, Notification, NotificationMessage, NotificationSeverity, RetryableError,
+    RoutableMessage,
+};
 use async_trait::async_trait;
 use rusoto_core::{Region, RusotoError};
-use rusoto_sns::{PublishError, PublishInput, Sns, SnsClient};
+use rusoto_sns::{MessageAttributeValue, PublishError, PublishInput, Sns, SnsClient};
+use std::collections::HashMap;
+use std::str::FromStr;
 use thiserror::Error;
 use typed_builder::TypedBuilder;
 
@@ -9,6 +14,8 @@ use typed_builder::TypedBuilder;
 #[builder(field_defaults(setter(into)))]
 pub struct SnsNotification<S: Sns = SnsClient> {
     client: S,
+    #[builder(default, setter(strip_option))]
+    topic_arn: Option<String>,
 }
 
 #[derive(Debug, Error)]
@@ -24,15 +31,54 @@ where
 {
     type Error = SnsNotificationError;
 
-    async fn push(&self, message: PublishInput) -> Result<(), Self::Error> {
+    async fn push(&self, mut message: PublishInput) -> Result<(), Self::Error> {
+        if message.topic_arn.is_none() && message.target_arn.is_none() && message.phone_number.is_none() {
+            message.topic_arn = self.topic_arn.clone();
+        }
         self.client.publish(message).await?;
         Ok(())
     }
 }
 
+#[async_trait]
+impl<S> Notification<NotificationMessage> for SnsNotification<S>
+where
+    S: Sns + Send + Sync,
+{
+    type Error = SnsNotificationError;
+
+    async fn push(&self, message: NotificationMessage) -> Result<(), Self::Error> {
+        Notification::<PublishInput>::push(self, message.into()).await
+    }
+}
+
+impl<S> BatchNotification<PublishInput> for SnsNotification<S> where S: Sns + Send + Sync {}
+
+impl<S> BatchNotification<NotificationMessage> for SnsNotification<S> where S: Sns + Send + Sync {}
+
+impl<S> SnsNotification<S>
+where
+    S: Sns,
+{
+    pub fn with_topic_arn<T>(mut self, topic_arn: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.topic_arn = Some(topic_arn.into());
+        self
+    }
+
+    pub fn topic_arn(&self) -> Option<&str> {
+        self.topic_arn.as_deref()
+    }
+}
+
 impl SnsNotification<SnsClient> {
     pub fn new(client: SnsClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            topic_arn: None,
+        }
     }
 
     pub fn from_region<R>(region: R) -> Self
@@ -42,3 +88,89 @@ impl SnsNotification<SnsClient> {
         Self::new(SnsClient::new(region.into()))
     }
 }
+
+impl RoutableMessage for PublishInput {
+    fn severity(&self) -> NotificationSeverity {
+        self.message_attributes
+            .as_ref()
+            .and_then(|attributes| attributes.get("severity"))
+            .and_then(|attribute| attribute.string_value.as_ref())
+            .and_then(|severity| NotificationSeverity::from_str(severity).ok())
+            .unwrap_or_default()
+    }
+
+    // the topic attribute set by the NotificationMessage conversion takes precedence over the arn.
+    fn topic(&self) -> Option<&str> {
+        self.message_attributes
+            .as_ref()
+            .and_then(|attributes| attributes.get("topic"))
+            .and_then(|attribute| attribute.string_value.as_deref())
+            .or(self.topic_arn.as_deref())
+    }
+}
+
+impl DedupKey for PublishInput {
+    fn dedup_key(&self) -> String {
+        match &self.message_deduplication_id {
+            Some(deduplication_id) => deduplication_id.clone(),
+            None => format!(
+                "{}\n{}\n{}",
+                self.topic_arn.as_deref().unwrap_or_default(),
+                self.subject.as_deref().unwrap_or_default(),
+                self.message
+            ),
+        }
+    }
+}
+
+impl From<NotificationMessage> for PublishInput {
+    fn from(message: NotificationMessage) -> Self {
+        let mut attributes = message
+            .attributes
+            .into_iter()
+            .map(|(name, value)| (name, string_attribute("String", value)))
+            .collect::<HashMap<_, _>>();
+        attributes.insert(
+            "severity".to_string(),
+            string_attribute("String", message.severity.to_string()),
+        );
+        if let Some(topic) = message.topic {
+            attributes.insert("topic".to_string(), string_attribute("String", topic));
+        }
+        if !message.tags.is_empty() {
+            let tags = serde_json::to_string(&message.tags).unwrap_or_default();
+            attributes.insert("tags".to_string(), string_attribute("String.Array", tags));
+        }
+        PublishInput {
+            message: message.body,
+            subject: message.title,
+            message_attributes: Some(attributes),
+            message_deduplication_id: message.dedup_id,
+            message_group_id: message.group_id,
+            ..Default::default()
+        }
+    }
+}
+
+impl RetryableError for SnsNotificationError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            SnsNotificationError::PushError(RusotoError::Service(error)) => {
+                matches!(error, PublishError::InternalError(_) | PublishError::KMSThrottling(_))
+            }
+            SnsNotificationError::PushError(RusotoError::HttpDispatch(_)) => true,
+            SnsNotificationError::PushError(RusotoError::Unknown(response)) => {
+                response.status.is_server_error() || response.status.as_u16() == 429
+            }
+            _ => false,
+        }
+    }
+}
+
+fn string_attribute(data_type: &str, value: String) -> MessageAttributeValue {
+    MessageAttributeValue {
+        data_type: data_type.to_string(),
+        string_value: Some(value),
+        ..Default::default()
+    }
+}
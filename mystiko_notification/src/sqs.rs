@@ -0,0 +1,173 @@
+use crate::{
+    BatchNotification, DedupKey, Notification, NotificationMessage, NotificationSeverity, RetryableError,
+    RoutableMessage,
+};
+use async_trait::async_trait;
+use rusoto_core::{Region, RusotoError};
+use rusoto_sqs::{MessageAttributeValue, SendMessageError, SendMessageRequest, Sqs, SqsClient};
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+use typed_builder::TypedBuilder;
+
+#[derive(Debug, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct SqsNotification<S: Sqs = SqsClient> {
+    client: S,
+    #[builder(default, setter(strip_option))]
+    queue_url: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum SqsNotificationError {
+    #[error(transparent)]
+    PushError(#[from] RusotoError<SendMessageError>),
+    #[error("sqs queue url is not specified")]
+    MissingQueueUrlError,
+}
+
+#[async_trait]
+impl<S> Notification<SendMessageRequest> for SqsNotification<S>
+where
+    S: Sqs + Send + Sync,
+{
+    type Error = SqsNotificationError;
+
+    async fn push(&self, mut message: SendMessageRequest) -> Result<(), Self::Error> {
+        if message.queue_url.is_empty() {
+            message.queue_url = self
+                .queue_url
+                .clone()
+                .ok_or(SqsNotificationError::MissingQueueUrlError)?;
+        }
+        self.client.send_message(message).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> Notification<NotificationMessage> for SqsNotification<S>
+where
+    S: Sqs + Send + Sync,
+{
+    type Error = SqsNotificationError;
+
+    async fn push(&self, message: NotificationMessage) -> Result<(), Self::Error> {
+        Notification::<SendMessageRequest>::push(self, message.into()).await
+    }
+}
+
+impl<S> BatchNotification<SendMessageRequest> for SqsNotification<S> where S: Sqs + Send + Sync {}
+
+impl<S> BatchNotification<NotificationMessage> for SqsNotification<S> where S: Sqs + Send + Sync {}
+
+impl<S> SqsNotification<S>
+where
+    S: Sqs,
+{
+    pub fn with_queue_url<U>(mut self, queue_url: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.queue_url = Some(queue_url.into());
+        self
+    }
+
+    pub fn queue_url(&self) -> Option<&str> {
+        self.queue_url.as_deref()
+    }
+}
+
+impl SqsNotification<SqsClient> {
+    pub fn new(client: SqsClient) -> Self {
+        Self {
+            client,
+            queue_url: None,
+        }
+    }
+
+    pub fn from_region<R>(region: R) -> Self
+    where
+        R: Into<Region>,
+    {
+        Self::new(SqsClient::new(region.into()))
+    }
+}
+
+impl RoutableMessage for SendMessageRequest {
+    fn severity(&self) -> NotificationSeverity {
+        self.message_attributes
+            .as_ref()
+            .and_then(|attributes| attributes.get("severity"))
+            .and_then(|attribute| attribute.string_value.as_ref())
+            .and_then(|severity| NotificationSeverity::from_str(severity).ok())
+            .unwrap_or_default()
+    }
+
+    fn topic(&self) -> Option<&str> {
+        self.message_attributes
+            .as_ref()
+            .and_then(|attributes| attributes.get("topic"))
+            .and_then(|attribute| attribute.string_value.as_deref())
+    }
+}
+
+impl DedupKey for SendMessageRequest {
+    fn dedup_key(&self) -> String {
+        match &self.message_deduplication_id {
+            Some(deduplication_id) => deduplication_id.clone(),
+            None => format!("{}\n{}", self.queue_url, self.message_body),
+        }
+    }
+}
+
+impl From<NotificationMessage> for SendMessageRequest {
+    fn from(message: NotificationMessage) -> Self {
+        let mut attributes = message
+            .attributes
+            .into_iter()
+            .map(|(name, value)| (name, string_attribute("String", value)))
+            .collect::<HashMap<_, _>>();
+        attributes.insert(
+            "severity".to_string(),
+            string_attribute("String", message.severity.to_string()),
+        );
+        if let Some(title) = message.title {
+            attributes.insert("title".to_string(), string_attribute("String", title));
+        }
+        if let Some(topic) = message.topic {
+            attributes.insert("topic".to_string(), string_attribute("String", topic));
+        }
+        if !message.tags.is_empty() {
+            let tags = serde_json::to_string(&message.tags).unwrap_or_default();
+            attributes.insert("tags".to_string(), string_attribute("String.Array", tags));
+        }
+        SendMessageRequest {
+            message_body: message.body,
+            message_attributes: Some(attributes),
+            message_deduplication_id: message.dedup_id,
+            message_group_id: message.group_id,
+            ..Default::default()
+        }
+    }
+}
+
+impl RetryableError for SqsNotificationError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            SqsNotificationError::PushError(RusotoError::HttpDispatch(_)) => true,
+            SqsNotificationError::PushError(RusotoError::Unknown(response)) => {
+                response.status.is_server_error() || response.status.as_u16() == 429
+            }
+            _ => false,
+        }
+    }
+}
+
+fn string_attribute(data_type: &str, value: String) -> MessageAttributeValue {
+    MessageAttributeValue {
+        data_type: data_type.to_string(),
+        string_value: Some(value),
+        ..Default::default()
+    }
+}
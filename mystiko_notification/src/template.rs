@@ -0,0 +1,223 @@
+use crate::{NotificationMessage, NotificationSeverity};
+use std::collections::HashMap;
+use thiserror::Error;
+use typed_builder::TypedBuilder;
+
+const DEFAULT_LOCALE: &str = "en";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TemplateFormat {
+    #[default]
+    Text,
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct NotificationTemplate {
+    #[builder(default, setter(strip_option))]
+    pub title: Option<String>,
+    pub text: String,
+    #[builder(default, setter(strip_option))]
+    pub markdown: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub html: Option<String>,
+    #[builder(default)]
+    pub severity: NotificationSeverity,
+    #[builder(default, setter(strip_option))]
+    pub topic: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderedTemplate {
+    pub title: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TemplateRegistry {
+    default_locale: String,
+    templates: HashMap<(String, String), NotificationTemplate>,
+}
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("notification template {0} is not registered for locale {1}")]
+    TemplateNotFound(String, String),
+    #[error("notification template variable {0} is missing")]
+    MissingVariable(String),
+}
+
+impl TemplateRegistry {
+    pub fn new<L>(default_locale: L) -> Self
+    where
+        L: AsRef<str>,
+    {
+        Self {
+            default_locale: normalize_locale(default_locale.as_ref()),
+            templates: HashMap::new(),
+        }
+    }
+
+    pub fn add_template<N, L>(&mut self, name: N, locale: L, template: NotificationTemplate)
+    where
+        N: Into<String>,
+        L: AsRef<str>,
+    {
+        self.templates
+            .insert((name.into(), normalize_locale(locale.as_ref())), template);
+    }
+
+    pub fn template<N, L>(mut self, name: N, locale: L, template: NotificationTemplate) -> Self
+    where
+        N: Into<String>,
+        L: AsRef<str>,
+    {
+        self.add_template(name, locale, template);
+        self
+    }
+
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    pub fn get(&self, name: &str, locale: &str) -> Option<&NotificationTemplate> {
+        fallback_locales(&normalize_locale(locale), &self.default_locale)
+            .into_iter()
+            .find_map(|locale| self.templates.get(&(name.to_string(), locale)))
+    }
+
+    pub fn render(
+        &self,
+        name: &str,
+        locale: &str,
+        variables: &HashMap<String, String>,
+        format: TemplateFormat,
+    ) -> Result<RenderedTemplate, TemplateError> {
+        render_with(self.find(name, locale)?, variables, format)
+    }
+
+    pub fn render_message(
+        &self,
+        name: &str,
+        locale: &str,
+        variables: &HashMap<String, String>,
+        format: TemplateFormat,
+    ) -> Result<NotificationMessage, TemplateError> {
+        let template = self.find(name, locale)?;
+        let rendered = render_with(template, variables, format)?;
+        Ok(NotificationMessage {
+            title: rendered.title,
+            body: rendered.body,
+            severity: template.severity,
+            topic: template.topic.clone(),
+            attributes: variables.clone(),
+            ..Default::default()
+        })
+    }
+
+    fn find(&self, name: &str, locale: &str) -> Result<&NotificationTemplate, TemplateError> {
+        self.get(name, locale)
+            .ok_or_else(|| TemplateError::TemplateNotFound(name.to_string(), locale.to_string()))
+    }
+}
+
+impl Default for TemplateRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCALE)
+    }
+}
+
+pub fn render_template<F>(template: &str, mut lookup: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start + 2..].find("}}") {
+            Some(end) => end,
+            None => break,
+        };
+        let placeholder = &rest[start..start + 2 + end + 2];
+        rendered.push_str(&rest[..start]);
+        match lookup(placeholder[2..placeholder.len() - 2].trim()) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(placeholder),
+        }
+        rest = &rest[start + placeholder.len()..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+pub fn escape_for_format(value: &str, format: TemplateFormat) -> String {
+    match format {
+        TemplateFormat::Text => value.to_string(),
+        // slack mrkdwn has no backslash escapes, only these three characters are encoded.
+        TemplateFormat::Markdown => value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;"),
+        TemplateFormat::Html => value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;"),
+    }
+}
+
+fn render_with(
+    template: &NotificationTemplate,
+    variables: &HashMap<String, String>,
+    format: TemplateFormat,
+) -> Result<RenderedTemplate, TemplateError> {
+    let title = template
+        .title
+        .as_ref()
+        .map(|title| render_strict(title, variables, TemplateFormat::Text))
+        .transpose()?;
+    let specific = match format {
+        TemplateFormat::Text => None,
+        TemplateFormat::Markdown => template.markdown.as_ref(),
+        TemplateFormat::Html => template.html.as_ref(),
+    };
+    let body = match specific {
+        Some(body) => render_strict(body, variables, format)?,
+        None => escape_for_format(&render_strict(&template.text, variables, TemplateFormat::Text)?, format),
+    };
+    Ok(RenderedTemplate { title, body })
+}
+
+fn render_strict(
+    template: &str,
+    variables: &HashMap<String, String>,
+    format: TemplateFormat,
+) -> Result<String, TemplateError> {
+    let mut missing = None;
+    let rendered = render_template(template, |name| match variables.get(name) {
+        Some(value) => Some(escape_for_format(value, format)),
+        None => {
+            missing.get_or_insert_with(|| name.to_string());
+            None
+        }
+    });
+    match missing {
+        Some(name) => Err(TemplateError::MissingVariable(name)),
+        None => Ok(rendered),
+    }
+}
+
+fn normalize_locale(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_lowercase()
+}
+
+fn fallback_locales(locale: &str, default_locale: &str) -> Vec<String> {
+    let mut locales = vec![locale.to_string()];
+    if let Some((language, _)) = locale.split_once('-') {
+        locales.push(language.to_string());
+    }
+    locales.push(default_locale.to_string());
+    if let Some((language, _)) = default_locale.split_once('-') {
+        locales.push(language.to_string());
+    }
+    locales
+}
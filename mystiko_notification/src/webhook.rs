@@ -0,0 +1,195 @@
+use crate::{
+    escape_for_format, render_template, BatchNotification, DedupKey, Notification, NotificationMessage, RetryableError,
+    RoutableMessage, TemplateFormat,
+};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+use typed_builder::TypedBuilder;
+
+const DISCORD_MAX_CONTENT_LENGTH: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct WebhookMessage {
+    #[builder(default, setter(strip_option))]
+    pub title: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum WebhookFormat {
+    #[default]
+    Slack,
+    Discord,
+    Telegram {
+        chat_id: String,
+    },
+    Template(String),
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct WebhookNotification {
+    #[builder(default)]
+    client: Client,
+    url: String,
+    #[builder(default)]
+    format: WebhookFormat,
+    #[builder(default)]
+    headers: HashMap<String, String>,
+}
+
+#[derive(Debug, Error)]
+pub enum WebhookNotificationError {
+    #[error(transparent)]
+    RequestError(#[from] reqwest::Error),
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+    #[error("invalid webhook header {0}")]
+    HeaderError(String),
+    #[error("webhook responded with status {0}: {1}")]
+    ResponseError(u16, String),
+}
+
+#[async_trait]
+impl Notification<WebhookMessage> for WebhookNotification {
+    type Error = WebhookNotificationError;
+
+    async fn push(&self, message: WebhookMessage) -> Result<(), Self::Error> {
+        let body = self.format.format(&message)?;
+        let response = self
+            .client
+            .post(&self.url)
+            .headers(self.header_map()?)
+            .json(&body)
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let text = response.text().await.unwrap_or_default();
+            Err(WebhookNotificationError::ResponseError(status.as_u16(), text))
+        }
+    }
+}
+
+#[async_trait]
+impl Notification<NotificationMessage> for WebhookNotification {
+    type Error = WebhookNotificationError;
+
+    async fn push(&self, message: NotificationMessage) -> Result<(), Self::Error> {
+        Notification::<WebhookMessage>::push(self, message.into()).await
+    }
+}
+
+impl BatchNotification<WebhookMessage> for WebhookNotification {}
+
+impl BatchNotification<NotificationMessage> for WebhookNotification {}
+
+impl WebhookNotification {
+    pub fn new<U>(url: U, format: WebhookFormat) -> Self
+    where
+        U: Into<String>,
+    {
+        Self::builder().url(url).format(format).build()
+    }
+
+    fn header_map(&self) -> Result<HeaderMap, WebhookNotificationError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers.iter() {
+            let name =
+                HeaderName::from_str(name).map_err(|_| WebhookNotificationError::HeaderError(name.to_string()))?;
+            let value =
+                HeaderValue::from_str(value).map_err(|_| WebhookNotificationError::HeaderError(name.to_string()))?;
+            headers.insert(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+impl WebhookFormat {
+    pub fn format(&self, message: &WebhookMessage) -> Result<Value, WebhookNotificationError> {
+        let body = match self {
+            WebhookFormat::Slack => {
+                let text = match &message.title {
+                    Some(title) => format!("*{}*\n{}", title, message.text),
+                    None => message.text.clone(),
+                };
+                json!({ "text": text })
+            }
+            WebhookFormat::Discord => {
+                let content = match &message.title {
+                    Some(title) => format!("**{}**\n{}", title, message.text),
+                    None => message.text.clone(),
+                };
+                json!({ "content": content.chars().take(DISCORD_MAX_CONTENT_LENGTH).collect::<String>() })
+            }
+            WebhookFormat::Telegram { chat_id } => {
+                let text = match &message.title {
+                    Some(title) => format!(
+                        "<b>{}</b>\n{}",
+                        escape_for_format(title, TemplateFormat::Html),
+                        escape_for_format(&message.text, TemplateFormat::Html)
+                    ),
+                    None => escape_for_format(&message.text, TemplateFormat::Html),
+                };
+                json!({ "chat_id": chat_id, "text": text, "parse_mode": "HTML" })
+            }
+            WebhookFormat::Template(template) => {
+                let title = escape_json(message.title.as_deref().unwrap_or_default())?;
+                let text = escape_json(&message.text)?;
+                let rendered = render_template(template, |name| match name {
+                    "title" => Some(title.clone()),
+                    "text" => Some(text.clone()),
+                    _ => None,
+                });
+                serde_json::from_str(&rendered)?
+            }
+        };
+        Ok(body)
+    }
+}
+
+impl RoutableMessage for WebhookMessage {}
+
+impl DedupKey for WebhookMessage {
+    fn dedup_key(&self) -> String {
+        format!("{}\n{}", self.title.as_deref().unwrap_or_default(), self.text)
+    }
+}
+
+impl RetryableError for WebhookNotificationError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            WebhookNotificationError::RequestError(error) => error.is_timeout() || error.is_connect(),
+            WebhookNotificationError::ResponseError(status, _) => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl From<String> for WebhookMessage {
+    fn from(text: String) -> Self {
+        Self::builder().text(text).build()
+    }
+}
+
+impl From<NotificationMessage> for WebhookMessage {
+    fn from(message: NotificationMessage) -> Self {
+        Self {
+            title: message.title,
+            text: message.body,
+        }
+    }
+}
+
+fn escape_json(value: &str) -> Result<String, WebhookNotificationError> {
+    let quoted = serde_json::to_string(value)?;
+    Ok(quoted[1..quoted.len() - 1].to_string())
+}
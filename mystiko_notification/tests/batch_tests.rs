@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use mockall::{mock, Sequence};
+use mystiko_notification::{BatchNotification, BatchOptions, BatchPushError, BatchingNotification, Notification};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+mock! {
+    pub Backend {}
+
+    #[async_trait]
+    impl Notification<String> for Backend {
+        type Error = anyhow::Error;
+
+        async fn push(&self, message: String) -> Result<(), anyhow::Error>;
+    }
+
+    #[async_trait]
+    impl BatchNotification<String> for Backend {
+        async fn push_batch(&self, messages: Vec<String>) -> Result<(), BatchPushError<String, anyhow::Error>>;
+    }
+}
+
+type Batches = Arc<Mutex<Vec<(Instant, Vec<String>)>>>;
+
+#[derive(Default)]
+struct RecordingBackend {
+    batches: Batches,
+}
+
+#[async_trait]
+impl Notification<String> for RecordingBackend {
+    type Error = anyhow::Error;
+
+    async fn push(&self, message: String) -> Result<(), Self::Error> {
+        self.batches.lock().unwrap().push((Instant::now(), vec![message]));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BatchNotification<String> for RecordingBackend {
+    async fn push_batch(&self, messages: Vec<String>) -> Result<(), BatchPushError<String, Self::Error>> {
+        self.batches.lock().unwrap().push((Instant::now(), messages));
+        Ok(())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_coalesce() {
+    let mut backend = MockBackend::new();
+    backend
+        .expect_push_batch()
+        .withf(|messages| messages == &vec!["low balance".to_string(), "stuck tx".to_string()])
+        .times(1)
+        .returning(|_| Ok(()));
+    let options = BatchOptions::builder().window(Duration::from_secs(5)).build();
+    let notification = BatchingNotification::new(backend, options);
+    notification.push("low balance".to_string()).await.unwrap();
+    notification.push("stuck tx".to_string()).await.unwrap();
+    notification.push("low balance".to_string()).await.unwrap();
+    assert_eq!(notification.buffered(), 2);
+    assert_eq!(notification.coalesced(), 1);
+    tokio::time::sleep(Duration::from_secs(6)).await;
+    assert_eq!(notification.buffered(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_max_batch_size() {
+    let backend = RecordingBackend::default();
+    let batches = backend.batches.clone();
+    let options = BatchOptions::builder()
+        .window(Duration::from_secs(1))
+        .max_batch_size(2_usize)
+        .build();
+    let notification = BatchingNotification::new(backend, options);
+    for index in 0..5 {
+        notification.push(format!("message {}", index)).await.unwrap();
+    }
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let batches = batches.lock().unwrap();
+    assert_eq!(batches.len(), 3);
+    assert_eq!(batches[0].1, vec!["message 0".to_string(), "message 1".to_string()]);
+    assert_eq!(batches[2].1, vec!["message 4".to_string()]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_rate_limit() {
+    let backend = RecordingBackend::default();
+    let batches = backend.batches.clone();
+    let options = BatchOptions::builder()
+        .window(Duration::from_secs(1))
+        .max_messages_per_second(2_u32)
+        .build();
+    let notification = BatchingNotification::new(backend, options);
+    let started = Instant::now();
+    for index in 0..6 {
+        notification.push(format!("message {}", index)).await.unwrap();
+    }
+    assert_eq!(notification.flush().await.unwrap(), 6);
+    let batches = batches.lock().unwrap();
+    assert_eq!(batches.len(), 3);
+    assert_eq!(batches[2].0 - started, Duration::from_secs(2));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_flush_error() {
+    let mut backend = MockBackend::new();
+    let mut seq = Sequence::new();
+    backend
+        .expect_push_batch()
+        .withf(|messages| messages == &vec!["first".to_string()])
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|messages| Err(BatchPushError::new(anyhow::anyhow!("failed"), messages)));
+    backend
+        .expect_push_batch()
+        .times(3)
+        .in_sequence(&mut seq)
+        .returning(|_| Ok(()));
+    let options = BatchOptions::builder()
+        .window(Duration::from_secs(60))
+        .max_batch_size(1_usize)
+        .build();
+    let notification = BatchingNotification::new(backend, options);
+    notification.push("first".to_string()).await.unwrap();
+    notification.push("second".to_string()).await.unwrap();
+    let error = notification.flush().await.unwrap_err();
+    assert_eq!(error.to_string(), "failed");
+    assert_eq!(notification.buffered(), 2);
+    notification.push("third".to_string()).await.unwrap();
+    assert_eq!(notification.buffered(), 3);
+    assert_eq!(notification.flush().await.unwrap(), 3);
+    assert_eq!(notification.buffered(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_flush_partial_failure() {
+    let mut backend = MockBackend::new();
+    let mut seq = Sequence::new();
+    backend
+        .expect_push_batch()
+        .withf(|messages| messages.len() == 3)
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|messages| Err(BatchPushError::new(anyhow::anyhow!("failed"), messages[1..2].to_vec())));
+    backend
+        .expect_push_batch()
+        .withf(|messages| messages == &vec!["second".to_string()])
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|_| Ok(()));
+    let options = BatchOptions::builder().window(Duration::from_secs(60)).build();
+    let notification = BatchingNotification::new(backend, options);
+    for message in ["first", "second", "third"] {
+        notification.push(message.to_string()).await.unwrap();
+    }
+    assert!(notification.flush().await.is_err());
+    assert_eq!(notification.buffered(), 1);
+    assert_eq!(notification.flush().await.unwrap(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_background_flush_retry() {
+    let mut backend = MockBackend::new();
+    let mut seq = Sequence::new();
+    backend
+        .expect_push_batch()
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|messages| Err(BatchPushError::new(anyhow::anyhow!("failed"), messages)));
+    backend
+        .expect_push_batch()
+        .withf(|messages| messages == &vec!["alert".to_string()])
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|_| Ok(()));
+    let options = BatchOptions::builder().window(Duration::from_secs(5)).build();
+    let notification = BatchingNotification::new(backend, options);
+    notification.push("alert".to_string()).await.unwrap();
+    tokio::time::sleep(Duration::from_secs(6)).await;
+    assert_eq!(notification.buffered(), 1);
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    assert_eq!(notification.buffered(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_shutdown() {
+    let backend = RecordingBackend::default();
+    let batches = backend.batches.clone();
+    let options = BatchOptions::builder().window(Duration::from_secs(60)).build();
+    let notification = BatchingNotification::new(backend, options);
+    notification.push("message 0".to_string()).await.unwrap();
+    notification.push("message 1".to_string()).await.unwrap();
+    assert_eq!(notification.shutdown().await.unwrap(), 2);
+    assert_eq!(notification.buffered(), 0);
+    notification.push("message 2".to_string()).await.unwrap();
+    assert_eq!(notification.buffered(), 0);
+    let batches = batches.lock().unwrap();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].1, vec!["message 0".to_string(), "message 1".to_string()]);
+    assert_eq!(batches[1].1, vec!["message 2".to_string()]);
+}
+
+#[test]
+fn test_default_options() {
+    let options = BatchOptions::default();
+    assert_eq!(options.window, Duration::from_secs(1));
+    assert_eq!(options.max_batch_size, 10);
+    assert!(options.max_messages_per_second.is_none());
+}
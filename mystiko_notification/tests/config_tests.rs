@@ -0,0 +1,114 @@
+use mystiko_notification::{
+    DispatchMode, Notification, NotificationBackendConfig, NotificationConfig, NotificationConfigError,
+    NotificationMessage, NotificationRetryConfig, NotificationRouteConfig, NotificationSeverity, WebhookFormatConfig,
+};
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn test_read_config() {
+    let config = NotificationConfig::new(Some(PathBuf::from("tests/files/config"))).unwrap();
+    assert_eq!(config.mode, DispatchMode::FailFast);
+    let retry = config.retry.as_ref().unwrap();
+    assert_eq!(retry.max_retries, 5);
+    assert_eq!(retry.initial_backoff_ms, 200);
+    assert_eq!(retry.timeout_ms, Some(3000));
+    assert_eq!(config.routes.len(), 3);
+    let oncall = &config.routes["oncall"];
+    assert!(oncall.enabled);
+    assert_eq!(oncall.min_severity, Some(NotificationSeverity::Critical));
+    assert_eq!(oncall.topics, vec!["relayer".to_string()]);
+    assert!(matches!(
+        &oncall.backend,
+        NotificationBackendConfig::Webhook {
+            format: WebhookFormatConfig::Telegram,
+            chat_id: Some(_),
+            ..
+        }
+    ));
+    assert!(!config.routes["audit"].enabled);
+
+    let router = config.create_router().unwrap();
+    let names = router.routes().iter().map(|route| route.name()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["oncall", "ops"]);
+    assert_eq!(router.mode(), &DispatchMode::FailFast);
+    assert_eq!(
+        router.routes()[1].filter().min_severity,
+        Some(NotificationSeverity::Warning)
+    );
+
+    let notification = config.create_notification().unwrap();
+    notification
+        .push(
+            NotificationMessage::builder()
+                .body("debug message")
+                .severity(NotificationSeverity::Debug)
+                .build(),
+        )
+        .await
+        .unwrap();
+}
+
+#[test]
+fn test_read_config_from_env() {
+    let config = NotificationConfig::new(None).unwrap();
+    assert_eq!(config, NotificationConfig::default());
+    assert!(config.create_router().unwrap().routes().is_empty());
+
+    env::set_var("MYSTIKO_NOTIFICATION.MODE", "fail_fast");
+    env::set_var("MYSTIKO_NOTIFICATION.ROUTES.OPS.MIN_SEVERITY", "error");
+    env::set_var("MYSTIKO_NOTIFICATION.ROUTES.OPS.BACKEND.TYPE", "webhook");
+    env::set_var(
+        "MYSTIKO_NOTIFICATION.ROUTES.OPS.BACKEND.URL",
+        "https://discord.com/api/webhooks/test",
+    );
+    env::set_var("MYSTIKO_NOTIFICATION.ROUTES.OPS.BACKEND.FORMAT", "discord");
+    let config = NotificationConfig::new(None).unwrap();
+    env::remove_var("MYSTIKO_NOTIFICATION.MODE");
+    env::remove_var("MYSTIKO_NOTIFICATION.ROUTES.OPS.MIN_SEVERITY");
+    env::remove_var("MYSTIKO_NOTIFICATION.ROUTES.OPS.BACKEND.TYPE");
+    env::remove_var("MYSTIKO_NOTIFICATION.ROUTES.OPS.BACKEND.URL");
+    env::remove_var("MYSTIKO_NOTIFICATION.ROUTES.OPS.BACKEND.FORMAT");
+    assert_eq!(config.mode, DispatchMode::FailFast);
+    assert_eq!(config.routes["ops"].min_severity, Some(NotificationSeverity::Error));
+    assert_eq!(config.create_router().unwrap().routes().len(), 1);
+}
+
+#[test]
+fn test_invalid_route() {
+    let mut routes = HashMap::new();
+    routes.insert(
+        "oncall".to_string(),
+        NotificationRouteConfig::builder()
+            .backend(NotificationBackendConfig::Webhook {
+                url: "https://api.telegram.org/bot-token/sendMessage".to_string(),
+                format: WebhookFormatConfig::Telegram,
+                chat_id: None,
+                template: None,
+                headers: HashMap::new(),
+            })
+            .build(),
+    );
+    let config = NotificationConfig::builder().routes(routes).build();
+    let error = config.create_router().err().unwrap();
+    assert!(matches!(error, NotificationConfigError::InvalidRouteError(_, _)));
+    assert_eq!(
+        error.to_string(),
+        "invalid notification route oncall: telegram chat_id is missing"
+    );
+}
+
+#[test]
+fn test_invalid_retry() {
+    for backoff_multiplier in [-2.0, 0.5, f64::NAN, f64::INFINITY] {
+        let retry = NotificationRetryConfig::builder()
+            .backoff_multiplier(backoff_multiplier)
+            .build();
+        let config = NotificationConfig::builder().retry(retry).build();
+        let error = config.create_router().err().unwrap();
+        assert!(matches!(error, NotificationConfigError::InvalidRetryError(_)));
+    }
+    let retry = NotificationRetryConfig::builder().backoff_multiplier(1.0).build();
+    assert!(retry.validate().is_ok());
+}
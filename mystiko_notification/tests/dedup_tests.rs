@@ -0,0 +1,120 @@
+use mystiko_notification::{
+    DedupKey, DedupMessage, DedupNotification, MemoryNotification, Notification, NotificationMessage,
+    NotificationSeverity,
+};
+use std::time::Duration;
+
+#[tokio::test(start_paused = true)]
+async fn test_push_suppress() {
+    let recorder = MemoryNotification::<String>::new();
+    let notification = DedupNotification::new(recorder.clone(), Duration::from_secs(60));
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    notification.push("stuck tx 0x2".to_string()).await.unwrap();
+    assert_eq!(recorder.len(), 2);
+    assert_eq!(notification.suppressed("stuck tx 0x1"), 2);
+    assert_eq!(notification.summary().len(), 1);
+
+    tokio::time::advance(Duration::from_secs(61)).await;
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    assert_eq!(
+        recorder.last().as_deref(),
+        Some("stuck tx 0x1 (2 duplicates suppressed)")
+    );
+    assert_eq!(notification.suppressed("stuck tx 0x1"), 0);
+    assert!(notification.summary().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_resolve() {
+    let recorder = MemoryNotification::<String>::new();
+    let notification = DedupNotification::builder()
+        .inner(recorder.clone())
+        .window(Duration::from_secs(60))
+        .build();
+    assert!(!notification.resolve("stuck tx 0x1").await.unwrap());
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    assert!(notification.is_active("stuck tx 0x1"));
+    assert!(notification.resolve("stuck tx 0x1").await.unwrap());
+    assert!(!notification.is_active("stuck tx 0x1"));
+    assert_eq!(
+        recorder.messages(),
+        vec![
+            "stuck tx 0x1".to_string(),
+            "[resolved] stuck tx 0x1 (1 duplicates suppressed)".to_string()
+        ]
+    );
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    assert_eq!(recorder.len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_failure_not_suppressed() {
+    let recorder = MemoryNotification::<String>::new();
+    let notification = DedupNotification::new(recorder.clone(), Duration::from_secs(60));
+    recorder.fail_next("throttled");
+    assert!(notification.push("stuck tx 0x1".to_string()).await.is_err());
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    assert_eq!(recorder.messages(), vec!["stuck tx 0x1".to_string()]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_resolve_failure_keeps_alert() {
+    let recorder = MemoryNotification::<String>::new();
+    let notification = DedupNotification::new(recorder.clone(), Duration::from_secs(60));
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    recorder.fail_next("throttled");
+    assert!(notification.resolve("stuck tx 0x1").await.is_err());
+    assert!(notification.is_active("stuck tx 0x1"));
+    assert!(notification.resolve("stuck tx 0x1").await.unwrap());
+    assert!(!notification.is_active("stuck tx 0x1"));
+    assert_eq!(recorder.last().as_deref(), Some("[resolved] stuck tx 0x1"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_evict_alerts() {
+    let recorder = MemoryNotification::<String>::new();
+    let notification = DedupNotification::builder()
+        .inner(recorder.clone())
+        .window(Duration::from_secs(60))
+        .retention(Duration::from_secs(120))
+        .max_alerts(2)
+        .build();
+    notification.push("stuck tx 0x1".to_string()).await.unwrap();
+    tokio::time::advance(Duration::from_secs(121)).await;
+    notification.push("stuck tx 0x2".to_string()).await.unwrap();
+    assert!(!notification.is_active("stuck tx 0x1"));
+    assert_eq!(notification.len(), 1);
+
+    tokio::time::advance(Duration::from_secs(1)).await;
+    notification.push("stuck tx 0x3".to_string()).await.unwrap();
+    tokio::time::advance(Duration::from_secs(1)).await;
+    notification.push("stuck tx 0x2".to_string()).await.unwrap();
+    notification.push("stuck tx 0x4".to_string()).await.unwrap();
+    assert_eq!(notification.len(), 2);
+    assert!(notification.is_active("stuck tx 0x2"));
+    assert!(!notification.is_active("stuck tx 0x3"));
+    assert!(notification.is_active("stuck tx 0x4"));
+    assert_eq!(notification.suppressed("stuck tx 0x2"), 1);
+}
+
+#[test]
+fn test_notification_message() {
+    let message = NotificationMessage::builder()
+        .title("Stuck transaction")
+        .body("tx 0x1 is stuck")
+        .severity(NotificationSeverity::Critical)
+        .dedup_id("stuck-0x1")
+        .build();
+    assert_eq!(message.dedup_key(), "stuck-0x1");
+    let resolved = message.resolved();
+    assert_eq!(resolved.title.as_deref(), Some("[resolved] Stuck transaction"));
+    assert_eq!(resolved.severity, NotificationSeverity::Info);
+    assert_eq!(resolved.dedup_id.as_deref(), Some("stuck-0x1-resolved"));
+    assert_eq!(resolved.attributes["status"], "resolved");
+    let suppressed = message.with_suppressed(3);
+    assert_eq!(suppressed.body, "tx 0x1 is stuck\n\n(3 duplicates suppressed)");
+    assert_eq!(suppressed.attributes["suppressed"], "3");
+}
@@ -0,0 +1,158 @@
+use lettre::transport::stub::AsyncStubTransport;
+use lettre::{AsyncSmtpTransport, Tokio1Executor};
+use mystiko_notification::{
+    EmailMessage, EmailNotification, EmailNotificationError, Notification, RetryableError, SmtpConfig, SmtpSecurity,
+};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn test_push_text() {
+    let transport = AsyncStubTransport::new_ok();
+    let notification = EmailNotification::<AsyncStubTransport>::builder()
+        .transport(transport.clone())
+        .from("Mystiko Alert <alert@mystiko.network>")
+        .to(vec!["ops@mystiko.network".to_string()])
+        .build();
+    let message = EmailMessage::builder()
+        .subject("Low balance")
+        .text("relayer balance is low")
+        .build();
+    notification.push(message).await.unwrap();
+    let messages = transport.messages().await;
+    assert_eq!(messages.len(), 1);
+    let (envelope, raw) = &messages[0];
+    assert_eq!(envelope.from().unwrap().to_string(), "alert@mystiko.network");
+    assert_eq!(envelope.to().len(), 1);
+    assert_eq!(envelope.to()[0].to_string(), "ops@mystiko.network");
+    assert!(raw.contains("Subject: Low balance"));
+    assert!(raw.contains("relayer balance is low"));
+}
+
+#[tokio::test]
+async fn test_push_html_multiple_recipients() {
+    let transport = AsyncStubTransport::new_ok();
+    let notification = EmailNotification::<AsyncStubTransport>::builder()
+        .transport(transport.clone())
+        .from("alert@mystiko.network")
+        .to(vec!["default@mystiko.network".to_string()])
+        .build();
+    let message = EmailMessage::builder()
+        .from("relayer@mystiko.network")
+        .to(vec![
+            "ops1@mystiko.network".to_string(),
+            "ops2@mystiko.network".to_string(),
+        ])
+        .cc(vec!["cc@mystiko.network".to_string()])
+        .bcc(vec!["bcc@mystiko.network".to_string()])
+        .subject("Stuck transaction")
+        .text("tx is stuck")
+        .html("<p>tx is stuck</p>")
+        .build();
+    notification.push(message).await.unwrap();
+    let messages = transport.messages().await;
+    let (envelope, raw) = &messages[0];
+    assert_eq!(envelope.from().unwrap().to_string(), "relayer@mystiko.network");
+    let recipients = envelope.to().iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        recipients,
+        vec![
+            "ops1@mystiko.network",
+            "ops2@mystiko.network",
+            "cc@mystiko.network",
+            "bcc@mystiko.network"
+        ]
+    );
+    assert!(raw.contains("multipart/alternative"));
+    assert!(raw.contains("<p>tx is stuck</p>"));
+    assert!(!raw.contains("default@mystiko.network"));
+}
+
+#[tokio::test]
+async fn test_push_errors() {
+    let notification = EmailNotification::<AsyncStubTransport>::builder()
+        .transport(AsyncStubTransport::new_ok())
+        .from("alert@mystiko.network")
+        .build();
+    let message = EmailMessage::builder().subject("No recipients").build();
+    assert!(matches!(
+        notification.push(message).await.unwrap_err(),
+        EmailNotificationError::NoRecipientsError
+    ));
+    let message = EmailMessage::builder()
+        .to(vec!["invalid address".to_string()])
+        .subject("Invalid")
+        .build();
+    assert!(matches!(
+        notification.push(message).await.unwrap_err(),
+        EmailNotificationError::AddressError(_)
+    ));
+
+    let notification = EmailNotification::<AsyncStubTransport>::builder()
+        .transport(AsyncStubTransport::new_error())
+        .from("alert@mystiko.network")
+        .to(vec!["ops@mystiko.network".to_string()])
+        .build();
+    let message = EmailMessage::builder().subject("Failed").build();
+    let error = notification.push(message).await.unwrap_err();
+    assert!(matches!(error, EmailNotificationError::TransportError(_)));
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn test_push_smtp_rejection() {
+    for (reply, retryable) in [("550 5.1.1 no such user", false), ("451 4.3.0 try again later", true)] {
+        let port = smtp_server(reply).await;
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+            .port(port)
+            .build();
+        let notification = EmailNotification::new(
+            transport,
+            "alert@mystiko.network".to_string(),
+            vec!["ops@mystiko.network".to_string()],
+        );
+        let message = EmailMessage::builder().subject("Rejected").build();
+        let error = notification.push(message).await.unwrap_err();
+        assert!(matches!(error, EmailNotificationError::SmtpError(_)));
+        assert_eq!(error.is_retryable(), retryable);
+    }
+}
+
+#[tokio::test]
+async fn test_from_config() {
+    for security in [SmtpSecurity::Tls, SmtpSecurity::StartTls, SmtpSecurity::None] {
+        let config = SmtpConfig::builder()
+            .host("smtp.mystiko.network")
+            .port(2525_u16)
+            .security(security)
+            .username("user")
+            .password("smtp-secret")
+            .timeout_secs(10_u64)
+            .from("alert@mystiko.network")
+            .to(vec!["ops@mystiko.network".to_string()])
+            .build();
+        assert!(!format!("{:?}", config).contains("smtp-secret"));
+        EmailNotification::from_config(&config).unwrap();
+    }
+}
+
+async fn smtp_server(rcpt_reply: &'static str) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let reply = match line.split_whitespace().next().unwrap_or_default() {
+                "EHLO" | "HELO" | "MAIL" => "250 OK",
+                "RCPT" => rcpt_reply,
+                "QUIT" => "221 Bye",
+                _ => "502 unsupported",
+            };
+            writer.write_all(format!("{}\r\n", reply).as_bytes()).await.unwrap();
+        }
+    });
+    port
+}
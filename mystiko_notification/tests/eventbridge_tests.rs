@@ -0,0 +1,119 @@
+use mystiko_notification::{
+    BatchNotification, EventBridgeNotification, EventBridgeNotificationError, Notification, NotificationMessage,
+    NotificationSeverity, RetryableError,
+};
+use rusoto_core::{Region, RusotoError};
+use rusoto_events::{EventBridgeClient, PutEventsError, PutEventsRequestEntry};
+use rusoto_mock::{MockCredentialsProvider, MockRequestDispatcher};
+
+#[tokio::test]
+async fn test_push() {
+    let notification = create_notification(
+        MockRequestDispatcher::default().with_body(r#"{"FailedEntryCount":0,"Entries":[{"EventId":"event-1"}]}"#),
+    );
+    let message = PutEventsRequestEntry {
+        detail: Some(r#"{"balance":"low"}"#.to_string()),
+        detail_type: Some("Low balance".to_string()),
+        ..Default::default()
+    };
+    notification.push(message).await.unwrap();
+    let message = NotificationMessage::builder()
+        .title("Low balance")
+        .body("relayer balance is low")
+        .build();
+    notification.push(message).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_push_batch() {
+    let notification = create_notification(MockRequestDispatcher::default().with_body(r#"{"FailedEntryCount":0}"#));
+    let messages = (0..25)
+        .map(|index| NotificationMessage::from(format!("message {}", index)))
+        .collect::<Vec<_>>();
+    notification.push_batch(messages).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_push_failed_entries() {
+    let notification = create_notification(MockRequestDispatcher::default().with_body(
+        r#"{"FailedEntryCount":1,"Entries":[{"ErrorCode":"ThrottlingException","ErrorMessage":"slow down"}]}"#,
+    ));
+    let error = notification
+        .push(NotificationMessage::from("relayer balance is low"))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, EventBridgeNotificationError::FailedEntriesError(_)));
+    assert_eq!(
+        error.to_string(),
+        "eventbridge rejected 1 event(s): ThrottlingException: slow down"
+    );
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn test_push_batch_failed_entries() {
+    let notification = create_notification(MockRequestDispatcher::default().with_body(
+        r#"{"FailedEntryCount":1,"Entries":[{"EventId":"event-1"},{"ErrorCode":"InternalFailure","ErrorMessage":"retry"}]}"#,
+    ));
+    let messages = vec![
+        NotificationMessage::from("relayer balance is low"),
+        NotificationMessage::from("transaction is stuck"),
+    ];
+    let error = notification.push_batch(messages.clone()).await.unwrap_err();
+    assert!(error.error.is_retryable());
+    assert_eq!(error.undelivered, vec![messages[1].clone()]);
+}
+
+#[tokio::test]
+async fn test_push_error() {
+    let notification = create_notification(MockRequestDispatcher::with_status(500));
+    let error = notification
+        .push(NotificationMessage::from("relayer balance is low"))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, EventBridgeNotificationError::PushError(_)));
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn test_from_region() {
+    let notification = EventBridgeNotification::from_region(Region::ApSoutheast1);
+    assert_eq!(notification.source(), "mystiko.notification");
+    assert_eq!(notification.event_bus_name(), None);
+    let notification = notification
+        .with_source("mystiko.relayer")
+        .with_event_bus_name("alerts");
+    assert_eq!(notification.source(), "mystiko.relayer");
+    assert_eq!(notification.event_bus_name(), Some("alerts"));
+}
+
+#[test]
+fn test_from_notification_message() {
+    let message = NotificationMessage::builder()
+        .title("Low balance")
+        .body("relayer balance is low")
+        .severity(NotificationSeverity::Critical)
+        .build();
+    let entry = PutEventsRequestEntry::from(message);
+    assert_eq!(entry.detail_type.as_deref(), Some("Low balance"));
+    let detail = entry.detail.unwrap();
+    assert!(detail.contains(r#""body":"relayer balance is low""#));
+    assert!(detail.contains(r#""severity":"critical""#));
+    assert!(entry.source.is_none());
+}
+
+#[test]
+fn test_retryable_error() {
+    let error: EventBridgeNotificationError =
+        RusotoError::Service(PutEventsError::Internal("internal".to_string())).into();
+    assert!(error.is_retryable());
+    let error: EventBridgeNotificationError = RusotoError::<PutEventsError>::Validation("invalid".to_string()).into();
+    assert!(!error.is_retryable());
+    let error = EventBridgeNotificationError::FailedEntriesError(vec!["ValidationException: invalid".to_string()]);
+    assert!(!error.is_retryable());
+}
+
+fn create_notification(dispatcher: MockRequestDispatcher) -> EventBridgeNotification {
+    let client = EventBridgeClient::new_with(dispatcher, MockCredentialsProvider, Region::UsEast1);
+    EventBridgeNotification::builder().client(client).build()
+}
@@ -0,0 +1,38 @@
+use mystiko_notification::{BatchNotification, FileNotification, Notification, NotificationMessage};
+
+#[tokio::test]
+async fn test_push_and_read() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("logs").join("notifications.jsonl");
+    let notification = FileNotification::<String>::new(&path);
+    assert_eq!(notification.path(), path.as_path());
+    assert!(notification.read().await.unwrap().is_empty());
+    notification.push("message 1".to_string()).await.unwrap();
+    notification
+        .push_batch(vec!["message 2".to_string(), "message 3".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(
+        notification.read().await.unwrap(),
+        vec![
+            "message 1".to_string(),
+            "message 2".to_string(),
+            "message 3".to_string()
+        ]
+    );
+    let content = tokio::fs::read_to_string(&path).await.unwrap();
+    assert_eq!(content, "\"message 1\"\n\"message 2\"\n\"message 3\"\n");
+}
+
+#[tokio::test]
+async fn test_push_notification_message() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("notifications.jsonl");
+    let message = NotificationMessage::builder()
+        .title("Low balance")
+        .body("relayer balance is low")
+        .build();
+    FileNotification::new(&path).push(message.clone()).await.unwrap();
+    let notification = FileNotification::<NotificationMessage>::new(&path);
+    assert_eq!(notification.read().await.unwrap(), vec![message]);
+}
@@ -0,0 +1,61 @@
+use mystiko_notification::{
+    MemoryNotification, MemoryNotificationError, Notification, NotificationMessage, NotificationRoute,
+    NotificationRouter, NotificationSeverity, RetryOptions, RetryingNotification,
+};
+
+#[tokio::test]
+async fn test_push() {
+    let notification = MemoryNotification::<String>::new();
+    assert!(notification.is_empty());
+    notification.push("message 1".to_string()).await.unwrap();
+    notification.push("message 2".to_string()).await.unwrap();
+    assert_eq!(notification.len(), 2);
+    assert_eq!(notification.attempts(), 2);
+    assert_eq!(notification.last().as_deref(), Some("message 2"));
+    assert_eq!(
+        notification.messages(),
+        vec!["message 1".to_string(), "message 2".to_string()]
+    );
+    assert_eq!(notification.take().len(), 2);
+    assert!(notification.is_empty());
+}
+
+#[tokio::test]
+async fn test_injected_failures() {
+    let notification = MemoryNotification::<String>::new();
+    notification.fail_next("throttled");
+    let error = notification.push("message 1".to_string()).await.unwrap_err();
+    assert!(matches!(error, MemoryNotificationError::InjectedError(_)));
+    assert_eq!(error.to_string(), "injected notification failure: throttled");
+    notification.push("message 2".to_string()).await.unwrap();
+
+    notification.fail_always("unavailable");
+    assert!(notification.push("message 3".to_string()).await.is_err());
+    assert!(notification.push("message 4".to_string()).await.is_err());
+    notification.recover();
+    notification.push("message 5".to_string()).await.unwrap();
+    assert_eq!(
+        notification.messages(),
+        vec!["message 2".to_string(), "message 5".to_string()]
+    );
+    assert_eq!(notification.attempts(), 5);
+    notification.clear();
+    assert_eq!(notification.attempts(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_shared_recorder() {
+    let recorder = MemoryNotification::<NotificationMessage>::new();
+    recorder.fail_next("throttled");
+    let router = NotificationRouter::builder().build().route(NotificationRoute::new(
+        "memory",
+        RetryingNotification::new(recorder.clone(), RetryOptions::default()),
+    ));
+    let message = NotificationMessage::builder()
+        .body("relayer balance is low")
+        .severity(NotificationSeverity::Warning)
+        .build();
+    router.push(message.clone()).await.unwrap();
+    assert_eq!(recorder.attempts(), 2);
+    assert_eq!(recorder.messages(), vec![message]);
+}
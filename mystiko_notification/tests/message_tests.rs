@@ -0,0 +1,65 @@
+use mystiko_notification::{
+    DedupKey, EmailMessage, NotificationMessage, NotificationSeverity, RoutableMessage, WebhookMessage,
+};
+use std::collections::HashMap;
+
+#[test]
+fn test_builder() {
+    let message = NotificationMessage::from("relayer balance is low");
+    assert_eq!(message.body, "relayer balance is low");
+    assert!(message.title.is_none());
+    assert_eq!(message.severity, NotificationSeverity::Info);
+    assert!(message.tags.is_empty());
+    assert!(message.attributes.is_empty());
+    assert_eq!(message.subject(), "[info] notification");
+
+    let mut attributes = HashMap::new();
+    attributes.insert("chain".to_string(), "ethereum".to_string());
+    let message = NotificationMessage::builder()
+        .title("Low balance")
+        .body("relayer balance is low")
+        .severity(NotificationSeverity::Critical)
+        .topic("relayer")
+        .tags(vec!["balance".to_string()])
+        .attributes(attributes)
+        .group_id("relayer")
+        .build();
+    assert_eq!(message.subject(), "Low balance");
+    assert_eq!(message.severity(), NotificationSeverity::Critical);
+    assert_eq!(message.topic(), Some("relayer"));
+    assert_eq!(message.attributes["chain"], "ethereum");
+    assert_eq!(message.group_id.as_deref(), Some("relayer"));
+}
+
+#[test]
+fn test_dedup_key() {
+    let message = NotificationMessage::builder()
+        .title("Low balance")
+        .body("relayer balance is low")
+        .topic("relayer")
+        .build();
+    assert_eq!(message.dedup_key(), "relayer\nLow balance\nrelayer balance is low");
+    let message = NotificationMessage::builder()
+        .body("relayer balance is low")
+        .dedup_id("balance-1")
+        .build();
+    assert_eq!(message.dedup_key(), "balance-1");
+}
+
+#[test]
+fn test_into_backend_messages() {
+    let message = NotificationMessage::builder()
+        .title("Low balance")
+        .body("relayer balance is low")
+        .severity(NotificationSeverity::Warning)
+        .build();
+    let webhook_message = WebhookMessage::from(message.clone());
+    assert_eq!(webhook_message.title.as_deref(), Some("Low balance"));
+    assert_eq!(webhook_message.text, "relayer balance is low");
+    let email_message = EmailMessage::from(message);
+    assert_eq!(email_message.subject, "Low balance");
+    assert_eq!(email_message.text, "relayer balance is low");
+    assert!(email_message.to.is_empty());
+    let email_message = EmailMessage::from(NotificationMessage::from("stuck tx"));
+    assert_eq!(email_message.subject, "[info] notification");
+}
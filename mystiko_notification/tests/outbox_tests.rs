@@ -0,0 +1,209 @@
+use async_trait::async_trait;
+use mockall::{mock, Sequence};
+use mystiko_notification::{Notification, NotificationOutbox, OutboxOptions};
+use std::sync::Arc;
+use std::time::Duration;
+
+mock! {
+    pub Backend {}
+
+    #[async_trait]
+    impl Notification<String> for Backend {
+        type Error = anyhow::Error;
+
+        async fn push(&self, message: String) -> Result<(), anyhow::Error>;
+    }
+}
+
+#[tokio::test]
+async fn test_enqueue_and_drain() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let outbox = NotificationOutbox::<String>::new(OutboxOptions::builder().folder(temp_dir.path()).build())
+        .await
+        .unwrap();
+    assert_eq!(outbox.depth().await.unwrap(), 0);
+    assert!(outbox.oldest_age().await.unwrap().is_none());
+    outbox.push("message 1".to_string()).await.unwrap();
+    outbox.enqueue("message 2".to_string()).await.unwrap();
+    assert_eq!(outbox.depth().await.unwrap(), 2);
+    assert!(outbox.oldest_age().await.unwrap().is_some());
+
+    let mut backend = MockBackend::new();
+    let mut seq = Sequence::new();
+    backend
+        .expect_push()
+        .withf(|message| message == "message 1")
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|_| Ok(()));
+    backend
+        .expect_push()
+        .withf(|message| message == "message 2")
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|_| Ok(()));
+    let result = outbox.drain(&backend).await.unwrap();
+    assert_eq!(result.delivered, 2);
+    assert!(!result.failed);
+    assert_eq!(outbox.depth().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_drain_failure_keeps_order() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let outbox = NotificationOutbox::<String>::new(OutboxOptions::builder().folder(temp_dir.path()).build())
+        .await
+        .unwrap();
+    outbox.enqueue("message 1".to_string()).await.unwrap();
+    outbox.enqueue("message 2".to_string()).await.unwrap();
+
+    let mut backend = MockBackend::new();
+    backend
+        .expect_push()
+        .withf(|message| message == "message 1")
+        .times(1)
+        .returning(|_| Err(anyhow::anyhow!("downstream outage")));
+    let result = outbox.drain(&backend).await.unwrap();
+    assert_eq!(result.delivered, 0);
+    assert!(result.failed);
+    assert_eq!(outbox.depth().await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_drain_dead_letter() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let options = OutboxOptions::builder()
+        .folder(temp_dir.path())
+        .max_attempts(2_u32)
+        .build();
+    let outbox = NotificationOutbox::<String>::new(options).await.unwrap();
+    outbox.enqueue("message 1".to_string()).await.unwrap();
+    outbox.enqueue("message 2".to_string()).await.unwrap();
+
+    let mut backend = MockBackend::new();
+    backend
+        .expect_push()
+        .withf(|message| message == "message 1")
+        .times(2)
+        .returning(|_| Err(anyhow::anyhow!("invalid message")));
+    backend
+        .expect_push()
+        .withf(|message| message == "message 2")
+        .times(1)
+        .returning(|_| Ok(()));
+    let result = outbox.drain(&backend).await.unwrap();
+    assert!(result.failed);
+    let result = outbox.drain(&backend).await.unwrap();
+    assert_eq!(result.dead, 1);
+    assert_eq!(result.delivered, 1);
+    assert_eq!(outbox.depth().await.unwrap(), 0);
+    assert_eq!(outbox.dead_letters().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_drain_undecodable_entries() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let outbox = NotificationOutbox::<String>::new(OutboxOptions::builder().folder(temp_dir.path()).build())
+        .await
+        .unwrap();
+    let queue_folder = temp_dir.path().join("queue");
+    tokio::fs::write(
+        queue_folder.join("00000000000000000000-00000000000000000000.json"),
+        b"{",
+    )
+    .await
+    .unwrap();
+    tokio::fs::write(
+        queue_folder.join("00000000000000000000-00000000000000000001.json"),
+        br#"{"enqueued_at":0,"attempts":0,"message":1}"#,
+    )
+    .await
+    .unwrap();
+    outbox.enqueue("message 1".to_string()).await.unwrap();
+
+    let mut backend = MockBackend::new();
+    backend
+        .expect_push()
+        .withf(|message| message == "message 1")
+        .times(1)
+        .returning(|_| Ok(()));
+    let result = outbox.drain(&backend).await.unwrap();
+    assert_eq!(result.dead, 2);
+    assert_eq!(result.delivered, 1);
+    assert!(!result.failed);
+    assert_eq!(outbox.depth().await.unwrap(), 0);
+    assert_eq!(outbox.dead_letters().await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_concurrent_drains() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let outbox = NotificationOutbox::<String>::new(OutboxOptions::builder().folder(temp_dir.path()).build())
+        .await
+        .unwrap();
+    outbox.enqueue("message 1".to_string()).await.unwrap();
+    outbox.enqueue("message 2".to_string()).await.unwrap();
+
+    let mut backend = MockBackend::new();
+    backend.expect_push().times(2).returning(|_| Ok(()));
+    let (first, second) = tokio::join!(outbox.drain(&backend), outbox.drain(&backend));
+    assert_eq!(first.unwrap().delivered + second.unwrap().delivered, 2);
+    assert_eq!(outbox.depth().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_survives_restart() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let options = OutboxOptions::builder().folder(temp_dir.path()).build();
+    let outbox = NotificationOutbox::<String>::new(options.clone()).await.unwrap();
+    outbox.enqueue("message 1".to_string()).await.unwrap();
+    drop(outbox);
+    tokio::fs::write(temp_dir.path().join("queue").join("partial.tmp"), b"{")
+        .await
+        .unwrap();
+
+    let outbox = NotificationOutbox::<String>::new(options).await.unwrap();
+    assert_eq!(outbox.depth().await.unwrap(), 1);
+    assert!(!temp_dir.path().join("queue").join("partial.tmp").exists());
+    let mut backend = MockBackend::new();
+    backend
+        .expect_push()
+        .withf(|message| message == "message 1")
+        .times(1)
+        .returning(|_| Ok(()));
+    assert_eq!(outbox.drain(&backend).await.unwrap().delivered, 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_spawn_worker() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let options = OutboxOptions::builder()
+        .folder(temp_dir.path())
+        .poll_interval(Duration::from_millis(10))
+        .retry_backoff(Duration::from_millis(10))
+        .build();
+    let outbox = Arc::new(NotificationOutbox::<String>::new(options).await.unwrap());
+    let mut backend = MockBackend::new();
+    let mut seq = Sequence::new();
+    backend
+        .expect_push()
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|_| Err(anyhow::anyhow!("downstream outage")));
+    backend
+        .expect_push()
+        .withf(|message| message == "message 1")
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|_| Ok(()));
+    let worker = outbox.spawn(backend);
+    outbox.enqueue("message 1".to_string()).await.unwrap();
+    for _ in 0..100 {
+        if outbox.depth().await.unwrap() == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    worker.shutdown().await;
+    assert_eq!(outbox.depth().await.unwrap(), 0);
+}
@@ -0,0 +1,213 @@
+use async_trait::async_trait;
+use mockall::{mock, Sequence};
+use mystiko_notification::{
+    BatchNotification, BatchPushError, Notification, RetryOptions, RetryableError, RetryingNotification,
+    RetryingNotificationError,
+};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TestError {
+    #[error("throttled")]
+    Throttled,
+    #[error("invalid")]
+    Invalid,
+}
+
+impl RetryableError for TestError {
+    fn is_retryable(&self) -> bool {
+        matches!(self, TestError::Throttled)
+    }
+}
+
+mock! {
+    pub Backend {}
+
+    #[async_trait]
+    impl Notification<String> for Backend {
+        type Error = TestError;
+
+        async fn push(&self, message: String) -> Result<(), TestError>;
+    }
+}
+
+struct SlowBackend {
+    delay: Duration,
+    calls: AtomicU32,
+}
+
+#[async_trait]
+impl Notification<String> for SlowBackend {
+    type Error = TestError;
+
+    async fn push(&self, _message: String) -> Result<(), Self::Error> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        Ok(())
+    }
+}
+
+// the first batch loses its last message, every later one is delivered in full.
+#[derive(Default)]
+struct PartialBackend {
+    delivered: Mutex<Vec<String>>,
+    failed: AtomicBool,
+}
+
+#[async_trait]
+impl Notification<String> for PartialBackend {
+    type Error = TestError;
+
+    async fn push(&self, message: String) -> Result<(), Self::Error> {
+        self.delivered.lock().unwrap().push(message);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BatchNotification<String> for PartialBackend {
+    async fn push_batch(&self, mut messages: Vec<String>) -> Result<(), BatchPushError<String, Self::Error>> {
+        let undelivered = if self.failed.swap(true, Ordering::SeqCst) {
+            vec![]
+        } else {
+            messages.split_off(messages.len() - 1)
+        };
+        self.delivered.lock().unwrap().extend(messages);
+        if undelivered.is_empty() {
+            Ok(())
+        } else {
+            Err(BatchPushError::new(TestError::Throttled, undelivered))
+        }
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_retry_success() {
+    let mut backend = MockBackend::new();
+    let mut seq = Sequence::new();
+    backend
+        .expect_push()
+        .times(2)
+        .in_sequence(&mut seq)
+        .returning(|_| Err(TestError::Throttled));
+    backend
+        .expect_push()
+        .withf(|message| message == "hello")
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|_| Ok(()));
+    let notification = RetryingNotification::builder().inner(backend).build();
+    let started = tokio::time::Instant::now();
+    notification.push("hello".to_string()).await.unwrap();
+    assert_eq!(started.elapsed(), Duration::from_millis(600));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_retry_exhausted() {
+    let mut backend = MockBackend::new();
+    backend.expect_push().times(3).returning(|_| Err(TestError::Throttled));
+    let options = RetryOptions::builder()
+        .max_retries(2_u32)
+        .initial_backoff(Duration::from_secs(1))
+        .max_backoff(Duration::from_millis(1500))
+        .build();
+    let notification = RetryingNotification::new(backend, options);
+    let started = tokio::time::Instant::now();
+    let error = notification.push("hello".to_string()).await.unwrap_err();
+    assert!(matches!(
+        error,
+        RetryingNotificationError::PushError(TestError::Throttled)
+    ));
+    assert_eq!(started.elapsed(), Duration::from_millis(2500));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_non_retryable() {
+    let mut backend = MockBackend::new();
+    backend.expect_push().times(1).returning(|_| Err(TestError::Invalid));
+    let notification = RetryingNotification::builder().inner(backend).build();
+    let error = notification.push("hello".to_string()).await.unwrap_err();
+    assert_eq!(error.to_string(), "invalid");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_timeout() {
+    let backend = SlowBackend {
+        delay: Duration::from_secs(10),
+        calls: AtomicU32::new(0),
+    };
+    let options = RetryOptions::builder()
+        .max_retries(1_u32)
+        .timeout(Duration::from_secs(1))
+        .build();
+    let notification = RetryingNotification::new(backend, options);
+    let error = notification.push("hello".to_string()).await.unwrap_err();
+    assert!(matches!(error, RetryingNotificationError::TimeoutError(_)));
+    assert_eq!(notification.inner().calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_circuit_breaker() {
+    let mut backend = MockBackend::new();
+    let mut seq = Sequence::new();
+    backend
+        .expect_push()
+        .times(3)
+        .in_sequence(&mut seq)
+        .returning(|_| Err(TestError::Throttled));
+    backend
+        .expect_push()
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|_| Ok(()));
+    let options = RetryOptions::builder()
+        .max_retries(5_u32)
+        .circuit_breaker_threshold(3_u32)
+        .circuit_breaker_cooldown(Duration::from_secs(60))
+        .build();
+    let notification = RetryingNotification::new(backend, options);
+    let error = notification.push("hello".to_string()).await.unwrap_err();
+    assert!(matches!(error, RetryingNotificationError::CircuitOpenError));
+    assert!(notification.is_circuit_open());
+    let error = notification.push("hello".to_string()).await.unwrap_err();
+    assert_eq!(error.to_string(), "notification circuit breaker is open");
+    tokio::time::advance(Duration::from_secs(61)).await;
+    assert!(!notification.is_circuit_open());
+    notification.push("hello".to_string()).await.unwrap();
+    assert!(!notification.is_circuit_open());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_batch_retry_undelivered() {
+    let notification = RetryingNotification::new(PartialBackend::default(), RetryOptions::default());
+    let messages = vec!["first".to_string(), "second".to_string(), "third".to_string()];
+    notification.push_batch(messages.clone()).await.unwrap();
+    assert_eq!(*notification.inner().delivered.lock().unwrap(), messages);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_push_invalid_multiplier() {
+    for backoff_multiplier in [-2.0, f64::NAN] {
+        let mut backend = MockBackend::new();
+        backend.expect_push().times(3).returning(|_| Err(TestError::Throttled));
+        let options = RetryOptions::builder()
+            .max_retries(2_u32)
+            .backoff_multiplier(backoff_multiplier)
+            .build();
+        let notification = RetryingNotification::new(backend, options);
+        assert!(notification.push("hello".to_string()).await.is_err());
+    }
+}
+
+#[test]
+fn test_default_options() {
+    let options = RetryOptions::default();
+    assert_eq!(options.max_retries, 3);
+    assert_eq!(options.initial_backoff, Duration::from_millis(200));
+    assert!(options.timeout.is_none());
+    assert!(options.circuit_breaker_threshold.is_none());
+    assert!(anyhow::anyhow!("error").is_retryable());
+}
@@ -0,0 +1,190 @@
+use async_trait::async_trait;
+use mockall::mock;
+use mystiko_notification::{
+    DispatchMode, Notification, NotificationFilter, NotificationRoute, NotificationRouter, NotificationSeverity,
+    RoutableMessage,
+};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+struct TestMessage {
+    text: String,
+    severity: NotificationSeverity,
+    topic: Option<String>,
+}
+
+impl RoutableMessage for TestMessage {
+    fn severity(&self) -> NotificationSeverity {
+        self.severity
+    }
+
+    fn topic(&self) -> Option<&str> {
+        self.topic.as_deref()
+    }
+}
+
+mock! {
+    pub Backend {}
+
+    #[async_trait]
+    impl Notification<TestMessage> for Backend {
+        type Error = anyhow::Error;
+
+        async fn push(&self, message: TestMessage) -> Result<(), anyhow::Error>;
+    }
+}
+
+#[tokio::test]
+async fn test_push_all_routes() {
+    let mut backend1 = MockBackend::new();
+    backend1
+        .expect_push()
+        .withf(|message| message.text == "hello")
+        .times(1)
+        .returning(|_| Ok(()));
+    let mut backend2 = MockBackend::new();
+    backend2
+        .expect_push()
+        .withf(|message| message.text == "hello")
+        .times(1)
+        .returning(|_| Ok(()));
+    let router = NotificationRouter::builder()
+        .build()
+        .route(NotificationRoute::new("backend1", backend1))
+        .route(NotificationRoute::new("backend2", backend2));
+    assert_eq!(router.routes().len(), 2);
+    assert_eq!(router.mode(), &DispatchMode::BestEffort);
+    router
+        .push(message("hello", NotificationSeverity::Info, None))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_push_with_filters() {
+    let mut critical = MockBackend::new();
+    critical.expect_push().times(1).returning(|_| Ok(()));
+    let mut relayer = MockBackend::new();
+    relayer.expect_push().times(2).returning(|_| Ok(()));
+    let router = NotificationRouter::builder()
+        .build()
+        .route(
+            NotificationRoute::new("critical", critical).with_filter(
+                NotificationFilter::builder()
+                    .min_severity(NotificationSeverity::Error)
+                    .build(),
+            ),
+        )
+        .route(
+            NotificationRoute::new("relayer", relayer).with_filter(
+                NotificationFilter::builder()
+                    .topics(vec!["relayer".to_string()])
+                    .build(),
+            ),
+        );
+    assert_eq!(router.routes()[0].name(), "critical");
+    assert_eq!(router.routes()[1].filter().topics, vec!["relayer".to_string()]);
+    router
+        .push(message("info", NotificationSeverity::Info, Some("relayer")))
+        .await
+        .unwrap();
+    router
+        .push(message("critical", NotificationSeverity::Critical, Some("relayer")))
+        .await
+        .unwrap();
+    router
+        .push(message("other", NotificationSeverity::Warning, Some("other")))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_push_best_effort() {
+    let mut backend1 = MockBackend::new();
+    backend1
+        .expect_push()
+        .times(1)
+        .returning(|_| Err(anyhow::anyhow!("backend1 error")));
+    let mut backend2 = MockBackend::new();
+    backend2.expect_push().times(1).returning(|_| Ok(()));
+    let mut backend3 = MockBackend::new();
+    backend3
+        .expect_push()
+        .times(1)
+        .returning(|_| Err(anyhow::anyhow!("backend3 error")));
+    let router = NotificationRouter::builder()
+        .mode(DispatchMode::BestEffort)
+        .build()
+        .route(NotificationRoute::new("backend1", backend1))
+        .route(NotificationRoute::new("backend2", backend2))
+        .route(NotificationRoute::new("backend3", backend3));
+    let error = router
+        .push(message("hello", NotificationSeverity::Info, None))
+        .await
+        .unwrap_err();
+    assert_eq!(error.routes(), vec!["backend1", "backend3"]);
+    assert_eq!(
+        error.to_string(),
+        "notification route backend1 failed: backend1 error; notification route backend3 failed: backend3 error"
+    );
+}
+
+#[tokio::test]
+async fn test_push_fail_fast() {
+    let mut backend1 = MockBackend::new();
+    backend1.expect_push().times(1).returning(|_| Ok(()));
+    let mut backend2 = MockBackend::new();
+    backend2
+        .expect_push()
+        .times(1)
+        .returning(|_| Err(anyhow::anyhow!("backend2 error")));
+    let mut backend3 = MockBackend::new();
+    backend3.expect_push().never();
+    let router = NotificationRouter::builder()
+        .mode(DispatchMode::FailFast)
+        .build()
+        .route(NotificationRoute::new("backend1", backend1))
+        .route(NotificationRoute::new("backend2", backend2))
+        .route(NotificationRoute::new("backend3", backend3));
+    let error = router
+        .push(message("hello", NotificationSeverity::Info, None))
+        .await
+        .unwrap_err();
+    assert_eq!(error.routes(), vec!["backend2"]);
+}
+
+#[tokio::test]
+async fn test_push_boxed_router() {
+    let mut backend = MockBackend::new();
+    backend.expect_push().times(1).returning(|_| Ok(()));
+    let mut router = NotificationRouter::builder().build();
+    router.add_route(NotificationRoute::new("backend", backend));
+    let boxed: Box<dyn Notification<TestMessage, Error = _>> = Box::new(router);
+    boxed
+        .push(message("hello", NotificationSeverity::Info, None))
+        .await
+        .unwrap();
+}
+
+#[test]
+fn test_severity() {
+    assert!(NotificationSeverity::Critical > NotificationSeverity::Error);
+    assert!(NotificationSeverity::Warning > NotificationSeverity::Info);
+    assert_eq!(NotificationSeverity::default(), NotificationSeverity::Info);
+    assert_eq!(
+        NotificationSeverity::from_str("WARN").unwrap(),
+        NotificationSeverity::Warning
+    );
+    assert_eq!(NotificationSeverity::Critical.to_string(), "critical");
+    assert!(NotificationSeverity::from_str("unknown").is_err());
+    assert_eq!("text".to_string().severity(), NotificationSeverity::Info);
+    assert!("text".to_string().topic().is_none());
+}
+
+fn message(text: &str, severity: NotificationSeverity, topic: Option<&str>) -> TestMessage {
+    TestMessage {
+        text: text.to_string(),
+        severity,
+        topic: topic.map(|t| t.to_string()),
+    }
+}
@@ -1,8 +1,16 @@
+// the mocked rusoto traits fix the error types, so they cannot be boxed.
+#![allow(clippy::result_large_err)]
+
 use async_trait::async_trait;
 use mockall::mock;
-use mystiko_notification::{Notification, SnsNotification};
+use mystiko_notification::{
+    DedupKey, Notification, NotificationMessage, NotificationSeverity, RetryableError, RoutableMessage,
+    SnsNotification, SnsNotificationError,
+};
 use rusoto_core::{Region, RusotoError};
+use rusoto_mock::{MockCredentialsProvider, MockRequestDispatcher};
 use rusoto_sns::*;
+use std::collections::HashMap;
 
 #[tokio::test]
 async fn test_push() {
@@ -25,11 +33,116 @@ async fn test_push() {
     notification.push(message).await.unwrap();
 }
 
+#[tokio::test]
+async fn test_push_notification_message() {
+    let mut client = MockSnsClient::new();
+    client
+        .expect_publish()
+        .withf(|input| {
+            let attributes = input.message_attributes.as_ref().unwrap();
+            input.message == "relayer balance is low"
+                && input.subject.as_ref().unwrap() == "Low balance"
+                && input.topic_arn.as_ref().unwrap() == "Default Topic"
+                && input.message_deduplication_id.as_ref().unwrap() == "balance-1"
+                && input.message_group_id.as_ref().unwrap() == "relayer"
+                && attributes["severity"].string_value.as_ref().unwrap() == "warning"
+                && attributes["chain"].string_value.as_ref().unwrap() == "ethereum"
+                && attributes["tags"].data_type == "String.Array"
+                && attributes["tags"].string_value.as_ref().unwrap() == "[\"relayer\",\"balance\"]"
+        })
+        .returning(|_| Ok(PublishResponse::default()));
+    let notification = SnsNotification::<MockSnsClient>::builder()
+        .client(client)
+        .build()
+        .with_topic_arn("Default Topic");
+    let mut attributes = HashMap::new();
+    attributes.insert("chain".to_string(), "ethereum".to_string());
+    let message = NotificationMessage::builder()
+        .title("Low balance")
+        .body("relayer balance is low")
+        .severity(NotificationSeverity::Warning)
+        .tags(vec!["relayer".to_string(), "balance".to_string()])
+        .attributes(attributes)
+        .dedup_id("balance-1")
+        .group_id("relayer")
+        .build();
+    notification.push(message).await.unwrap();
+}
+
 #[tokio::test]
 async fn test_from_region() {
     SnsNotification::from_region(Region::ApSoutheast1);
 }
 
+#[test]
+fn test_routable_publish_input() {
+    let mut message = PublishInput {
+        message: "Hello, world!".to_string(),
+        topic_arn: Some("Test Topic".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(message.topic(), Some("Test Topic"));
+    assert_eq!(message.severity(), NotificationSeverity::Info);
+    let mut attributes = HashMap::new();
+    attributes.insert(
+        "severity".to_string(),
+        MessageAttributeValue {
+            data_type: "String".to_string(),
+            string_value: Some("critical".to_string()),
+            ..Default::default()
+        },
+    );
+    message.message_attributes = Some(attributes);
+    assert_eq!(message.severity(), NotificationSeverity::Critical);
+    let message = PublishInput::from(
+        NotificationMessage::builder()
+            .body("Hello, world!")
+            .topic("relayer")
+            .build(),
+    );
+    assert!(message.topic_arn.is_none());
+    assert_eq!(message.topic(), Some("relayer"));
+}
+
+#[test]
+fn test_dedup_key_publish_input() {
+    let mut message = PublishInput {
+        message: "Hello, world!".to_string(),
+        subject: Some("Greeting".to_string()),
+        topic_arn: Some("Test Topic".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(message.dedup_key(), "Test Topic\nGreeting\nHello, world!");
+    message.message_deduplication_id = Some("greeting-1".to_string());
+    assert_eq!(message.dedup_key(), "greeting-1");
+}
+
+#[tokio::test]
+async fn test_retryable_error() {
+    let error: SnsNotificationError = RusotoError::Service(PublishError::KMSThrottling("throttled".to_string())).into();
+    assert!(error.is_retryable());
+    let error: SnsNotificationError = RusotoError::Service(PublishError::InternalError("internal".to_string())).into();
+    assert!(error.is_retryable());
+    let error: SnsNotificationError =
+        RusotoError::Service(PublishError::InvalidParameter("invalid".to_string())).into();
+    assert!(!error.is_retryable());
+    let error: SnsNotificationError = RusotoError::<PublishError>::Validation("invalid".to_string()).into();
+    assert!(!error.is_retryable());
+    for (status, retryable) in [(429, true), (503, true), (403, false)] {
+        let client = SnsClient::new_with(
+            MockRequestDispatcher::with_status(status),
+            MockCredentialsProvider,
+            Region::UsEast1,
+        );
+        let error = SnsNotification::new(client)
+            .with_topic_arn("arn:aws:sns:us-east-1:123456789012:test")
+            .push(NotificationMessage::builder().body("Hello, world!").build())
+            .await
+            .unwrap_err();
+        assert_eq!(error.is_retryable(), retryable);
+    }
+}
+
 mock! {
     pub SnsClient {}
 
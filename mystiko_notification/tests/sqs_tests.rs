@@ -0,0 +1,210 @@
+// the mocked rusoto traits fix the error types, so they cannot be boxed.
+#![allow(clippy::result_large_err)]
+
+use async_trait::async_trait;
+use mockall::mock;
+use mystiko_notification::{
+    DedupKey, Notification, NotificationMessage, NotificationSeverity, RetryableError, RoutableMessage,
+    SqsNotification, SqsNotificationError,
+};
+use rusoto_core::{Region, RusotoError};
+use rusoto_sqs::*;
+
+#[tokio::test]
+async fn test_push() {
+    let mut client = MockSqsClient::new();
+    client
+        .expect_send_message()
+        .withf(|input| input.message_body == "Hello, world!" && input.queue_url == "Test Queue")
+        .times(2)
+        .returning(|_| Ok(SendMessageResult::default()));
+    let notification = SqsNotification::<MockSqsClient>::builder()
+        .client(client)
+        .queue_url("Test Queue")
+        .build();
+    let message = SendMessageRequest {
+        message_body: "Hello, world!".to_string(),
+        ..Default::default()
+    };
+    notification.push(message).await.unwrap();
+    let message = SendMessageRequest {
+        message_body: "Hello, world!".to_string(),
+        queue_url: "Test Queue".to_string(),
+        ..Default::default()
+    };
+    notification.push(message).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_push_missing_queue_url() {
+    let notification = SqsNotification::<MockSqsClient>::builder()
+        .client(MockSqsClient::new())
+        .build();
+    let message = SendMessageRequest {
+        message_body: "Hello, world!".to_string(),
+        ..Default::default()
+    };
+    let error = notification.push(message).await.unwrap_err();
+    assert!(matches!(error, SqsNotificationError::MissingQueueUrlError));
+}
+
+#[tokio::test]
+async fn test_push_notification_message() {
+    let mut client = MockSqsClient::new();
+    client
+        .expect_send_message()
+        .withf(|input| {
+            let attributes = input.message_attributes.as_ref().unwrap();
+            input.message_body == "relayer balance is low"
+                && input.queue_url == "Test Queue.fifo"
+                && input.message_deduplication_id.as_ref().unwrap() == "balance-1"
+                && input.message_group_id.as_ref().unwrap() == "relayer"
+                && attributes["title"].string_value.as_ref().unwrap() == "Low balance"
+                && attributes["severity"].string_value.as_ref().unwrap() == "error"
+                && attributes["topic"].string_value.as_ref().unwrap() == "relayer"
+                && attributes["tags"].data_type == "String.Array"
+                && attributes["tags"].string_value.as_ref().unwrap() == "[\"relayer\",\"balance\"]"
+        })
+        .returning(|_| Ok(SendMessageResult::default()));
+    let notification = SqsNotification::<MockSqsClient>::builder()
+        .client(client)
+        .build()
+        .with_queue_url("Test Queue.fifo");
+    assert_eq!(notification.queue_url(), Some("Test Queue.fifo"));
+    let message = NotificationMessage::builder()
+        .title("Low balance")
+        .body("relayer balance is low")
+        .severity(NotificationSeverity::Error)
+        .topic("relayer")
+        .tags(vec!["relayer".to_string(), "balance".to_string()])
+        .dedup_id("balance-1")
+        .group_id("relayer")
+        .build();
+    notification.push(message).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_from_region() {
+    let notification = SqsNotification::from_region(Region::ApSoutheast1).with_queue_url("Test Queue");
+    assert_eq!(notification.queue_url(), Some("Test Queue"));
+}
+
+#[test]
+fn test_routable_send_message_request() {
+    let message = SendMessageRequest::from(
+        NotificationMessage::builder()
+            .body("Hello, world!")
+            .severity(NotificationSeverity::Warning)
+            .topic("Test Topic")
+            .build(),
+    );
+    assert_eq!(message.severity(), NotificationSeverity::Warning);
+    assert_eq!(message.topic(), Some("Test Topic"));
+    assert_eq!(message.dedup_key(), "\nHello, world!");
+    let message = SendMessageRequest {
+        message_body: "Hello, world!".to_string(),
+        message_deduplication_id: Some("greeting-1".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(message.severity(), NotificationSeverity::Info);
+    assert_eq!(message.topic(), None);
+    assert_eq!(message.dedup_key(), "greeting-1");
+}
+
+#[test]
+fn test_retryable_error() {
+    let error: SqsNotificationError =
+        RusotoError::Service(SendMessageError::InvalidMessageContents("invalid".to_string())).into();
+    assert!(!error.is_retryable());
+    let error: SqsNotificationError = RusotoError::<SendMessageError>::Validation("invalid".to_string()).into();
+    assert!(!error.is_retryable());
+    assert!(!SqsNotificationError::MissingQueueUrlError.is_retryable());
+}
+
+mock! {
+    pub SqsClient {}
+
+    #[async_trait]
+    impl Sqs for SqsClient {
+        async fn add_permission(
+            &self,
+            input: AddPermissionRequest,
+        ) -> Result<(), RusotoError<AddPermissionError>>;
+        async fn change_message_visibility(
+            &self,
+            input: ChangeMessageVisibilityRequest,
+        ) -> Result<(), RusotoError<ChangeMessageVisibilityError>>;
+        async fn change_message_visibility_batch(
+            &self,
+            input: ChangeMessageVisibilityBatchRequest,
+        ) -> Result<ChangeMessageVisibilityBatchResult, RusotoError<ChangeMessageVisibilityBatchError>>;
+        async fn create_queue(
+            &self,
+            input: CreateQueueRequest,
+        ) -> Result<CreateQueueResult, RusotoError<CreateQueueError>>;
+        async fn delete_message(
+            &self,
+            input: DeleteMessageRequest,
+        ) -> Result<(), RusotoError<DeleteMessageError>>;
+        async fn delete_message_batch(
+            &self,
+            input: DeleteMessageBatchRequest,
+        ) -> Result<DeleteMessageBatchResult, RusotoError<DeleteMessageBatchError>>;
+        async fn delete_queue(
+            &self,
+            input: DeleteQueueRequest,
+        ) -> Result<(), RusotoError<DeleteQueueError>>;
+        async fn get_queue_attributes(
+            &self,
+            input: GetQueueAttributesRequest,
+        ) -> Result<GetQueueAttributesResult, RusotoError<GetQueueAttributesError>>;
+        async fn get_queue_url(
+            &self,
+            input: GetQueueUrlRequest,
+        ) -> Result<GetQueueUrlResult, RusotoError<GetQueueUrlError>>;
+        async fn list_dead_letter_source_queues(
+            &self,
+            input: ListDeadLetterSourceQueuesRequest,
+        ) -> Result<ListDeadLetterSourceQueuesResult, RusotoError<ListDeadLetterSourceQueuesError>>;
+        async fn list_queue_tags(
+            &self,
+            input: ListQueueTagsRequest,
+        ) -> Result<ListQueueTagsResult, RusotoError<ListQueueTagsError>>;
+        async fn list_queues(
+            &self,
+            input: ListQueuesRequest,
+        ) -> Result<ListQueuesResult, RusotoError<ListQueuesError>>;
+        async fn purge_queue(
+            &self,
+            input: PurgeQueueRequest,
+        ) -> Result<(), RusotoError<PurgeQueueError>>;
+        async fn receive_message(
+            &self,
+            input: ReceiveMessageRequest,
+        ) -> Result<ReceiveMessageResult, RusotoError<ReceiveMessageError>>;
+        async fn remove_permission(
+            &self,
+            input: RemovePermissionRequest,
+        ) -> Result<(), RusotoError<RemovePermissionError>>;
+        async fn send_message(
+            &self,
+            input: SendMessageRequest,
+        ) -> Result<SendMessageResult, RusotoError<SendMessageError>>;
+        async fn send_message_batch(
+            &self,
+            input: SendMessageBatchRequest,
+        ) -> Result<SendMessageBatchResult, RusotoError<SendMessageBatchError>>;
+        async fn set_queue_attributes(
+            &self,
+            input: SetQueueAttributesRequest,
+        ) -> Result<(), RusotoError<SetQueueAttributesError>>;
+        async fn tag_queue(
+            &self,
+            input: TagQueueRequest,
+        ) -> Result<(), RusotoError<TagQueueError>>;
+        async fn untag_queue(
+            &self,
+            input: UntagQueueRequest,
+        ) -> Result<(), RusotoError<UntagQueueError>>;
+    }
+}
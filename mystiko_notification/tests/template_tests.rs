@@ -0,0 +1,109 @@
+use mystiko_notification::{
+    escape_for_format, render_template, NotificationSeverity, NotificationTemplate, TemplateError, TemplateFormat,
+    TemplateRegistry,
+};
+use std::collections::HashMap;
+
+fn registry() -> TemplateRegistry {
+    TemplateRegistry::default()
+        .template(
+            "low_balance",
+            "en",
+            NotificationTemplate::builder()
+                .title("Low balance on chain {{chain_id}}")
+                .text("Relayer {{ address }} balance is {{balance}}")
+                .markdown("*Relayer* `{{address}}` balance is *{{balance}}*")
+                .severity(NotificationSeverity::Warning)
+                .topic("relayer")
+                .build(),
+        )
+        .template(
+            "low_balance",
+            "zh",
+            NotificationTemplate::builder()
+                .title("链 {{chain_id}} 余额不足")
+                .text("中继 {{address}} 余额为 {{balance}}")
+                .build(),
+        )
+}
+
+fn variables() -> HashMap<String, String> {
+    let mut variables = HashMap::new();
+    variables.insert("chain_id".to_string(), "5".to_string());
+    variables.insert("address".to_string(), "0x<relayer>_1".to_string());
+    variables.insert("balance".to_string(), "0.1 ETH".to_string());
+    variables
+}
+
+#[test]
+fn test_render_formats() {
+    let registry = registry();
+    let rendered = registry
+        .render("low_balance", "en", &variables(), TemplateFormat::Text)
+        .unwrap();
+    assert_eq!(rendered.title.as_deref(), Some("Low balance on chain 5"));
+    assert_eq!(rendered.body, "Relayer 0x<relayer>_1 balance is 0.1 ETH");
+    let rendered = registry
+        .render("low_balance", "en", &variables(), TemplateFormat::Markdown)
+        .unwrap();
+    assert_eq!(rendered.body, "*Relayer* `0x&lt;relayer&gt;_1` balance is *0.1 ETH*");
+    let rendered = registry
+        .render("low_balance", "en", &variables(), TemplateFormat::Html)
+        .unwrap();
+    assert_eq!(rendered.body, "Relayer 0x&lt;relayer&gt;_1 balance is 0.1 ETH");
+}
+
+#[test]
+fn test_render_locale_fallback() {
+    let registry = registry();
+    let rendered = registry
+        .render("low_balance", "zh_CN", &variables(), TemplateFormat::Text)
+        .unwrap();
+    assert_eq!(rendered.title.as_deref(), Some("链 5 余额不足"));
+    let rendered = registry
+        .render("low_balance", "fr-FR", &variables(), TemplateFormat::Text)
+        .unwrap();
+    assert_eq!(rendered.title.as_deref(), Some("Low balance on chain 5"));
+    assert!(registry.get("low_balance", "EN").is_some());
+    assert_eq!(registry.default_locale(), "en");
+}
+
+#[test]
+fn test_render_errors() {
+    let registry = registry();
+    let error = registry
+        .render("stuck_transaction", "en", &variables(), TemplateFormat::Text)
+        .unwrap_err();
+    assert!(matches!(error, TemplateError::TemplateNotFound(_, _)));
+    let error = registry
+        .render("low_balance", "en", &HashMap::new(), TemplateFormat::Text)
+        .unwrap_err();
+    assert_eq!(error.to_string(), "notification template variable chain_id is missing");
+}
+
+#[test]
+fn test_render_message() {
+    let message = registry()
+        .render_message("low_balance", "en", &variables(), TemplateFormat::Text)
+        .unwrap();
+    assert_eq!(message.title.as_deref(), Some("Low balance on chain 5"));
+    assert_eq!(message.severity, NotificationSeverity::Warning);
+    assert_eq!(message.topic.as_deref(), Some("relayer"));
+    assert_eq!(message.attributes["chain_id"], "5");
+}
+
+#[test]
+fn test_render_template() {
+    let rendered = render_template("{{a}} and {{ b }} and {{c}} and {{d", |name| match name {
+        "a" => Some("1".to_string()),
+        "b" => Some("2".to_string()),
+        _ => None,
+    });
+    assert_eq!(rendered, "1 and 2 and {{c}} and {{d");
+    assert_eq!(escape_for_format("<a & b>", TemplateFormat::Html), "&lt;a &amp; b&gt;");
+    assert_eq!(
+        escape_for_format("*bold* <@here> & co", TemplateFormat::Markdown),
+        "*bold* &lt;@here&gt; &amp; co"
+    );
+    assert_eq!(escape_for_format("*bold*", TemplateFormat::Text), "*bold*");
+}
@@ -0,0 +1,156 @@
+use mockito::{Matcher, Server};
+use mystiko_notification::{
+    Notification, WebhookFormat, WebhookMessage, WebhookNotification, WebhookNotificationError,
+};
+use serde_json::json;
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_push_slack() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("POST", "/slack")
+        .match_header("content-type", "application/json")
+        .match_body(Matcher::Json(json!({ "text": "*Alert*\nHello, world!" })))
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+    let notification = WebhookNotification::new(format!("{}/slack", server.url()), WebhookFormat::Slack);
+    notification
+        .push(message(Some("Alert"), "Hello, world!"))
+        .await
+        .unwrap();
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_push_discord() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("POST", "/discord")
+        .match_body(Matcher::Json(json!({ "content": "Hello, world!" })))
+        .with_status(204)
+        .expect(1)
+        .create_async()
+        .await;
+    let notification = WebhookNotification::builder()
+        .url(format!("{}/discord", server.url()))
+        .format(WebhookFormat::Discord)
+        .build();
+    notification.push(message(None, "Hello, world!")).await.unwrap();
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_push_telegram() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("POST", "/bottoken/sendMessage")
+        .match_body(Matcher::Json(json!({
+            "chat_id": "-100123",
+            "text": "<b>Alert &amp; Warning</b>\nbalance &lt; 1",
+            "parse_mode": "HTML",
+        })))
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+    let notification = WebhookNotification::new(
+        format!("{}/bottoken/sendMessage", server.url()),
+        WebhookFormat::Telegram {
+            chat_id: "-100123".to_string(),
+        },
+    );
+    notification
+        .push(message(Some("Alert & Warning"), "balance < 1"))
+        .await
+        .unwrap();
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_push_template_with_headers() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("POST", "/custom")
+        .match_header("authorization", "Bearer token")
+        .match_body(Matcher::Json(json!({
+            "summary": "Alert",
+            "detail": "line1\n\"quoted\"",
+        })))
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+    let mut headers = HashMap::new();
+    headers.insert("Authorization".to_string(), "Bearer token".to_string());
+    let notification = WebhookNotification::builder()
+        .url(format!("{}/custom", server.url()))
+        .format(WebhookFormat::Template(
+            r#"{"summary": "{{title}}", "detail": "{{text}}"}"#.to_string(),
+        ))
+        .headers(headers)
+        .build();
+    notification
+        .push(message(Some("Alert"), "line1\n\"quoted\""))
+        .await
+        .unwrap();
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_push_error_status() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("POST", "/slack")
+        .with_status(500)
+        .with_body("internal error")
+        .expect(1)
+        .create_async()
+        .await;
+    let notification = WebhookNotification::new(format!("{}/slack", server.url()), WebhookFormat::Slack);
+    let error = notification
+        .push(WebhookMessage::from("Hello, world!".to_string()))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        WebhookNotificationError::ResponseError(500, ref body) if body == "internal error"
+    ));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_push_invalid_input() {
+    let notification = WebhookNotification::builder()
+        .url("http://127.0.0.1:1/invalid")
+        .format(WebhookFormat::Template("{{text}}".to_string()))
+        .build();
+    let error = notification.push(message(None, "not json")).await.unwrap_err();
+    assert!(matches!(error, WebhookNotificationError::SerdeJsonError(_)));
+
+    let mut headers = HashMap::new();
+    headers.insert("invalid header".to_string(), "value".to_string());
+    let notification = WebhookNotification::builder()
+        .url("http://127.0.0.1:1/invalid")
+        .headers(headers)
+        .build();
+    let error = notification.push(message(None, "hello")).await.unwrap_err();
+    assert!(matches!(error, WebhookNotificationError::HeaderError(_)));
+}
+
+#[test]
+fn test_format_discord_truncated() {
+    let body = WebhookFormat::Discord
+        .format(&message(None, &"a".repeat(3000)))
+        .unwrap();
+    assert_eq!(body["content"].as_str().unwrap().len(), 2000);
+}
+
+fn message(title: Option<&str>, text: &str) -> WebhookMessage {
+    match title {
+        Some(title) => WebhookMessage::builder().title(title).text(text).build(),
+        None => WebhookMessage::builder().text(text).build(),
+    }
+}
@@ -0,0 +1,595 @@
+use crate::rest::{
+    check_response, encode_component, encode_segments, object_key, object_path, object_prefix, request_body,
+    response_reader, MetadataTokenProvider,
+};
+use crate::{
+    parse_last_modified, range_header, AzureBlobStorageConfig, AzureCredentialsConfig, CopyRequest, CopyResponse,
+    ExistsRequest, ExistsResponse, GetRequest, GetResponse, GetStreamResponse, ListFilesRequest, ListFilesResponse,
+    ListFoldersRequest, ListFoldersResponse, ObjectMeta, PutRequest, PutResponse, PutStreamRequest, RemoveFileRequest,
+    RemoveFileResponse, RemoveFilesRequest, RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse,
+    StatRequest, StatResponse, Storage, StorageError, StorageReader,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, LAST_MODIFIED, RANGE};
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::time::Instant;
+use typed_builder::TypedBuilder;
+
+const API_VERSION: &str = "2021-08-06";
+const COPY_POLL_INTERVAL_MS: u64 = 200;
+const DEFAULT_COPY_TIMEOUT: Duration = Duration::from_secs(600);
+const DEFAULT_BLOCK_SIZE: u64 = 8 * 1024 * 1024;
+const MAX_BLOCK_SIZE: u64 = 4000 * 1024 * 1024;
+const MAX_BLOCKS: u64 = 50_000;
+const BLOCK_MAX_RETRIES: u32 = 3;
+const BLOCK_RETRY_BACKOFF_MS: u64 = 200;
+const DEFAULT_METADATA_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+const STORAGE_RESOURCE: &str = "https://storage.azure.com/";
+
+#[derive(Clone, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct AzureBlobStorage {
+    #[builder(default)]
+    pub client: Client,
+    pub endpoint: String,
+    #[builder(default = default_container())]
+    pub container: String,
+    #[builder(default)]
+    pub base: PathBuf,
+    #[builder(default, setter(strip_option))]
+    pub sas_token: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub token_provider: Option<Arc<MetadataTokenProvider>>,
+    #[builder(default = DEFAULT_BLOCK_SIZE)]
+    pub block_size: u64,
+    #[builder(default = DEFAULT_COPY_TIMEOUT)]
+    pub copy_timeout: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EnumerationResults {
+    #[serde(default)]
+    blobs: Blobs,
+    #[serde(default)]
+    next_marker: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Blobs {
+    #[serde(default)]
+    blob: Vec<Blob>,
+    #[serde(default)]
+    blob_prefix: Vec<BlobPrefix>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Blob {
+    name: String,
+    #[serde(default)]
+    properties: BlobProperties,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BlobProperties {
+    #[serde(rename = "Content-Length", default)]
+    content_length: Option<u64>,
+    #[serde(rename = "Last-Modified", default)]
+    last_modified: Option<String>,
+    #[serde(rename = "Etag", default)]
+    etag: Option<String>,
+    #[serde(rename = "Content-Type", default)]
+    content_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BlobPrefix {
+    name: String,
+}
+
+#[async_trait]
+impl Storage for AzureBlobStorage {
+    async fn list_folders(&self, request: ListFoldersRequest) -> Result<ListFoldersResponse> {
+        let mut folders = vec![];
+        let mut marker: Option<String> = None;
+        loop {
+            let page = self.list_page(&request.path, true, None, marker).await?;
+            for prefix in page.blobs.blob_prefix {
+                folders.push(object_path(&self.base, &prefix.name)?);
+            }
+            if page.next_marker.is_none() {
+                break;
+            } else {
+                marker = page.next_marker;
+            }
+        }
+        Ok(ListFoldersResponse::builder().folders(folders).build())
+    }
+
+    async fn list_files(&self, request: ListFilesRequest) -> Result<ListFilesResponse> {
+        let mut paths = vec![];
+        let mut metadata = vec![];
+        let mut marker: Option<String> = None;
+        loop {
+            let (objects, next_marker) = self.list_objects_page(&request, marker).await?;
+            paths.extend(objects.iter().map(|object| object.path.clone()));
+            if request.with_metadata {
+                metadata.extend(objects);
+            }
+            if next_marker.is_none() {
+                break;
+            } else {
+                marker = next_marker;
+            }
+        }
+        Ok(ListFilesResponse::builder().files(paths).objects(metadata).build())
+    }
+
+    fn list_files_stream(&self, request: ListFilesRequest) -> BoxStream<'_, Result<ObjectMeta>> {
+        stream::try_unfold(Some(None), move |marker: Option<Option<String>>| {
+            let request = request.clone();
+            async move {
+                if let Some(marker) = marker {
+                    let (objects, next_marker) = self.list_objects_page(&request, marker).await?;
+                    let objects = stream::iter(objects.into_iter().map(Ok));
+                    Ok::<_, anyhow::Error>(Some((objects, next_marker.map(Some))))
+                } else {
+                    Ok(None)
+                }
+            }
+        })
+        .try_flatten()
+        .boxed()
+    }
+
+    async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse> {
+        let exists = self.head_meta(&request.path).await?.is_some();
+        Ok(ExistsResponse::builder().exists(exists).build())
+    }
+
+    async fn stat(&self, request: StatRequest) -> Result<StatResponse> {
+        let meta = self
+            .head_meta(&request.path)
+            .await?
+            .ok_or_else(|| StorageError::NotFound(request.path.to_string_lossy().to_string()))?;
+        Ok(StatResponse::builder().meta(meta).build())
+    }
+
+    async fn get(&self, request: GetRequest) -> Result<GetResponse> {
+        let data = self.get_stream(request).await?.read_to_end().await?;
+        Ok(GetResponse::builder().data(data).build())
+    }
+
+    async fn put(&self, request: PutRequest) -> Result<PutResponse> {
+        let length = request.data.len() as u64;
+        let put_request = PutStreamRequest {
+            path: request.path,
+            reader: Box::pin(Cursor::new(request.data)) as StorageReader,
+            length,
+            overwrite: request.overwrite,
+            content_type: request.content_type,
+            cache_control: request.cache_control,
+            acl: request.acl,
+            if_match: request.if_match,
+            if_none_match: request.if_none_match,
+        };
+        self.put_stream(put_request).await
+    }
+
+    async fn get_stream(&self, request: GetRequest) -> Result<GetStreamResponse> {
+        if request.length == Some(0) {
+            return Ok(GetStreamResponse::from(vec![]));
+        }
+        let mut builder = self.request(Method::GET, self.blob_url(&request.path)).await?;
+        if let Some(range) = range_header(&request) {
+            builder = builder.header(RANGE, range);
+        }
+        let response = builder.send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Err(StorageError::NotFound(request.path.to_string_lossy().to_string()).into()),
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(GetStreamResponse::from(vec![])),
+            _ => {
+                let response = check_response(response).await?;
+                let length = response.content_length();
+                Ok(GetStreamResponse {
+                    reader: response_reader(response),
+                    length,
+                })
+            }
+        }
+    }
+
+    // blob access is governed by the container rather than per object, so acl is not applied.
+    // Blobs larger than block_size are staged block by block and committed with a block list.
+    async fn put_stream(&self, request: PutStreamRequest) -> Result<PutResponse> {
+        let conditional = request.if_match.is_some() || request.if_none_match.is_some();
+        let block_size = self.block_size.clamp(1, MAX_BLOCK_SIZE);
+        let mut builder = if request.length > block_size {
+            let block_ids = self
+                .stage_blocks(&request.path, request.reader, request.length, block_size)
+                .await?;
+            let body = block_list(&block_ids);
+            self.request(Method::PUT, self.blob_url(&request.path))
+                .await?
+                .query(&[("comp", "blocklist")])
+                .header(CONTENT_LENGTH, body.len())
+                .body(body)
+        } else {
+            self.request(Method::PUT, self.blob_url(&request.path))
+                .await?
+                .header("x-ms-blob-type", "BlockBlob")
+                .header(CONTENT_LENGTH, request.length)
+                .body(request_body(request.reader, request.length))
+        };
+        if let Some(content_type) = request.content_type {
+            builder = builder.header("x-ms-blob-content-type", content_type);
+        }
+        if let Some(cache_control) = request.cache_control {
+            builder = builder.header("x-ms-blob-cache-control", cache_control);
+        }
+        if let Some(if_match) = request.if_match {
+            builder = builder.header(IF_MATCH, if_match);
+        }
+        match request.if_none_match {
+            Some(if_none_match) => builder = builder.header(IF_NONE_MATCH, if_none_match),
+            None if !conditional && !request.overwrite => builder = builder.header(IF_NONE_MATCH, "*"),
+            None => {}
+        }
+        let response = builder.send().await?;
+        if matches!(
+            response.status(),
+            StatusCode::CONFLICT | StatusCode::PRECONDITION_FAILED
+        ) {
+            if conditional {
+                return Err(StorageError::PreconditionFailed(request.path.to_string_lossy().to_string()).into());
+            }
+            let etag = self.head_meta(&request.path).await?.and_then(|meta| meta.etag);
+            return Ok(PutResponse { etag });
+        }
+        let response = check_response(response).await?;
+        Ok(PutResponse {
+            etag: header_value(response.headers(), ETAG.as_str()),
+        })
+    }
+
+    async fn copy(&self, request: CopyRequest) -> Result<CopyResponse> {
+        let mut builder = self
+            .request(Method::PUT, self.blob_url(&request.to))
+            .await?
+            .header("x-ms-copy-source", self.blob_url(&request.from))
+            .header(CONTENT_LENGTH, 0);
+        if !request.overwrite {
+            builder = builder.header(IF_NONE_MATCH, "*");
+        }
+        let response = builder.send().await?;
+        let response = match response.status() {
+            StatusCode::NOT_FOUND => {
+                return Err(StorageError::NotFound(request.from.to_string_lossy().to_string()).into());
+            }
+            StatusCode::CONFLICT | StatusCode::PRECONDITION_FAILED => {
+                return Err(StorageError::AlreadyExists(request.to.to_string_lossy().to_string()).into());
+            }
+            _ => check_response(response).await?,
+        };
+        let mut headers = response.headers().clone();
+        let deadline = Instant::now() + self.copy_timeout;
+        while header_value(&headers, "x-ms-copy-status").as_deref() == Some("pending") {
+            if Instant::now() >= deadline {
+                if let Some(copy_id) = header_value(&headers, "x-ms-copy-id") {
+                    self.abort_copy(&request.to, &copy_id).await?;
+                }
+                return Err(anyhow::anyhow!(
+                    "copy from {} to {} did not complete within {:?}",
+                    request.from.to_string_lossy(),
+                    request.to.to_string_lossy(),
+                    self.copy_timeout
+                ));
+            }
+            tokio::time::sleep(Duration::from_millis(COPY_POLL_INTERVAL_MS)).await;
+            let response = self
+                .request(Method::HEAD, self.blob_url(&request.to))
+                .await?
+                .send()
+                .await?;
+            headers = check_response(response).await?.headers().clone();
+        }
+        match header_value(&headers, "x-ms-copy-status").as_deref() {
+            Some("success") | None => Ok(CopyResponse {
+                etag: header_value(&headers, ETAG.as_str()),
+            }),
+            Some(status) => Err(anyhow::anyhow!(
+                "copy from {} to {} ended with status {}",
+                request.from.to_string_lossy(),
+                request.to.to_string_lossy(),
+                status
+            )),
+        }
+    }
+
+    async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse> {
+        for path in request.paths.iter() {
+            let response = self.request(Method::DELETE, self.blob_url(path)).await?.send().await?;
+            if response.status() != StatusCode::NOT_FOUND {
+                check_response(response).await?;
+            }
+        }
+        Ok(RemoveFilesResponse::builder().build())
+    }
+
+    async fn remove_file(&self, request: RemoveFileRequest) -> Result<RemoveFileResponse> {
+        self.remove_files(vec![request.path].into()).await?;
+        Ok(RemoveFileResponse::builder().build())
+    }
+
+    async fn remove_folder(&self, request: RemoveFolderRequest) -> Result<RemoveFolderResponse> {
+        let objects = self.list_files(request.path.clone().into()).await?;
+        if request.non_recursively && !objects.files.is_empty() {
+            return Err(anyhow::anyhow!(
+                "folder {} is not empty",
+                request.path.to_string_lossy()
+            ));
+        }
+        self.remove_files(objects.files.into()).await?;
+        Ok(RemoveFolderResponse::builder().build())
+    }
+}
+
+impl AzureBlobStorage {
+    pub fn from_config(config: &AzureBlobStorageConfig) -> Result<Self> {
+        let client = Client::new();
+        let (sas_token, token_provider) = match &config.credentials {
+            AzureCredentialsConfig::Anonymous => (None, None),
+            AzureCredentialsConfig::Sas { token } => (Some(token.clone()), None),
+            AzureCredentialsConfig::ManagedIdentity { endpoint, client_id } => {
+                let mut url = format!(
+                    "{}?api-version=2018-02-01&resource={}",
+                    endpoint.as_deref().unwrap_or(DEFAULT_METADATA_ENDPOINT),
+                    encode_component(STORAGE_RESOURCE)
+                );
+                if let Some(client_id) = client_id {
+                    url.push_str(&format!("&client_id={}", encode_component(client_id)));
+                }
+                let provider = MetadataTokenProvider::builder()
+                    .client(client.clone())
+                    .url(url)
+                    .headers(vec![(String::from("Metadata"), String::from("true"))])
+                    .build();
+                (None, Some(Arc::new(provider)))
+            }
+        };
+        Ok(AzureBlobStorage {
+            client,
+            endpoint: config.endpoint(),
+            container: config.container.clone(),
+            base: PathBuf::from(&config.base),
+            sas_token,
+            token_provider,
+            block_size: config.block_size,
+            copy_timeout: Duration::from_secs(config.copy_timeout_seconds),
+        })
+    }
+
+    async fn request(&self, method: Method, url: String) -> Result<RequestBuilder> {
+        let builder = self.client.request(method, url).header("x-ms-version", API_VERSION);
+        Ok(match &self.token_provider {
+            Some(token_provider) => builder.bearer_auth(token_provider.token().await?),
+            None => builder,
+        })
+    }
+
+    async fn stage_blocks(
+        &self,
+        path: &Path,
+        mut reader: StorageReader,
+        length: u64,
+        block_size: u64,
+    ) -> Result<Vec<String>> {
+        let blocks = length.div_ceil(block_size);
+        if blocks > MAX_BLOCKS {
+            return Err(anyhow::anyhow!(
+                "{} needs {} blocks of {} bytes, more than the {} allowed",
+                path.to_string_lossy(),
+                blocks,
+                block_size,
+                MAX_BLOCKS
+            ));
+        }
+        let mut block_ids = vec![];
+        for index in 0..blocks {
+            let size = (length - index * block_size).min(block_size);
+            let mut data = vec![0u8; size as usize];
+            reader.read_exact(&mut data).await?;
+            let block_id = BASE64.encode(format!("block-{:06}", index));
+            self.put_block(path, &block_id, data).await?;
+            block_ids.push(block_id);
+        }
+        Ok(block_ids)
+    }
+
+    async fn put_block(&self, path: &Path, block_id: &str, data: Vec<u8>) -> Result<()> {
+        let mut retries = 0;
+        loop {
+            let result = self
+                .request(Method::PUT, self.blob_url(path))
+                .await?
+                .query(&[("comp", "block"), ("blockid", block_id)])
+                .header(CONTENT_LENGTH, data.len())
+                .body(data.clone())
+                .send()
+                .await;
+            match result {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) if !is_retryable(response.status()) || retries >= BLOCK_MAX_RETRIES => {
+                    check_response(response).await?;
+                    return Err(anyhow::anyhow!("failed to stage block for {}", path.to_string_lossy()));
+                }
+                Err(error) if retries >= BLOCK_MAX_RETRIES => return Err(error.into()),
+                _ => {
+                    let backoff = BLOCK_RETRY_BACKOFF_MS.saturating_mul(1 << retries);
+                    tokio::time::sleep(Duration::from_millis(backoff)).await;
+                    retries += 1;
+                }
+            }
+        }
+    }
+
+    async fn abort_copy(&self, path: &Path, copy_id: &str) -> Result<()> {
+        let response = self
+            .request(Method::PUT, self.blob_url(path))
+            .await?
+            .query(&[("comp", "copy"), ("copyid", copy_id)])
+            .header("x-ms-copy-action", "abort")
+            .header(CONTENT_LENGTH, 0)
+            .send()
+            .await?;
+        check_response(response).await?;
+        Ok(())
+    }
+
+    fn container_url(&self) -> String {
+        self.signed(format!(
+            "{}/{}",
+            self.endpoint.trim_end_matches('/'),
+            encode_segments(&self.container)
+        ))
+    }
+
+    fn blob_url(&self, path: &Path) -> String {
+        self.signed(format!(
+            "{}/{}/{}",
+            self.endpoint.trim_end_matches('/'),
+            encode_segments(&self.container),
+            encode_segments(&object_key(&self.base, path))
+        ))
+    }
+
+    fn signed(&self, url: String) -> String {
+        match &self.sas_token {
+            Some(sas_token) => format!("{}?{}", url, sas_token.trim_start_matches('?')),
+            None => url,
+        }
+    }
+
+    async fn list_page(
+        &self,
+        path: &Path,
+        delimiter: bool,
+        page_size: Option<i64>,
+        marker: Option<String>,
+    ) -> Result<EnumerationResults> {
+        let mut query = vec![
+            ("restype", "container".to_string()),
+            ("comp", "list".to_string()),
+            ("prefix", object_prefix(&self.base, path)),
+        ];
+        if delimiter {
+            query.push(("delimiter", "/".to_string()));
+        }
+        if let Some(page_size) = page_size {
+            query.push(("maxresults", page_size.to_string()));
+        }
+        if let Some(marker) = marker {
+            query.push(("marker", marker));
+        }
+        let response = self
+            .request(Method::GET, self.container_url())
+            .await?
+            .query(&query)
+            .send()
+            .await?;
+        let text = check_response(response).await?.text().await?;
+        let mut results = quick_xml::de::from_str::<EnumerationResults>(&text)?;
+        results.next_marker = results.next_marker.filter(|marker| !marker.is_empty());
+        Ok(results)
+    }
+
+    async fn list_objects_page(
+        &self,
+        request: &ListFilesRequest,
+        marker: Option<String>,
+    ) -> Result<(Vec<ObjectMeta>, Option<String>)> {
+        let page = self
+            .list_page(&request.path, request.non_recursively, request.page_size, marker)
+            .await?;
+        let mut objects = vec![];
+        for blob in page.blobs.blob {
+            objects.push(ObjectMeta {
+                path: object_path(&self.base, &blob.name)?,
+                size: blob.properties.content_length,
+                last_modified: blob.properties.last_modified.as_deref().and_then(parse_last_modified),
+                etag: blob.properties.etag,
+                content_type: blob.properties.content_type,
+            });
+        }
+        Ok((objects, page.next_marker))
+    }
+
+    async fn head_meta(&self, path: &Path) -> Result<Option<ObjectMeta>> {
+        let response = self.request(Method::HEAD, self.blob_url(path)).await?.send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check_response(response).await?;
+        let headers = response.headers();
+        Ok(Some(ObjectMeta {
+            path: path.to_path_buf(),
+            size: header_value(headers, CONTENT_LENGTH.as_str()).and_then(|size| size.parse().ok()),
+            last_modified: header_value(headers, LAST_MODIFIED.as_str()).and_then(|value| parse_last_modified(&value)),
+            etag: header_value(headers, ETAG.as_str()),
+            content_type: header_value(headers, CONTENT_TYPE.as_str()),
+        }))
+    }
+}
+
+impl std::fmt::Debug for AzureBlobStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AzureBlobStorage")
+            .field("endpoint", &self.endpoint)
+            .field("container", &self.container)
+            .field("base", &self.base)
+            .field("sas_token", &self.sas_token.as_ref().map(|_| "<redacted>"))
+            .field("token_provider", &self.token_provider)
+            .field("block_size", &self.block_size)
+            .field("copy_timeout", &self.copy_timeout)
+            .finish()
+    }
+}
+
+fn block_list(block_ids: &[String]) -> String {
+    let blocks = block_ids
+        .iter()
+        .map(|block_id| format!("<Latest>{}</Latest>", block_id))
+        .collect::<String>();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?><BlockList>{}</BlockList>",
+        blocks
+    )
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+fn default_container() -> String {
+    String::from("static")
+}
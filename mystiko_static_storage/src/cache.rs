@@ -1,12 +1,20 @@
 use crate::{
-    ExistsRequest, ExistsResponse, FileStorage, GetRequest, GetResponse, ListFilesRequest, ListFilesResponse,
-    ListFoldersRequest, ListFoldersResponse, PutRequest, PutResponse, RemoveFileRequest, RemoveFileResponse,
-    RemoveFilesRequest, RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse, Storage, StorageCacheConfig,
+    CopyRequest, CopyResponse, ExistsRequest, ExistsResponse, FileStorage, GetRequest, GetResponse, GetStreamResponse,
+    ListFilesRequest, ListFilesResponse, ListFoldersRequest, ListFoldersResponse, ObjectMeta, PutRequest, PutResponse,
+    PutStreamRequest, RemoveFileRequest, RemoveFileResponse, RemoveFilesRequest, RemoveFilesResponse,
+    RemoveFolderRequest, RemoveFolderResponse, RenameRequest, RenameResponse, StatRequest, StatResponse, Storage,
+    StorageCacheConfig,
 };
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::fs;
+use tokio::sync::Notify;
 use typed_builder::TypedBuilder;
 
 #[derive(TypedBuilder)]
@@ -14,6 +22,12 @@ use typed_builder::TypedBuilder;
 pub struct CachedStorageOptions<P: AsRef<Path> + Send + Clone, S: Storage = Box<dyn Storage>> {
     pub cache_folder: P,
     pub raw: Arc<S>,
+    #[builder(default, setter(strip_option))]
+    pub max_size: Option<u64>,
+    #[builder(default, setter(strip_option))]
+    pub ttl: Option<Duration>,
+    #[builder(default = false)]
+    pub revalidate: bool,
 }
 
 #[derive(TypedBuilder)]
@@ -21,6 +35,40 @@ pub struct CachedStorageOptions<P: AsRef<Path> + Send + Clone, S: Storage = Box<
 pub struct CachedStorage<S: Storage = Box<dyn Storage>> {
     cache: FileStorage,
     raw: Arc<S>,
+    #[builder(default, setter(strip_option))]
+    max_size: Option<u64>,
+    #[builder(default, setter(strip_option))]
+    ttl: Option<Duration>,
+    #[builder(default = false)]
+    revalidate: bool,
+    #[builder(default, setter(skip))]
+    index: Arc<Mutex<CacheIndex>>,
+    #[builder(default, setter(skip))]
+    evicted: Notify,
+}
+
+// reads pin a path until its cached file is open; eviction skips pinned paths and pins wait for
+// the paths being evicted, so a file is never removed underneath a read.
+#[derive(Debug, Default)]
+struct CacheIndex {
+    entries: HashMap<PathBuf, CacheEntry>,
+    total_size: u64,
+    clock: u64,
+    pins: HashMap<PathBuf, usize>,
+    evicting: HashSet<PathBuf>,
+}
+
+struct CachePin {
+    index: Arc<Mutex<CacheIndex>>,
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    size: u64,
+    etag: Option<String>,
+    cached_at: DateTime<Utc>,
+    last_access: u64,
 }
 
 impl<S> CachedStorage<S>
@@ -34,10 +82,30 @@ where
     {
         let options = options.into();
         let cache = FileStorage::new(options.cache_folder).await?;
-        Ok(Self {
+        let storage = Self {
             cache,
             raw: options.raw,
-        })
+            max_size: options.max_size,
+            ttl: options.ttl,
+            revalidate: options.revalidate,
+            index: Arc::new(Mutex::new(CacheIndex::default())),
+            evicted: Notify::new(),
+        };
+        storage.load_index().await?;
+        Ok(storage)
+    }
+
+    pub async fn warm<P: AsRef<Path>>(&self, prefix: P) -> Result<usize> {
+        let files = self.raw.list_files(prefix.as_ref().into()).await?.files;
+        let mut warmed = 0;
+        for path in files.iter() {
+            if !self.is_cached(path).await? {
+                self.fetch(path).await?;
+                self.evict(path).await?;
+                warmed += 1;
+            }
+        }
+        Ok(warmed)
     }
 }
 
@@ -54,37 +122,144 @@ where
         self.raw.list_files(request).await
     }
 
+    fn list_files_stream(&self, request: ListFilesRequest) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.raw.list_files_stream(request)
+    }
+
     async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse> {
         self.raw.exists(request).await
     }
 
+    async fn stat(&self, request: StatRequest) -> Result<StatResponse> {
+        self.raw.stat(request).await
+    }
+
     async fn get(&self, request: GetRequest) -> Result<GetResponse> {
         if request.no_cache {
-            self.raw.get(request).await
-        } else {
-            let exists_request: ExistsRequest = request.path.clone().into();
-            let exists_response = self.cache.exists(exists_request).await?;
-            if exists_response.exists {
-                self.cache.get(request).await
-            } else {
-                let path = request.path.clone();
-                let raw_response = self.raw.get(request).await?;
-                let put_request: PutRequest = PutRequest::builder()
-                    .path(path)
-                    .data(raw_response.data.clone())
-                    .overwrite(true)
-                    .build();
-                self.cache.put(put_request).await?;
-                Ok(raw_response)
-            }
+            return self.raw.get(request).await;
         }
+        let path = request.path.clone();
+        let response = {
+            let _pin = self.pin(&path).await?;
+            if !self.is_cached(&path).await? {
+                self.fetch(&path).await?;
+            }
+            self.cache.get(request).await?
+        };
+        self.evict(&path).await?;
+        Ok(response)
     }
 
     async fn put(&self, request: PutRequest) -> Result<PutResponse> {
-        let mut cache_put = request.clone();
-        cache_put.overwrite = true;
+        let path = request.path.clone();
+        let create_only = !request.overwrite && request.if_match.is_none() && request.if_none_match.is_none();
+        if create_only && self.raw.exists(path.clone().into()).await?.exists {
+            return self.raw.put(request).await;
+        }
+        let cache_put = PutRequest {
+            overwrite: true,
+            if_match: None,
+            if_none_match: None,
+            ..request.clone()
+        };
+        let size = request.data.len() as u64;
         self.cache.put(cache_put).await?;
-        self.raw.put(request).await
+        match self.raw.put(request).await {
+            Ok(response) => {
+                self.record(&path, size, response.etag.clone()).await?;
+                Ok(response)
+            }
+            Err(error) => {
+                self.forget(&path).await?;
+                Err(error)
+            }
+        }
+    }
+
+    async fn get_stream(&self, request: GetRequest) -> Result<GetStreamResponse> {
+        if request.no_cache {
+            return self.raw.get_stream(request).await;
+        }
+        let path = request.path.clone();
+        let response = {
+            let _pin = self.pin(&path).await?;
+            if !self.is_cached(&path).await? {
+                self.fetch(&path).await?;
+            }
+            self.cache.get_stream(request).await?
+        };
+        self.evict(&path).await?;
+        Ok(response)
+    }
+
+    async fn put_stream(&self, request: PutStreamRequest) -> Result<PutResponse> {
+        let PutStreamRequest {
+            path,
+            reader,
+            length,
+            overwrite,
+            content_type,
+            cache_control,
+            acl,
+            if_match,
+            if_none_match,
+        } = request;
+        let create_only = !overwrite && if_match.is_none() && if_none_match.is_none();
+        if create_only && self.raw.exists(path.clone().into()).await?.exists {
+            let raw_put = PutStreamRequest {
+                path,
+                reader,
+                length,
+                overwrite,
+                content_type,
+                cache_control,
+                acl,
+                if_match,
+                if_none_match,
+            };
+            return self.raw.put_stream(raw_put).await;
+        }
+        let cache_put = PutStreamRequest::builder()
+            .path(path.clone())
+            .reader(reader)
+            .length(length)
+            .overwrite(true)
+            .build();
+        self.cache.put_stream(cache_put).await?;
+        let cached = self.cache.get_stream(path.clone().into()).await?;
+        let raw_put = PutStreamRequest {
+            path: path.clone(),
+            reader: cached.reader,
+            length,
+            overwrite,
+            content_type,
+            cache_control,
+            acl,
+            if_match,
+            if_none_match,
+        };
+        match self.raw.put_stream(raw_put).await {
+            Ok(response) => {
+                self.record(&path, length, response.etag.clone()).await?;
+                Ok(response)
+            }
+            Err(error) => {
+                self.forget(&path).await?;
+                Err(error)
+            }
+        }
+    }
+
+    async fn copy(&self, request: CopyRequest) -> Result<CopyResponse> {
+        let response = self.raw.copy(request.clone()).await?;
+        self.sync_cached(&request.from, &request.to, false).await?;
+        Ok(response)
+    }
+
+    async fn rename(&self, request: RenameRequest) -> Result<RenameResponse> {
+        let response = self.raw.rename(request.clone()).await?;
+        self.sync_cached(&request.from, &request.to, true).await?;
+        Ok(response)
     }
 
     async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse> {
@@ -98,7 +273,13 @@ where
             }
         }
         if !existing_cached_files.is_empty() {
-            self.cache.remove_files(existing_cached_files.into()).await?;
+            self.cache.remove_files(existing_cached_files.clone().into()).await?;
+        }
+        {
+            let mut index = self.index()?;
+            for path in existing_cached_files.iter() {
+                index.remove(path);
+            }
         }
         self.raw.remove_files(request).await
     }
@@ -108,7 +289,7 @@ where
         let exists_request: ExistsRequest = path.clone().into();
         let exists_response = self.cache.exists(exists_request).await?;
         if exists_response.exists {
-            self.cache.remove_file(path.into()).await?;
+            self.forget(&path).await?;
         }
         self.raw.remove_file(request).await
     }
@@ -118,7 +299,8 @@ where
         let exists_request: ExistsRequest = path.clone().into();
         let exists_response = self.cache.exists(exists_request).await?;
         if exists_response.exists {
-            self.cache.remove_folder(path.into()).await?;
+            self.cache.remove_folder(path.clone().into()).await?;
+            self.index()?.remove_folder(&path);
         }
         self.raw.remove_folder(request).await
     }
@@ -128,6 +310,169 @@ impl<S> CachedStorage<S>
 where
     S: Storage,
 {
+    async fn sync_cached(&self, from: &Path, to: &Path, rename: bool) -> Result<()> {
+        if self.cache.exists(from.into()).await?.exists {
+            if rename {
+                let rename_request = RenameRequest::builder().from(from).to(to).overwrite(true).build();
+                self.cache.rename(rename_request).await?;
+            } else {
+                let copy_request = CopyRequest::builder().from(from).to(to).overwrite(true).build();
+                self.cache.copy(copy_request).await?;
+            }
+            let etag = self.index()?.copy(from, to, rename);
+            self.cache.set_source_etag(to, etag.as_deref()).await?;
+            self.evict(to).await?;
+        } else if self.cache.exists(to.into()).await?.exists {
+            self.forget(to).await?;
+        }
+        Ok(())
+    }
+
+    async fn load_index(&self) -> Result<()> {
+        let files = self.cache.list_files(PathBuf::default().into()).await?.files;
+        for path in files.into_iter() {
+            let metadata = fs::metadata(self.cache.base.join(&path)).await?;
+            let etag = self.cache.source_etag(&path).await;
+            let cached_at = metadata.modified().map_or_else(|_| Utc::now(), DateTime::<Utc>::from);
+            self.index()?.insert(path, metadata.len(), etag, cached_at);
+        }
+        self.evict(Path::new("")).await
+    }
+
+    // an entry is served while it is younger than the ttl; without a ttl, revalidate checks every read.
+    async fn is_cached(&self, path: &Path) -> Result<bool> {
+        let entry = self.index()?.touch(path);
+        if !self.cache.exists(path.into()).await?.exists {
+            self.index()?.remove(path);
+            return Ok(false);
+        }
+        let entry = match entry {
+            Some(entry) => entry,
+            None => self.adopt(path).await?,
+        };
+        let expired = match self.ttl {
+            Some(ttl) => Utc::now()
+                .signed_duration_since(entry.cached_at)
+                .to_std()
+                .is_ok_and(|age| age >= ttl),
+            None => self.revalidate,
+        };
+        if !expired {
+            return Ok(true);
+        }
+        if self.revalidate && entry.etag.is_some() && self.raw_etag(path).await == entry.etag {
+            self.index()?.renew(path);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    async fn adopt(&self, path: &Path) -> Result<CacheEntry> {
+        let meta = self.cache.stat(path.into()).await?.meta;
+        let etag = self.cache.source_etag(path).await;
+        let cached_at = meta.last_modified.unwrap_or_else(Utc::now);
+        let mut index = self.index()?;
+        index.insert(path.to_path_buf(), meta.size.unwrap_or_default(), etag, cached_at);
+        index
+            .touch(path)
+            .ok_or_else(|| anyhow::anyhow!("{} is not indexed", path.to_string_lossy()))
+    }
+
+    async fn fetch(&self, path: &Path) -> Result<()> {
+        let etag = self.raw_etag(path).await;
+        let raw_response = self.raw.get_stream(path.into()).await?;
+        let size = match raw_response.length {
+            Some(length) => {
+                let put_request = PutStreamRequest::builder()
+                    .path(path)
+                    .reader(raw_response.reader)
+                    .length(length)
+                    .overwrite(true)
+                    .build();
+                self.cache.put_stream(put_request).await?;
+                length
+            }
+            None => {
+                let data = raw_response.read_to_end().await?;
+                let size = data.len() as u64;
+                let put_request = PutRequest::builder().path(path).data(data).overwrite(true).build();
+                self.cache.put(put_request).await?;
+                size
+            }
+        };
+        self.remember(path, size, etag).await
+    }
+
+    async fn raw_etag(&self, path: &Path) -> Option<String> {
+        if !self.revalidate {
+            return None;
+        }
+        self.raw
+            .stat(path.into())
+            .await
+            .ok()
+            .and_then(|response| response.meta.etag)
+    }
+
+    async fn record(&self, path: &Path, size: u64, etag: Option<String>) -> Result<()> {
+        self.remember(path, size, etag).await?;
+        self.evict(path).await
+    }
+
+    async fn remember(&self, path: &Path, size: u64, etag: Option<String>) -> Result<()> {
+        self.cache.set_source_etag(path, etag.as_deref()).await?;
+        self.index()?.insert(path.to_path_buf(), size, etag, Utc::now());
+        Ok(())
+    }
+
+    async fn forget(&self, path: &Path) -> Result<()> {
+        self.cache.remove_file(path.into()).await?;
+        self.index()?.remove(path);
+        Ok(())
+    }
+
+    async fn evict(&self, keep: &Path) -> Result<()> {
+        let Some(max_size) = self.max_size else {
+            return Ok(());
+        };
+        let victims = self.index()?.evict(max_size, keep);
+        if victims.is_empty() {
+            return Ok(());
+        }
+        let removed = self.cache.remove_files(victims.clone().into()).await;
+        {
+            let mut index = self.index()?;
+            for path in victims.iter() {
+                index.evicting.remove(path);
+            }
+        }
+        self.evicted.notify_waiters();
+        removed.map(|_| ())
+    }
+
+    async fn pin(&self, path: &Path) -> Result<CachePin> {
+        loop {
+            let evicted = self.evicted.notified();
+            {
+                let mut index = self.index()?;
+                if !index.evicting.contains(path) {
+                    *index.pins.entry(path.to_path_buf()).or_default() += 1;
+                    return Ok(CachePin {
+                        index: self.index.clone(),
+                        path: path.to_path_buf(),
+                    });
+                }
+            }
+            evicted.await;
+        }
+    }
+
+    fn index(&self) -> Result<MutexGuard<'_, CacheIndex>> {
+        self.index
+            .lock()
+            .map_err(|err| anyhow::anyhow!("cache index lock poisoned: {}", err))
+    }
+
     pub async fn from_config(config: &StorageCacheConfig, raw: S) -> Result<Self> {
         let cache_dir = dirs::data_dir()
             .ok_or(anyhow::anyhow!("cannot detect the data directory of current OS"))?
@@ -135,10 +480,118 @@ where
             .join("datapacker")
             .join("cache");
         let cache_dir = config.path.as_ref().map(PathBuf::from).unwrap_or(cache_dir);
-        let options = CachedStorageOptions::<PathBuf, S>::builder()
-            .raw(raw)
-            .cache_folder(cache_dir)
-            .build();
+        let options = CachedStorageOptions::<PathBuf, S> {
+            cache_folder: cache_dir,
+            raw: Arc::new(raw),
+            max_size: config.max_size,
+            ttl: config.ttl_seconds.map(Duration::from_secs),
+            revalidate: config.revalidate,
+        };
         Self::new(options).await
     }
 }
+
+impl CacheIndex {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn insert(&mut self, path: PathBuf, size: u64, etag: Option<String>, cached_at: DateTime<Utc>) {
+        self.remove(&path);
+        let last_access = self.tick();
+        self.total_size += size;
+        let entry = CacheEntry {
+            size,
+            etag,
+            cached_at,
+            last_access,
+        };
+        self.entries.insert(path, entry);
+    }
+
+    fn touch(&mut self, path: &Path) -> Option<CacheEntry> {
+        let last_access = self.tick();
+        let entry = self.entries.get_mut(path)?;
+        entry.last_access = last_access;
+        Some(entry.clone())
+    }
+
+    fn renew(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.cached_at = Utc::now();
+        }
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.total_size -= entry.size;
+        }
+    }
+
+    fn remove_folder(&mut self, path: &Path) {
+        let paths = self
+            .entries
+            .keys()
+            .filter(|key| key.starts_with(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        for path in paths.iter() {
+            self.remove(path);
+        }
+    }
+
+    fn copy(&mut self, from: &Path, to: &Path, rename: bool) -> Option<String> {
+        let Some(entry) = self.entries.get(from).cloned() else {
+            self.remove(to);
+            return None;
+        };
+        if rename {
+            self.remove(from);
+        }
+        self.insert(to.to_path_buf(), entry.size, entry.etag.clone(), entry.cached_at);
+        entry.etag
+    }
+
+    // removes the least recently used unpinned entries above max_size and marks them as evicting.
+    fn evict(&mut self, max_size: u64, keep: &Path) -> Vec<PathBuf> {
+        let victims = self.victims(max_size, keep);
+        for path in victims.iter() {
+            self.remove(path);
+            self.evicting.insert(path.clone());
+        }
+        victims
+    }
+
+    fn victims(&self, max_size: u64, keep: &Path) -> Vec<PathBuf> {
+        let mut entries = self
+            .entries
+            .iter()
+            .filter(|(path, _)| path.as_path() != keep && !self.pins.contains_key(path.as_path()))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(_, entry)| entry.last_access);
+        let mut total_size = self.total_size;
+        let mut victims = vec![];
+        for (path, entry) in entries.into_iter() {
+            if total_size <= max_size {
+                break;
+            }
+            total_size -= entry.size;
+            victims.push(path.clone());
+        }
+        victims
+    }
+}
+
+impl Drop for CachePin {
+    fn drop(&mut self) {
+        if let Ok(mut index) = self.index.lock() {
+            if let Some(pins) = index.pins.get_mut(&self.path) {
+                *pins -= 1;
+                if *pins == 0 {
+                    index.pins.remove(&self.path);
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,32 @@
-use serde::{Deserialize, Serialize};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
 use typed_builder::TypedBuilder;
 
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct StorageConfig {
+    #[builder(default)]
+    #[serde(default, rename = "type")]
+    pub storage_type: StorageType,
+    #[builder(default)]
+    #[serde(default)]
+    pub s3: S3StorageConfig,
+    #[builder(default)]
+    #[serde(default)]
+    pub file: FileStorageConfig,
+    #[builder(default, setter(strip_option))]
+    #[serde(default)]
+    pub gcs: Option<GcsStorageConfig>,
+    #[builder(default, setter(strip_option))]
+    #[serde(default)]
+    pub azure_blob: Option<AzureBlobStorageConfig>,
+    #[builder(default)]
+    #[serde(default)]
+    pub cache: StorageCacheConfig,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct FileStorageConfig {
@@ -21,13 +47,139 @@ pub struct S3StorageConfig {
     #[builder(default = default_s3_base())]
     #[serde(default = "default_s3_base")]
     pub base: String,
+    #[builder(default, setter(strip_option))]
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[builder(default)]
+    #[serde(default)]
+    pub credentials: S3CredentialsConfig,
+    #[builder(default)]
+    #[serde(default)]
+    pub multipart: S3MultipartConfig,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "lowercase")]
+pub enum S3CredentialsConfig {
+    #[default]
+    Default,
+    #[serde(rename = "env")]
+    Environment,
+    Profile {
+        #[serde(default)]
+        profile: Option<String>,
+    },
+    Static {
+        access_key_id: String,
+        secret_access_key: String,
+        #[serde(default)]
+        session_token: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct S3MultipartConfig {
+    #[builder(default = default_multipart_threshold())]
+    #[serde(default = "default_multipart_threshold")]
+    pub threshold: u64,
+    #[builder(default = default_multipart_part_size())]
+    #[serde(default = "default_multipart_part_size")]
+    pub part_size: u64,
+    #[builder(default = default_multipart_concurrency())]
+    #[serde(default = "default_multipart_concurrency")]
+    pub concurrency: usize,
+    #[builder(default = default_multipart_max_retries())]
+    #[serde(default = "default_multipart_max_retries")]
+    pub max_retries: u32,
+    #[builder(default = default_multipart_retry_backoff_ms())]
+    #[serde(default = "default_multipart_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct GcsStorageConfig {
+    #[builder(default = default_gcs_bucket())]
+    #[serde(default = "default_gcs_bucket")]
+    pub bucket: String,
+    #[builder(default)]
+    #[serde(default)]
+    pub base: String,
+    #[builder(default = default_gcs_endpoint())]
+    #[serde(default = "default_gcs_endpoint")]
+    pub endpoint: String,
+    #[builder(default)]
+    #[serde(default)]
+    pub credentials: GcsCredentialsConfig,
+    #[builder(default = default_gcs_chunk_size())]
+    #[serde(default = "default_gcs_chunk_size")]
+    pub chunk_size: u64,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "lowercase")]
+pub enum GcsCredentialsConfig {
+    #[default]
+    Anonymous,
+    Static {
+        token: String,
+    },
+    Metadata {
+        #[serde(default)]
+        endpoint: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct AzureBlobStorageConfig {
+    pub account: String,
+    #[builder(default = default_azure_container())]
+    #[serde(default = "default_azure_container")]
+    pub container: String,
+    #[builder(default)]
+    #[serde(default)]
+    pub base: String,
+    #[builder(default, setter(strip_option))]
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[builder(default)]
+    #[serde(default)]
+    pub credentials: AzureCredentialsConfig,
+    #[builder(default = default_azure_block_size())]
+    #[serde(default = "default_azure_block_size")]
+    pub block_size: u64,
+    #[builder(default = default_azure_copy_timeout_seconds())]
+    #[serde(default = "default_azure_copy_timeout_seconds")]
+    pub copy_timeout_seconds: u64,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum AzureCredentialsConfig {
+    #[default]
+    Anonymous,
+    Sas {
+        token: String,
+    },
+    ManagedIdentity {
+        #[serde(default)]
+        endpoint: Option<String>,
+        #[serde(default)]
+        client_id: Option<String>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum StorageType {
+    #[default]
     S3,
     File,
+    Gcs,
+    #[serde(rename = "azure_blob")]
+    AzureBlob,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedBuilder)]
@@ -39,6 +191,21 @@ pub struct StorageCacheConfig {
     #[builder(default)]
     #[serde(default)]
     pub path: Option<String>,
+    #[builder(default)]
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    #[builder(default)]
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+    #[builder(default)]
+    #[serde(default)]
+    pub revalidate: bool,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
 }
 
 impl Default for FileStorageConfig {
@@ -53,9 +220,87 @@ impl Default for S3StorageConfig {
     }
 }
 
-impl Default for StorageType {
+impl std::fmt::Debug for S3CredentialsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => f.write_str("Default"),
+            Self::Environment => f.write_str("Environment"),
+            Self::Profile { profile } => f.debug_struct("Profile").field("profile", profile).finish(),
+            Self::Static {
+                access_key_id,
+                session_token,
+                ..
+            } => f
+                .debug_struct("Static")
+                .field("access_key_id", access_key_id)
+                .field("secret_access_key", &REDACTED)
+                .field("session_token", &session_token.as_ref().map(|_| REDACTED))
+                .finish(),
+        }
+    }
+}
+
+impl Default for S3MultipartConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl Default for GcsStorageConfig {
     fn default() -> Self {
-        Self::S3
+        Self::builder().build()
+    }
+}
+
+impl std::fmt::Debug for GcsCredentialsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Anonymous => f.write_str("Anonymous"),
+            Self::Static { .. } => f.debug_struct("Static").field("token", &REDACTED).finish(),
+            Self::Metadata { endpoint } => f.debug_struct("Metadata").field("endpoint", endpoint).finish(),
+        }
+    }
+}
+
+impl std::fmt::Debug for AzureCredentialsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Anonymous => f.write_str("Anonymous"),
+            Self::Sas { .. } => f.debug_struct("Sas").field("token", &REDACTED).finish(),
+            Self::ManagedIdentity { endpoint, client_id } => f
+                .debug_struct("ManagedIdentity")
+                .field("endpoint", endpoint)
+                .field("client_id", client_id)
+                .finish(),
+        }
+    }
+}
+
+impl AzureBlobStorageConfig {
+    pub fn endpoint(&self) -> String {
+        self.endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://{}.blob.core.windows.net", self.account))
+    }
+}
+
+// backends whose feature is compiled out are rejected here rather than when the storage is built.
+impl<'de> Deserialize<'de> for StorageType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match name.as_str() {
+            "s3" => Ok(Self::S3),
+            "file" => Ok(Self::File),
+            #[cfg(feature = "gcs")]
+            "gcs" => Ok(Self::Gcs),
+            #[cfg(not(feature = "gcs"))]
+            "gcs" => Err(D::Error::custom("gcs storage requires the gcs feature")),
+            #[cfg(feature = "azure")]
+            "azure_blob" => Ok(Self::AzureBlob),
+            #[cfg(not(feature = "azure"))]
+            "azure_blob" => Err(D::Error::custom("azure_blob storage requires the azure feature")),
+            _ => Err(D::Error::unknown_variant(&name, &["s3", "file", "gcs", "azure_blob"])),
+        }
     }
 }
 
@@ -76,3 +321,47 @@ fn default_s3_region() -> String {
 fn default_s3_base() -> String {
     String::from("/")
 }
+
+fn default_gcs_bucket() -> String {
+    String::from("static.mystiko.network")
+}
+
+fn default_gcs_endpoint() -> String {
+    String::from("https://storage.googleapis.com")
+}
+
+fn default_gcs_chunk_size() -> u64 {
+    8 * 1024 * 1024
+}
+
+fn default_azure_container() -> String {
+    String::from("static")
+}
+
+fn default_azure_block_size() -> u64 {
+    8 * 1024 * 1024
+}
+
+fn default_azure_copy_timeout_seconds() -> u64 {
+    600
+}
+
+fn default_multipart_threshold() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_multipart_part_size() -> u64 {
+    16 * 1024 * 1024
+}
+
+fn default_multipart_concurrency() -> usize {
+    4
+}
+
+fn default_multipart_max_retries() -> u32 {
+    3
+}
+
+fn default_multipart_retry_backoff_ms() -> u64 {
+    200
+}
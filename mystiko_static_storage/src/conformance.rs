@@ -0,0 +1,305 @@
+use crate::{
+    CopyRequest, GetRequest, ListFilesRequest, PutRequest, PutStreamRequest, RemoveFolderRequest, RenameRequest,
+    Storage, StorageError, StorageReader,
+};
+use anyhow::{ensure, Result};
+use futures::TryStreamExt;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+pub async fn check_storage<S: Storage>(storage: &S) -> Result<()> {
+    check_list_folders(storage).await?;
+    check_list_files(storage).await?;
+    check_get_put(storage).await?;
+    check_conditional_put(storage).await?;
+    check_stream(storage).await?;
+    check_copy_rename(storage).await?;
+    check_remove(storage).await
+}
+
+pub async fn check_list_folders<S: Storage>(storage: &S) -> Result<()> {
+    let root = Path::new("conformance/list_folders");
+    let folders = storage.list_folders(root.join("missing").into()).await?.folders;
+    ensure!(folders.is_empty(), "missing folder listed {:?}", folders);
+    put_all(
+        storage,
+        root,
+        &[
+            "a/file.txt",
+            "a/b/file.txt",
+            "a/b/c/file.txt",
+            "ab/file.txt",
+            "file.txt",
+        ],
+    )
+    .await?;
+    let folders = sorted(storage.list_folders(root.into()).await?.folders);
+    ensure!(
+        folders == vec![root.join("a"), root.join("ab")],
+        "unexpected folders {:?}",
+        folders
+    );
+    let folders = sorted(storage.list_folders(root.join("a").into()).await?.folders);
+    ensure!(folders == vec![root.join("a/b")], "unexpected folders {:?}", folders);
+    let folders = storage.list_folders(root.join("file.txt").into()).await?.folders;
+    ensure!(folders.is_empty(), "file listed as folder {:?}", folders);
+    storage.remove_folder(root.into()).await?;
+    Ok(())
+}
+
+pub async fn check_list_files<S: Storage>(storage: &S) -> Result<()> {
+    let root = Path::new("conformance/list_files");
+    let files = storage.list_files(root.join("missing").into()).await?.files;
+    ensure!(files.is_empty(), "missing folder listed {:?}", files);
+    put_all(
+        storage,
+        root,
+        &["a/file.txt", "a/b/file.txt", "a/b/c/file.txt", "ab/file.txt"],
+    )
+    .await?;
+    let files = sorted(storage.list_files(root.join("a").into()).await?.files);
+    let expected = vec![
+        root.join("a/b/c/file.txt"),
+        root.join("a/b/file.txt"),
+        root.join("a/file.txt"),
+    ];
+    ensure!(files == expected, "unexpected files {:?}", files);
+    let request = ListFilesRequest::builder()
+        .path(root.join("a"))
+        .non_recursively(true)
+        .build();
+    let files = storage.list_files(request).await?.files;
+    ensure!(files == vec![root.join("a/file.txt")], "unexpected files {:?}", files);
+    let request = ListFilesRequest::builder()
+        .path(root.join("a"))
+        .with_metadata(true)
+        .page_size(1)
+        .build();
+    let objects = storage.list_files_stream(request).try_collect::<Vec<_>>().await?;
+    let files = sorted(objects.iter().map(|object| object.path.clone()).collect());
+    ensure!(files == expected, "unexpected streamed files {:?}", files);
+    ensure!(
+        objects.iter().all(|object| object.size == Some(4)),
+        "unexpected streamed sizes {:?}",
+        objects
+    );
+    storage.remove_folder(root.into()).await?;
+    Ok(())
+}
+
+pub async fn check_get_put<S: Storage>(storage: &S) -> Result<()> {
+    let path = Path::new("conformance/get_put/file.txt");
+    ensure!(!storage.exists(path.into()).await?.exists, "missing file exists");
+    ensure!(
+        is_storage_error(storage.get(path.into()).await, is_not_found),
+        "missing file readable"
+    );
+    ensure!(
+        is_storage_error(storage.get_stream(path.into()).await, is_not_found),
+        "missing file streamable"
+    );
+    ensure!(
+        is_storage_error(storage.stat(path.into()).await, is_not_found),
+        "missing file has metadata"
+    );
+    let put_request = PutRequest::builder().path(path).data("hello world").build();
+    storage.put(put_request).await?;
+    ensure!(storage.exists(path.into()).await?.exists, "file does not exist");
+    let meta = storage.stat(path.into()).await?.meta;
+    ensure!(
+        meta.path == path && meta.size == Some(11),
+        "unexpected metadata {:?}",
+        meta
+    );
+    let put_request = PutRequest::builder().path(path).data("hello mystiko").build();
+    storage.put(put_request).await?;
+    let data = storage.get(path.into()).await?.data;
+    ensure!(data == b"hello world", "file overwritten without overwrite");
+    let put_request = PutRequest::builder()
+        .path(path)
+        .data("hello mystiko")
+        .overwrite(true)
+        .build();
+    storage.put(put_request).await?;
+    let data = storage.get(path.into()).await?.data;
+    ensure!(data == b"hello mystiko", "file not overwritten");
+    let request = GetRequest::builder().path(path).offset(6u64).length(4u64).build();
+    let data = storage.get(request).await?.data;
+    ensure!(data == b"myst", "unexpected range {:?}", data);
+    storage.remove_file(path.into()).await?;
+    ensure!(!storage.exists(path.into()).await?.exists, "removed file exists");
+    Ok(())
+}
+
+pub async fn check_conditional_put<S: Storage>(storage: &S) -> Result<()> {
+    let path = Path::new("conformance/conditional_put/file.txt");
+    let create_request = PutRequest::builder()
+        .path(path)
+        .data("hello world")
+        .if_none_match("*")
+        .build();
+    let etag = match storage.put(create_request.clone()).await {
+        Err(error) if matches!(error.downcast_ref::<StorageError>(), Some(StorageError::Unsupported(_))) => {
+            return Ok(());
+        }
+        response => response?.etag,
+    };
+    ensure!(etag.is_some(), "put returned no etag");
+    ensure!(
+        is_storage_error(storage.put(create_request).await, |error| matches!(
+            error,
+            StorageError::PreconditionFailed(_)
+        )),
+        "if_none_match did not fail on an existing file"
+    );
+    let stale_request = PutRequest::builder()
+        .path(path)
+        .data("hello mystiko")
+        .if_match("stale-etag")
+        .build();
+    ensure!(
+        storage.put(stale_request).await.is_err(),
+        "if_match did not fail on a stale etag"
+    );
+    let update_request = PutRequest::builder()
+        .path(path)
+        .data("hello mystiko")
+        .if_match(etag.unwrap_or_default())
+        .build();
+    storage.put(update_request).await?;
+    let data = storage.get(path.into()).await?.data;
+    ensure!(data == b"hello mystiko", "conditional put did not update the file");
+    storage.remove_file(path.into()).await?;
+    Ok(())
+}
+
+pub async fn check_stream<S: Storage>(storage: &S) -> Result<()> {
+    let path = Path::new("conformance/stream/file.txt");
+    let request = PutStreamRequest::builder()
+        .path(path)
+        .reader(Box::pin(Cursor::new(b"hello world".to_vec())) as StorageReader)
+        .length(11u64)
+        .build();
+    storage.put_stream(request).await?;
+    let data = storage.get_stream(path.into()).await?.read_to_end().await?;
+    ensure!(data == b"hello world", "unexpected streamed data {:?}", data);
+    let request = GetRequest::builder().path(path).offset(6u64).build();
+    let data = storage.get_stream(request).await?.read_to_end().await?;
+    ensure!(data == b"world", "unexpected streamed range {:?}", data);
+    let request = GetRequest::builder().path(path).offset(20u64).build();
+    let data = storage.get_stream(request.clone()).await?.read_to_end().await?;
+    ensure!(data.is_empty(), "unexpected streamed data past the end {:?}", data);
+    let data = storage.get(request).await?.data;
+    ensure!(data.is_empty(), "unexpected data past the end {:?}", data);
+    storage.remove_file(path.into()).await?;
+    Ok(())
+}
+
+pub async fn check_copy_rename<S: Storage>(storage: &S) -> Result<()> {
+    let root = Path::new("conformance/copy_rename");
+    put_all(storage, root, &["a/file.txt", "b/file.txt"]).await?;
+    storage
+        .copy((root.join("a/file.txt"), root.join("c/file.txt")).into())
+        .await?;
+    let data = storage.get(root.join("c/file.txt").into()).await?.data;
+    ensure!(data == b"data", "unexpected copied data {:?}", data);
+    ensure!(
+        storage.exists(root.join("a/file.txt").into()).await?.exists,
+        "copy removed the source"
+    );
+    ensure!(
+        is_storage_error(
+            storage
+                .copy((root.join("a/file.txt"), root.join("b/file.txt")).into())
+                .await,
+            |error| matches!(error, StorageError::AlreadyExists(_))
+        ),
+        "copy overwrote an existing file"
+    );
+    let request = RenameRequest::builder()
+        .from(root.join("a/file.txt"))
+        .to(root.join("b/file.txt"))
+        .overwrite(true)
+        .build();
+    storage.rename(request).await?;
+    ensure!(
+        !storage.exists(root.join("a/file.txt").into()).await?.exists,
+        "rename kept the source"
+    );
+    let folders = sorted(storage.list_folders(root.into()).await?.folders);
+    ensure!(
+        folders == vec![root.join("b"), root.join("c")],
+        "unexpected folders after rename {:?}",
+        folders
+    );
+    let request = CopyRequest::builder()
+        .from(root.join("missing.txt"))
+        .to(root.join("d/file.txt"))
+        .build();
+    ensure!(
+        is_storage_error(storage.copy(request).await, is_not_found),
+        "copied a missing file"
+    );
+    storage.remove_folder(root.into()).await?;
+    Ok(())
+}
+
+pub async fn check_remove<S: Storage>(storage: &S) -> Result<()> {
+    let root = Path::new("conformance/remove");
+    storage.remove_file(root.join("missing.txt").into()).await?;
+    storage.remove_folder(root.join("missing").into()).await?;
+    put_all(
+        storage,
+        root,
+        &["a/file.txt", "a/b/file.txt", "ab/file.txt", "c/file.txt"],
+    )
+    .await?;
+    storage
+        .remove_files(vec![root.join("c/file.txt"), root.join("c/missing.txt")].into())
+        .await?;
+    let folders = sorted(storage.list_folders(root.into()).await?.folders);
+    ensure!(
+        folders == vec![root.join("a"), root.join("ab")],
+        "emptied folder still listed {:?}",
+        folders
+    );
+    let request = RemoveFolderRequest::builder()
+        .path(root.join("a"))
+        .non_recursively(true)
+        .build();
+    ensure!(
+        storage.remove_folder(request).await.is_err(),
+        "removed a non-empty folder non-recursively"
+    );
+    storage.remove_folder(root.join("a").into()).await?;
+    let files = storage.list_files(root.into()).await?.files;
+    ensure!(files == vec![root.join("ab/file.txt")], "unexpected files {:?}", files);
+    storage.remove_folder(root.into()).await?;
+    let files = storage.list_files(root.into()).await?.files;
+    ensure!(files.is_empty(), "removed folder still has files {:?}", files);
+    Ok(())
+}
+
+async fn put_all<S: Storage>(storage: &S, root: &Path, paths: &[&str]) -> Result<()> {
+    for path in paths {
+        let put_request = PutRequest::builder().path(root.join(path)).data("data").build();
+        storage.put(put_request).await?;
+    }
+    Ok(())
+}
+
+fn sorted(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths.sort();
+    paths
+}
+
+fn is_storage_error<T>(result: Result<T>, predicate: impl Fn(&StorageError) -> bool) -> bool {
+    match result {
+        Ok(_) => false,
+        Err(error) => error.downcast_ref::<StorageError>().is_some_and(predicate),
+    }
+}
+
+fn is_not_found(error: &StorageError) -> bool {
+    matches!(error, StorageError::NotFound(_))
+}
@@ -0,0 +1,29 @@
+use crate::ObjectMeta;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("{0} does not exist")]
+    NotFound(String),
+    #[error("{0} already exists")]
+    AlreadyExists(String),
+    #[error("precondition failed for {0}")]
+    PreconditionFailed(String),
+    #[error("{0} is not supported")]
+    Unsupported(String),
+}
+
+pub(crate) fn check_put_preconditions(
+    path: &Path,
+    current: Option<&ObjectMeta>,
+    if_match: Option<&str>,
+    if_none_match: Option<&str>,
+) -> Result<(), StorageError> {
+    let matches =
+        |expected: &str| current.is_some_and(|current| expected == "*" || current.etag.as_deref() == Some(expected));
+    if if_match.is_some_and(|expected| !matches(expected)) || if_none_match.is_some_and(matches) {
+        return Err(StorageError::PreconditionFailed(path.to_string_lossy().to_string()));
+    }
+    Ok(())
+}
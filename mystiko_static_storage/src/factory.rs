@@ -0,0 +1,44 @@
+use crate::{CachedStorage, FileStorage, S3Storage, Storage, StorageConfig, StorageType};
+use anyhow::Result;
+
+pub async fn build_storage(config: &StorageConfig) -> Result<Box<dyn Storage>> {
+    let raw: Box<dyn Storage> = match config.storage_type {
+        StorageType::S3 => Box::new(S3Storage::from_config(&config.s3)?),
+        StorageType::File => Box::new(FileStorage::from_config(&config.file).await?),
+        StorageType::Gcs => build_gcs_storage(config)?,
+        StorageType::AzureBlob => build_azure_blob_storage(config)?,
+    };
+    if config.cache.enabled {
+        Ok(Box::new(CachedStorage::from_config(&config.cache, raw).await?))
+    } else {
+        Ok(raw)
+    }
+}
+
+#[cfg(feature = "gcs")]
+fn build_gcs_storage(config: &StorageConfig) -> Result<Box<dyn Storage>> {
+    let gcs_config = config
+        .gcs
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("missing gcs storage config"))?;
+    Ok(Box::new(crate::GcsStorage::from_config(gcs_config)?))
+}
+
+#[cfg(not(feature = "gcs"))]
+fn build_gcs_storage(_config: &StorageConfig) -> Result<Box<dyn Storage>> {
+    Err(anyhow::anyhow!("gcs storage requires the gcs feature"))
+}
+
+#[cfg(feature = "azure")]
+fn build_azure_blob_storage(config: &StorageConfig) -> Result<Box<dyn Storage>> {
+    let azure_config = config
+        .azure_blob
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("missing azure_blob storage config"))?;
+    Ok(Box::new(crate::AzureBlobStorage::from_config(azure_config)?))
+}
+
+#[cfg(not(feature = "azure"))]
+fn build_azure_blob_storage(_config: &StorageConfig) -> Result<Box<dyn Storage>> {
+    Err(anyhow::anyhow!("azure_blob storage requires the azure feature"))
+}
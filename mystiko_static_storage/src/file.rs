@@ -1,14 +1,31 @@
 use crate::{
-    ExistsRequest, ExistsResponse, FileStorageConfig, GetRequest, GetResponse, ListFilesRequest, ListFilesResponse,
-    ListFoldersRequest, ListFoldersResponse, PutRequest, PutResponse, RemoveFileRequest, RemoveFileResponse,
-    RemoveFilesRequest, RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse, Storage,
+    check_put_preconditions, CopyRequest, CopyResponse, ExistsRequest, ExistsResponse, FileStorageConfig, GetRequest,
+    GetResponse, GetStreamResponse, ListFilesRequest, ListFilesResponse, ListFoldersRequest, ListFoldersResponse,
+    ObjectMeta, PutRequest, PutResponse, PutStreamRequest, RemoveFileRequest, RemoveFileResponse, RemoveFilesRequest,
+    RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse, RenameRequest, RenameResponse, StatRequest,
+    StatResponse, Storage, StorageError, StorageReader,
 };
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::io::{ErrorKind, SeekFrom};
 use std::path::{Path, PathBuf};
-use tokio::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::OwnedMutexGuard;
 use typed_builder::TypedBuilder;
 
+const ETAG_EXTENSION: &str = "etag";
+const SOURCE_EXTENSION: &str = "source";
+const TEMP_EXTENSION: &str = "tmp";
+const SIDECAR_EXTENSIONS: [&str; 2] = [ETAG_EXTENSION, SOURCE_EXTENSION];
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
 #[derive(Debug, Default, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct FileStorageOptions {
@@ -16,6 +33,8 @@ pub struct FileStorageOptions {
     base: PathBuf,
 }
 
+/// Preconditions are checked under a lock that is local to this process, so `if_match` is
+/// not atomic when several processes write to the same directory.
 #[derive(Debug, Clone)]
 pub struct FileStorage {
     pub base: PathBuf,
@@ -27,6 +46,9 @@ impl Storage for FileStorage {
         let mut paths = vec![];
         let mut dir = self.base.clone();
         dir.push(&request.path);
+        if !is_folder(&dir).await? {
+            return Ok(ListFoldersResponse::builder().folders(paths).build());
+        }
         let mut entries = fs::read_dir(dir).await?;
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
@@ -39,19 +61,37 @@ impl Storage for FileStorage {
 
     async fn list_files(&self, request: ListFilesRequest) -> Result<ListFilesResponse> {
         let mut paths = vec![];
+        let mut objects = vec![];
         let mut dir = self.base.clone();
         dir.push(&request.path);
+        if !is_folder(&dir).await? {
+            return Ok(ListFilesResponse::builder().files(paths).objects(objects).build());
+        }
         let mut entries = fs::read_dir(dir).await?;
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
+            if is_internal(&path) {
+                continue;
+            }
             if path.is_dir() && !request.non_recursively {
-                let mut sub_files = self.list_files(path.into()).await?;
+                let sub_request = ListFilesRequest::builder()
+                    .path(path.strip_prefix(&self.base)?)
+                    .with_metadata(request.with_metadata)
+                    .build();
+                let mut sub_files = self.list_files(sub_request).await?;
                 paths.append(&mut sub_files.files);
+                objects.append(&mut sub_files.objects);
             } else if !path.is_dir() {
-                paths.push(path.strip_prefix(&self.base)?.to_path_buf());
+                let relative_path = path.strip_prefix(&self.base)?.to_path_buf();
+                if request.with_metadata {
+                    let metadata = fs::metadata(&path).await?;
+                    let etag = file_etag(&path, &metadata).await;
+                    objects.push(object_meta(relative_path.clone(), &metadata, etag));
+                }
+                paths.push(relative_path);
             }
         }
-        Ok(ListFilesResponse::builder().files(paths).build())
+        Ok(ListFilesResponse::builder().files(paths).objects(objects).build())
     }
 
     async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse> {
@@ -62,33 +102,206 @@ impl Storage for FileStorage {
             .build())
     }
 
+    async fn stat(&self, request: StatRequest) -> Result<StatResponse> {
+        let mut full_path = self.base.clone();
+        full_path.push(&request.path);
+        let metadata = fs::metadata(&full_path)
+            .await
+            .map_err(|err| not_found(&request.path, err))?;
+        if metadata.is_dir() {
+            return Err(anyhow::anyhow!("{} is a folder", request.path.to_string_lossy()));
+        }
+        let etag = file_etag(&full_path, &metadata).await;
+        Ok(StatResponse::builder()
+            .meta(object_meta(request.path, &metadata, etag))
+            .build())
+    }
+
     async fn get(&self, request: GetRequest) -> Result<GetResponse> {
         let mut full_path = self.base.clone();
         full_path.push(&request.path);
-        Ok(GetResponse::builder().data(fs::read(full_path).await?).build())
+        if !request.is_ranged() {
+            return Ok(GetResponse::builder()
+                .data(fs::read(full_path).await.map_err(|err| not_found(&request.path, err))?)
+                .build());
+        }
+        let response = self.get_stream(request).await?;
+        Ok(GetResponse::builder().data(response.read_to_end().await?).build())
     }
 
     async fn put(&self, request: PutRequest) -> Result<PutResponse> {
+        let length = request.data.len() as u64;
+        let stream_request = PutStreamRequest {
+            path: request.path,
+            reader: Box::pin(std::io::Cursor::new(request.data)),
+            length,
+            overwrite: request.overwrite,
+            content_type: request.content_type,
+            cache_control: request.cache_control,
+            acl: request.acl,
+            if_match: request.if_match,
+            if_none_match: request.if_none_match,
+        };
+        self.put_stream(stream_request).await
+    }
+
+    async fn get_stream(&self, request: GetRequest) -> Result<GetStreamResponse> {
         let mut full_path = self.base.clone();
         full_path.push(&request.path);
-        if let Some(parent) = full_path.parent() {
-            if !fs::try_exists(parent).await? {
-                fs::create_dir_all(parent).await?;
+        let mut file = File::open(full_path)
+            .await
+            .map_err(|err| not_found(&request.path, err))?;
+        let size = file.metadata().await?.len();
+        let offset = request.offset.unwrap_or_default().min(size);
+        let length = request.length.map_or(size - offset, |length| length.min(size - offset));
+        if offset > 0 {
+            file.seek(SeekFrom::Start(offset)).await?;
+        }
+        Ok(GetStreamResponse::builder()
+            .reader(Box::pin(file.take(length)) as StorageReader)
+            .length(length)
+            .build())
+    }
+
+    async fn put_stream(&self, request: PutStreamRequest) -> Result<PutResponse> {
+        let mut full_path = self.base.clone();
+        full_path.push(&request.path);
+        create_parent(&full_path).await?;
+        let conditional = request.if_match.is_some() || request.if_none_match.is_some();
+        if !conditional && !request.overwrite {
+            if let Some(current) = current_meta(&request.path, &full_path).await? {
+                return Ok(PutResponse { etag: current.etag });
+            }
+        }
+        let temp_path = temp_path(&full_path);
+        let hash = match write_temp(&temp_path, request.reader, request.length).await {
+            Ok(hash) => hash,
+            Err(err) => {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(anyhow::anyhow!(
+                    "failed to write {}: {}",
+                    request.path.to_string_lossy(),
+                    err
+                ));
             }
+        };
+        let _lock = lock_path(&full_path).await;
+        let current = match current_meta(&request.path, &full_path).await {
+            Ok(current) => current,
+            Err(err) => {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(err);
+            }
+        };
+        if let Err(err) = check_put_preconditions(
+            &request.path,
+            current.as_ref(),
+            request.if_match.as_deref(),
+            request.if_none_match.as_deref(),
+        ) {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(err.into());
         }
-        let exists = self.exists(full_path.clone().into()).await?;
-        if !request.overwrite && exists.exists {
-            return Ok(PutResponse::builder().build());
+        let create_only = request.if_none_match.as_deref() == Some("*") || (!conditional && !request.overwrite);
+        if create_only {
+            if let Err(err) = move_no_clobber(&temp_path, &full_path).await {
+                let _ = fs::remove_file(&temp_path).await;
+                if err.kind() != ErrorKind::AlreadyExists {
+                    return Err(err.into());
+                }
+                if request.if_none_match.is_some() {
+                    return Err(StorageError::PreconditionFailed(request.path.to_string_lossy().to_string()).into());
+                }
+                let current = current_meta(&request.path, &full_path).await?;
+                return Ok(PutResponse {
+                    etag: current.and_then(|current| current.etag),
+                });
+            }
+        } else {
+            fs::rename(&temp_path, &full_path).await?;
         }
-        fs::write(full_path, request.data).await?;
-        Ok(PutResponse::builder().build())
+        let etag = write_etag(&full_path, &fs::metadata(&full_path).await?, &hash).await?;
+        Ok(PutResponse { etag: Some(etag) })
+    }
+
+    async fn copy(&self, request: CopyRequest) -> Result<CopyResponse> {
+        let from_path = self.base.join(&request.from);
+        let to_path = self.base.join(&request.to);
+        let metadata = fs::metadata(&from_path)
+            .await
+            .map_err(|err| not_found(&request.from, err))?;
+        if metadata.is_dir() {
+            return Err(anyhow::anyhow!("{} is a folder", request.from.to_string_lossy()));
+        }
+        create_parent(&to_path).await?;
+        let temp_path = temp_path(&to_path);
+        let hash = {
+            let _lock = lock_path(&from_path).await;
+            if let Err(err) = fs::copy(&from_path, &temp_path).await {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(err.into());
+            }
+            match recorded_hash(&from_path, &metadata).await {
+                Some(hash) => hash,
+                None => hash_file(&temp_path).await?,
+            }
+        };
+        let _lock = lock_path(&to_path).await;
+        if request.overwrite {
+            fs::rename(&temp_path, &to_path).await?;
+        } else if let Err(err) = move_no_clobber(&temp_path, &to_path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(no_clobber(err, &request.to));
+        }
+        let etag = write_etag(&to_path, &fs::metadata(&to_path).await?, &hash).await?;
+        Ok(CopyResponse { etag: Some(etag) })
+    }
+
+    async fn rename(&self, request: RenameRequest) -> Result<RenameResponse> {
+        let from_path = self.base.join(&request.from);
+        let to_path = self.base.join(&request.to);
+        if fs::metadata(&from_path)
+            .await
+            .map_err(|err| not_found(&request.from, err))?
+            .is_dir()
+        {
+            return Err(anyhow::anyhow!("{} is a folder", request.from.to_string_lossy()));
+        }
+        create_parent(&to_path).await?;
+        let _locks = lock_paths(&from_path, &to_path).await;
+        if request.overwrite {
+            fs::rename(&from_path, &to_path).await?;
+        } else {
+            move_no_clobber(&from_path, &to_path)
+                .await
+                .map_err(|err| no_clobber(err, &request.to))?;
+        }
+        for extension in SIDECAR_EXTENSIONS {
+            match fs::rename(sidecar_path(&from_path, extension), sidecar_path(&to_path, extension)).await {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => remove_sidecar(&to_path, extension).await?,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        self.remove_empty_parents(&from_path).await;
+        Ok(RenameResponse::builder().build())
     }
 
     async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse> {
         for path in request.paths.into_iter() {
             let mut full_path = self.base.clone();
             full_path.push(path.clone());
-            fs::remove_file(full_path).await?;
+            let _lock = lock_path(&full_path).await;
+            match fs::remove_file(&full_path).await {
+                Ok(()) => {
+                    for extension in SIDECAR_EXTENSIONS {
+                        remove_sidecar(&full_path, extension).await?;
+                    }
+                    self.remove_empty_parents(&full_path).await;
+                }
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
         }
         Ok(RemoveFilesResponse::builder().build())
     }
@@ -101,11 +314,15 @@ impl Storage for FileStorage {
     async fn remove_folder(&self, request: RemoveFolderRequest) -> Result<RemoveFolderResponse> {
         let mut full_path = self.base.clone();
         full_path.push(&request.path);
+        if !is_folder(&full_path).await? {
+            return Ok(RemoveFolderResponse::builder().build());
+        }
         if request.non_recursively {
-            fs::remove_dir(full_path).await?;
+            fs::remove_dir(&full_path).await?;
         } else {
-            fs::remove_dir_all(full_path).await?;
+            fs::remove_dir_all(&full_path).await?;
         }
+        self.remove_empty_parents(&full_path).await;
         Ok(RemoveFolderResponse::builder().build())
     }
 }
@@ -133,6 +350,34 @@ impl FileStorage {
 }
 
 impl FileStorage {
+    async fn remove_empty_parents(&self, full_path: &Path) {
+        for parent in full_path.ancestors().skip(1) {
+            if parent == self.base || !parent.starts_with(&self.base) || fs::remove_dir(parent).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    // the etag of the object a file was copied from; it is dropped once the file changes.
+    pub(crate) async fn source_etag(&self, path: &Path) -> Option<String> {
+        let full_path = self.base.join(path);
+        let metadata = fs::metadata(&full_path).await.ok()?;
+        let content = fs::read_to_string(sidecar_path(&full_path, SOURCE_EXTENSION))
+            .await
+            .ok()?;
+        let (recorded, etag) = content.split_once(' ')?;
+        (Some(recorded) == etag_stamp(&metadata).as_deref()).then(|| etag.to_string())
+    }
+
+    pub(crate) async fn set_source_etag(&self, path: &Path, etag: Option<&str>) -> Result<()> {
+        let full_path = self.base.join(path);
+        let _lock = lock_path(&full_path).await;
+        match (etag, fs::metadata(&full_path).await) {
+            (Some(etag), Ok(metadata)) => write_sidecar(&full_path, SOURCE_EXTENSION, &metadata, etag).await,
+            _ => remove_sidecar(&full_path, SOURCE_EXTENSION).await,
+        }
+    }
+
     pub async fn from_config(config: &FileStorageConfig) -> Result<Self> {
         let data_dir = dirs::data_dir()
             .ok_or(anyhow::anyhow!("cannot detect the data directory of current OS"))?
@@ -143,3 +388,230 @@ impl FileStorage {
         Self::new(path).await
     }
 }
+
+async fn is_folder(full_path: &Path) -> Result<bool> {
+    match fs::metadata(full_path).await {
+        Ok(metadata) => Ok(metadata.is_dir()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn create_parent(full_path: &Path) -> Result<()> {
+    if let Some(parent) = full_path.parent() {
+        if !fs::try_exists(parent).await? {
+            fs::create_dir_all(parent).await?;
+        }
+    }
+    Ok(())
+}
+
+fn not_found(path: &Path, err: std::io::Error) -> anyhow::Error {
+    if err.kind() == ErrorKind::NotFound {
+        StorageError::NotFound(path.to_string_lossy().to_string()).into()
+    } else {
+        err.into()
+    }
+}
+
+fn no_clobber(err: std::io::Error, path: &Path) -> anyhow::Error {
+    if err.kind() == ErrorKind::AlreadyExists {
+        StorageError::AlreadyExists(path.to_string_lossy().to_string()).into()
+    } else {
+        err.into()
+    }
+}
+
+// a hard link never replaces an existing target. File systems without hard links fall back to claiming the
+// target with an exclusively created placeholder that the rename then replaces, so readers may briefly see it empty.
+async fn move_no_clobber(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::hard_link(from, to).await {
+        Ok(()) => fs::remove_file(from).await,
+        Err(err) if matches!(err.kind(), ErrorKind::AlreadyExists | ErrorKind::NotFound) => Err(err),
+        Err(_) => {
+            fs::OpenOptions::new().write(true).create_new(true).open(to).await?;
+            let renamed = fs::rename(from, to).await;
+            if renamed.is_err() {
+                let _ = fs::remove_file(to).await;
+            }
+            renamed
+        }
+    }
+}
+
+async fn current_meta(path: &Path, full_path: &Path) -> Result<Option<ObjectMeta>> {
+    match fs::metadata(full_path).await {
+        Ok(metadata) if metadata.is_dir() => Err(anyhow::anyhow!("{} is a folder", path.to_string_lossy())),
+        Ok(metadata) => {
+            let etag = file_etag(full_path, &metadata).await;
+            Ok(Some(object_meta(path.to_path_buf(), &metadata, etag)))
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// serializes the precondition check and replacement of a file within this process only; other
+// processes sharing the directory are not excluded.
+async fn lock_path(full_path: &Path) -> PathLock {
+    static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> = OnceLock::new();
+    let locks = LOCKS.get_or_init(Default::default);
+    let lock = locks
+        .lock()
+        .unwrap()
+        .entry(full_path.to_path_buf())
+        .or_default()
+        .clone();
+    PathLock {
+        locks,
+        path: full_path.to_path_buf(),
+        guard: Some(lock.lock_owned().await),
+    }
+}
+
+async fn lock_paths(first: &Path, second: &Path) -> (PathLock, Option<PathLock>) {
+    let (first, second) = if first <= second {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    let first_lock = lock_path(first).await;
+    let second_lock = if first == second {
+        None
+    } else {
+        Some(lock_path(second).await)
+    };
+    (first_lock, second_lock)
+}
+
+struct PathLock {
+    locks: &'static Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>,
+    path: PathBuf,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for PathLock {
+    fn drop(&mut self) {
+        self.guard.take();
+        let mut locks = self.locks.lock().unwrap();
+        if locks.get(&self.path).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            locks.remove(&self.path);
+        }
+    }
+}
+
+// etags are the content hashes recorded when the file was written. Files written out of band fall back to
+// their modification time and size, so reads never hash a file or write a sidecar.
+async fn file_etag(full_path: &Path, metadata: &Metadata) -> Option<String> {
+    recorded_hash(full_path, metadata)
+        .await
+        .or_else(|| etag_stamp(metadata))
+}
+
+// the hash is kept in a hidden sidecar stamped with the modification time and size it was computed for,
+// so that out-of-band edits are detected.
+async fn recorded_hash(full_path: &Path, metadata: &Metadata) -> Option<String> {
+    let stamp = etag_stamp(metadata)?;
+    let content = fs::read_to_string(sidecar_path(full_path, ETAG_EXTENSION)).await.ok()?;
+    let (recorded, hash) = content.trim().split_once(' ')?;
+    (recorded == stamp).then(|| hash.to_string())
+}
+
+async fn write_etag(full_path: &Path, metadata: &Metadata, hash: &str) -> Result<String> {
+    write_sidecar(full_path, ETAG_EXTENSION, metadata, hash).await?;
+    Ok(hash.to_string())
+}
+
+async fn write_sidecar(full_path: &Path, extension: &str, metadata: &Metadata, value: &str) -> Result<()> {
+    if let Some(stamp) = etag_stamp(metadata) {
+        let sidecar_path = sidecar_path(full_path, extension);
+        let temp_path = temp_path(&sidecar_path);
+        fs::write(&temp_path, format!("{} {}", stamp, value)).await?;
+        fs::rename(&temp_path, &sidecar_path).await?;
+    }
+    Ok(())
+}
+
+async fn remove_sidecar(full_path: &Path, extension: &str) -> Result<()> {
+    match fs::remove_file(sidecar_path(full_path, extension)).await {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn etag_stamp(metadata: &Metadata) -> Option<String> {
+    let last_modified = metadata.modified().ok().map(DateTime::<Utc>::from)?;
+    Some(format!("{:x}-{:x}", last_modified.timestamp_micros(), metadata.len()))
+}
+
+fn sidecar_path(full_path: &Path, extension: &str) -> PathBuf {
+    let name = full_path.file_name().unwrap_or_default().to_string_lossy();
+    full_path.with_file_name(format!(".{}.{}", name, extension))
+}
+
+fn is_internal(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.starts_with('.')
+        && path
+            .extension()
+            .is_some_and(|extension| extension == TEMP_EXTENSION || SIDECAR_EXTENSIONS.iter().any(|e| extension == *e))
+}
+
+fn temp_path(full_path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let name = full_path.file_name().unwrap_or_default().to_string_lossy();
+    full_path.with_file_name(format!(
+        ".{}.{}.{}.{}",
+        name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        TEMP_EXTENSION
+    ))
+}
+
+async fn write_temp(temp_path: &Path, reader: StorageReader, length: u64) -> Result<String> {
+    let mut file = File::create(temp_path).await?;
+    let mut reader = reader.take(length);
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    let mut written = 0u64;
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        file.write_all(&buffer[..read]).await?;
+        written += read as u64;
+    }
+    file.flush().await?;
+    if written != length {
+        return Err(anyhow::anyhow!("expected {} bytes but read {}", length, written));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+async fn hash_file(full_path: &Path) -> Result<String> {
+    let mut file = File::open(full_path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn object_meta(path: PathBuf, metadata: &Metadata, etag: Option<String>) -> ObjectMeta {
+    ObjectMeta {
+        path,
+        size: Some(metadata.len()),
+        last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        etag,
+        content_type: None,
+    }
+}
@@ -0,0 +1,573 @@
+use crate::rest::{
+    check_response, encode_component, object_key, object_path, object_prefix, request_body, response_reader,
+    MetadataTokenProvider,
+};
+use crate::{
+    parse_last_modified, range_header, CopyRequest, CopyResponse, ExistsRequest, ExistsResponse, GcsCredentialsConfig,
+    GcsStorageConfig, GetRequest, GetResponse, GetStreamResponse, ListFilesRequest, ListFilesResponse,
+    ListFoldersRequest, ListFoldersResponse, ObjectMeta, PutRequest, PutResponse, PutStreamRequest, RemoveFileRequest,
+    RemoveFileResponse, RemoveFilesRequest, RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse,
+    StatRequest, StatResponse, Storage, StorageError, StorageReader,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, LOCATION, RANGE};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use typed_builder::TypedBuilder;
+
+const DEFAULT_METADATA_ENDPOINT: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+const DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+const CHUNK_GRANULARITY: u64 = 256 * 1024;
+const CHUNK_MAX_RETRIES: u32 = 3;
+const CHUNK_RETRY_BACKOFF_MS: u64 = 200;
+
+#[derive(Clone, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct GcsStorage {
+    #[builder(default)]
+    pub client: Client,
+    #[builder(default = default_endpoint())]
+    pub endpoint: String,
+    pub bucket: String,
+    #[builder(default)]
+    pub base: PathBuf,
+    #[builder(default, setter(strip_option))]
+    pub token: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub token_provider: Option<Arc<MetadataTokenProvider>>,
+    #[builder(default = DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: u64,
+}
+
+enum UploadStatus {
+    Persisted(u64),
+    Complete(Response),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcsObject {
+    name: String,
+    #[serde(default)]
+    size: Option<String>,
+    #[serde(default)]
+    updated: Option<String>,
+    #[serde(default)]
+    generation: Option<String>,
+    #[serde(default)]
+    content_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcsObjectList {
+    #[serde(default)]
+    items: Vec<GcsObject>,
+    #[serde(default)]
+    prefixes: Vec<String>,
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+#[async_trait]
+impl Storage for GcsStorage {
+    async fn list_folders(&self, request: ListFoldersRequest) -> Result<ListFoldersResponse> {
+        let mut folders = vec![];
+        let mut page_token: Option<String> = None;
+        loop {
+            let page = self.list_page(&request.path, true, None, page_token).await?;
+            for prefix in page.prefixes {
+                folders.push(object_path(&self.base, &prefix)?);
+            }
+            if page.next_page_token.is_none() {
+                break;
+            } else {
+                page_token = page.next_page_token;
+            }
+        }
+        Ok(ListFoldersResponse::builder().folders(folders).build())
+    }
+
+    async fn list_files(&self, request: ListFilesRequest) -> Result<ListFilesResponse> {
+        let mut paths = vec![];
+        let mut metadata = vec![];
+        let mut page_token: Option<String> = None;
+        loop {
+            let (objects, next_page_token) = self.list_objects_page(&request, page_token).await?;
+            paths.extend(objects.iter().map(|object| object.path.clone()));
+            if request.with_metadata {
+                metadata.extend(objects);
+            }
+            if next_page_token.is_none() {
+                break;
+            } else {
+                page_token = next_page_token;
+            }
+        }
+        Ok(ListFilesResponse::builder().files(paths).objects(metadata).build())
+    }
+
+    fn list_files_stream(&self, request: ListFilesRequest) -> BoxStream<'_, Result<ObjectMeta>> {
+        stream::try_unfold(Some(None), move |page_token: Option<Option<String>>| {
+            let request = request.clone();
+            async move {
+                if let Some(page_token) = page_token {
+                    let (objects, next_page_token) = self.list_objects_page(&request, page_token).await?;
+                    let objects = stream::iter(objects.into_iter().map(Ok));
+                    Ok::<_, anyhow::Error>(Some((objects, next_page_token.map(Some))))
+                } else {
+                    Ok(None)
+                }
+            }
+        })
+        .try_flatten()
+        .boxed()
+    }
+
+    async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse> {
+        let exists = self.head_meta(&request.path).await?.is_some();
+        Ok(ExistsResponse::builder().exists(exists).build())
+    }
+
+    async fn stat(&self, request: StatRequest) -> Result<StatResponse> {
+        let meta = self
+            .head_meta(&request.path)
+            .await?
+            .ok_or_else(|| StorageError::NotFound(request.path.to_string_lossy().to_string()))?;
+        Ok(StatResponse::builder().meta(meta).build())
+    }
+
+    async fn get(&self, request: GetRequest) -> Result<GetResponse> {
+        let data = self.get_stream(request).await?.read_to_end().await?;
+        Ok(GetResponse::builder().data(data).build())
+    }
+
+    async fn put(&self, request: PutRequest) -> Result<PutResponse> {
+        let length = request.data.len() as u64;
+        let put_request = PutStreamRequest {
+            path: request.path,
+            reader: Box::pin(Cursor::new(request.data)) as StorageReader,
+            length,
+            overwrite: request.overwrite,
+            content_type: request.content_type,
+            cache_control: request.cache_control,
+            acl: request.acl,
+            if_match: request.if_match,
+            if_none_match: request.if_none_match,
+        };
+        self.put_stream(put_request).await
+    }
+
+    async fn get_stream(&self, request: GetRequest) -> Result<GetStreamResponse> {
+        if request.length == Some(0) {
+            return Ok(GetStreamResponse::from(vec![]));
+        }
+        let mut builder = self
+            .request(Method::GET, self.object_url(&request.path))
+            .await?
+            .query(&[("alt", "media")]);
+        if let Some(range) = range_header(&request) {
+            builder = builder.header(RANGE, range);
+        }
+        let response = builder.send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Err(StorageError::NotFound(request.path.to_string_lossy().to_string()).into()),
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(GetStreamResponse::from(vec![])),
+            _ => {
+                let response = check_response(response).await?;
+                let length = response.content_length();
+                Ok(GetStreamResponse {
+                    reader: response_reader(response),
+                    length,
+                })
+            }
+        }
+    }
+
+    // uploads only carry the content type, so cache_control is not applied. Objects larger than
+    // chunk_size go through a resumable session so that a failed chunk is retried on its own.
+    // Object generations are used as etags so that preconditions map onto ifGenerationMatch.
+    async fn put_stream(&self, request: PutStreamRequest) -> Result<PutResponse> {
+        let conditional = request.if_match.is_some() || request.if_none_match.is_some();
+        let mut query = vec![("name", object_key(&self.base, &request.path))];
+        match request.if_match.as_deref() {
+            Some("*") => query.push(("ifGenerationNotMatch", "0".to_string())),
+            Some(if_match) => query.push(("ifGenerationMatch", if_match.to_string())),
+            None => {}
+        }
+        match request.if_none_match.as_deref() {
+            Some("*") => query.push(("ifGenerationMatch", "0".to_string())),
+            Some(if_none_match) => query.push(("ifGenerationNotMatch", if_none_match.to_string())),
+            None if !conditional && !request.overwrite => query.push(("ifGenerationMatch", "0".to_string())),
+            None => {}
+        }
+        if let Some(acl) = request.acl {
+            query.push(("predefinedAcl", acl));
+        }
+        let content_type = request
+            .content_type
+            .unwrap_or_else(|| String::from("application/octet-stream"));
+        let response = if request.length > self.chunk_size {
+            query.push(("uploadType", "resumable".to_string()));
+            self.upload_resumable(&query, content_type, request.reader, request.length)
+                .await?
+        } else {
+            query.push(("uploadType", "media".to_string()));
+            self.request(Method::POST, self.upload_url())
+                .await?
+                .query(&query)
+                .header(CONTENT_TYPE, content_type)
+                .header(CONTENT_LENGTH, request.length)
+                .body(request_body(request.reader, request.length))
+                .send()
+                .await?
+        };
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            if conditional {
+                return Err(StorageError::PreconditionFailed(request.path.to_string_lossy().to_string()).into());
+            }
+            let etag = self.head_meta(&request.path).await?.and_then(|meta| meta.etag);
+            return Ok(PutResponse { etag });
+        }
+        let object = check_response(response).await?.json::<GcsObject>().await?;
+        Ok(PutResponse {
+            etag: object.generation,
+        })
+    }
+
+    async fn copy(&self, request: CopyRequest) -> Result<CopyResponse> {
+        let url = format!(
+            "{}/copyTo/b/{}/o/{}",
+            self.object_url(&request.from),
+            encode_component(&self.bucket),
+            encode_component(&object_key(&self.base, &request.to))
+        );
+        let mut builder = self
+            .request(Method::POST, url)
+            .await?
+            .header(CONTENT_TYPE, "application/json")
+            .body("{}");
+        if !request.overwrite {
+            builder = builder.query(&[("ifGenerationMatch", "0")]);
+        }
+        let response = builder.send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Err(StorageError::NotFound(request.from.to_string_lossy().to_string()).into()),
+            StatusCode::PRECONDITION_FAILED => {
+                Err(StorageError::AlreadyExists(request.to.to_string_lossy().to_string()).into())
+            }
+            _ => {
+                let object = check_response(response).await?.json::<GcsObject>().await?;
+                Ok(CopyResponse {
+                    etag: object.generation,
+                })
+            }
+        }
+    }
+
+    async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse> {
+        for path in request.paths.iter() {
+            let response = self
+                .request(Method::DELETE, self.object_url(path))
+                .await?
+                .send()
+                .await?;
+            if response.status() != StatusCode::NOT_FOUND {
+                check_response(response).await?;
+            }
+        }
+        Ok(RemoveFilesResponse::builder().build())
+    }
+
+    async fn remove_file(&self, request: RemoveFileRequest) -> Result<RemoveFileResponse> {
+        self.remove_files(vec![request.path].into()).await?;
+        Ok(RemoveFileResponse::builder().build())
+    }
+
+    async fn remove_folder(&self, request: RemoveFolderRequest) -> Result<RemoveFolderResponse> {
+        let objects = self.list_files(request.path.clone().into()).await?;
+        if request.non_recursively && !objects.files.is_empty() {
+            return Err(anyhow::anyhow!(
+                "folder {} is not empty",
+                request.path.to_string_lossy()
+            ));
+        }
+        self.remove_files(objects.files.into()).await?;
+        Ok(RemoveFolderResponse::builder().build())
+    }
+}
+
+impl GcsStorage {
+    pub fn from_config(config: &GcsStorageConfig) -> Result<Self> {
+        let client = Client::new();
+        let (token, token_provider) = match &config.credentials {
+            GcsCredentialsConfig::Anonymous => (None, None),
+            GcsCredentialsConfig::Static { token } => (Some(token.clone()), None),
+            GcsCredentialsConfig::Metadata { endpoint } => {
+                let provider = MetadataTokenProvider::builder()
+                    .client(client.clone())
+                    .url(endpoint.as_deref().unwrap_or(DEFAULT_METADATA_ENDPOINT))
+                    .headers(vec![(String::from("Metadata-Flavor"), String::from("Google"))])
+                    .build();
+                (None, Some(Arc::new(provider)))
+            }
+        };
+        Ok(GcsStorage {
+            client,
+            endpoint: config.endpoint.clone(),
+            bucket: config.bucket.clone(),
+            base: PathBuf::from(&config.base),
+            token,
+            token_provider,
+            chunk_size: config.chunk_size,
+        })
+    }
+
+    async fn request(&self, method: Method, url: String) -> Result<RequestBuilder> {
+        let builder = self.client.request(method, url);
+        if let Some(token_provider) = &self.token_provider {
+            return Ok(builder.bearer_auth(token_provider.token().await?));
+        }
+        Ok(match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        })
+    }
+
+    // returns the final response of the session, or the initiating response if a precondition failed.
+    async fn upload_resumable(
+        &self,
+        query: &[(&str, String)],
+        content_type: String,
+        mut reader: StorageReader,
+        length: u64,
+    ) -> Result<Response> {
+        let response = self
+            .request(Method::POST, self.upload_url())
+            .await?
+            .query(query)
+            .header("X-Upload-Content-Type", content_type)
+            .header("X-Upload-Content-Length", length)
+            .header(CONTENT_LENGTH, 0)
+            .send()
+            .await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Ok(response);
+        }
+        let response = check_response(response).await?;
+        let session = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| anyhow::anyhow!("missing resumable upload session"))?
+            .to_string();
+        let chunk_size = self.chunk_size.div_ceil(CHUNK_GRANULARITY).max(1) * CHUNK_GRANULARITY;
+        let mut offset = 0;
+        loop {
+            let end = (offset + chunk_size).min(length);
+            let mut chunk = vec![0u8; (end - offset) as usize];
+            reader.read_exact(&mut chunk).await?;
+            match self.upload_chunk(&session, &chunk, offset, length).await? {
+                UploadStatus::Complete(response) => return Ok(response),
+                UploadStatus::Persisted(_) if end == length => {
+                    return Err(anyhow::anyhow!("resumable upload session was not completed"));
+                }
+                UploadStatus::Persisted(_) => offset = end,
+            }
+        }
+    }
+
+    async fn upload_chunk(&self, session: &str, chunk: &[u8], offset: u64, length: u64) -> Result<UploadStatus> {
+        let end = offset + chunk.len() as u64;
+        let mut persisted = offset;
+        let mut retries = 0;
+        while persisted < end {
+            let data = chunk[(persisted - offset) as usize..].to_vec();
+            let result = self
+                .request(Method::PUT, session.to_string())
+                .await?
+                .header(CONTENT_RANGE, format!("bytes {}-{}/{}", persisted, end - 1, length))
+                .header(CONTENT_LENGTH, data.len())
+                .body(data)
+                .send()
+                .await;
+            let status = match result {
+                Ok(response) if response.status() == StatusCode::PERMANENT_REDIRECT => {
+                    let bytes = persisted_bytes(response.headers());
+                    // a 308 that persisted nothing new is a failed attempt, or the chunk would be resent forever.
+                    if bytes <= persisted {
+                        if retries >= CHUNK_MAX_RETRIES {
+                            return Err(anyhow::anyhow!(
+                                "resumable upload made no progress at byte {}",
+                                persisted
+                            ));
+                        }
+                        chunk_backoff(retries).await;
+                        retries += 1;
+                    }
+                    UploadStatus::Persisted(bytes)
+                }
+                Ok(response) if response.status().is_success() => UploadStatus::Complete(response),
+                Ok(response) if !is_retryable(response.status()) || retries >= CHUNK_MAX_RETRIES => {
+                    return Err(check_response(response)
+                        .await
+                        .err()
+                        .unwrap_or_else(|| anyhow::anyhow!("unexpected resumable upload status")));
+                }
+                Err(error) if retries >= CHUNK_MAX_RETRIES => return Err(error.into()),
+                _ => {
+                    chunk_backoff(retries).await;
+                    retries += 1;
+                    self.upload_status(session, length).await?
+                }
+            };
+            match status {
+                UploadStatus::Persisted(bytes) => persisted = bytes.max(offset),
+                complete => return Ok(complete),
+            }
+        }
+        Ok(UploadStatus::Persisted(persisted))
+    }
+
+    async fn upload_status(&self, session: &str, length: u64) -> Result<UploadStatus> {
+        let response = self
+            .request(Method::PUT, session.to_string())
+            .await?
+            .header(CONTENT_RANGE, format!("bytes */{}", length))
+            .header(CONTENT_LENGTH, 0)
+            .send()
+            .await?;
+        if response.status() == StatusCode::PERMANENT_REDIRECT {
+            return Ok(UploadStatus::Persisted(persisted_bytes(response.headers())));
+        }
+        Ok(UploadStatus::Complete(check_response(response).await?))
+    }
+
+    fn objects_url(&self) -> String {
+        format!(
+            "{}/storage/v1/b/{}/o",
+            self.endpoint.trim_end_matches('/'),
+            encode_component(&self.bucket)
+        )
+    }
+
+    fn object_url(&self, path: &Path) -> String {
+        format!(
+            "{}/{}",
+            self.objects_url(),
+            encode_component(&object_key(&self.base, path))
+        )
+    }
+
+    fn upload_url(&self) -> String {
+        format!(
+            "{}/upload/storage/v1/b/{}/o",
+            self.endpoint.trim_end_matches('/'),
+            encode_component(&self.bucket)
+        )
+    }
+
+    async fn list_page(
+        &self,
+        path: &Path,
+        delimiter: bool,
+        page_size: Option<i64>,
+        page_token: Option<String>,
+    ) -> Result<GcsObjectList> {
+        let mut query = vec![("prefix", object_prefix(&self.base, path))];
+        if delimiter {
+            query.push(("delimiter", "/".to_string()));
+        }
+        if let Some(page_size) = page_size {
+            query.push(("maxResults", page_size.to_string()));
+        }
+        if let Some(page_token) = page_token {
+            query.push(("pageToken", page_token));
+        }
+        let response = self
+            .request(Method::GET, self.objects_url())
+            .await?
+            .query(&query)
+            .send()
+            .await?;
+        Ok(check_response(response).await?.json::<GcsObjectList>().await?)
+    }
+
+    async fn list_objects_page(
+        &self,
+        request: &ListFilesRequest,
+        page_token: Option<String>,
+    ) -> Result<(Vec<ObjectMeta>, Option<String>)> {
+        let page = self
+            .list_page(&request.path, request.non_recursively, request.page_size, page_token)
+            .await?;
+        let objects = page
+            .items
+            .into_iter()
+            .map(|object| self.object_meta(object))
+            .collect::<Result<Vec<_>>>()?;
+        Ok((objects, page.next_page_token))
+    }
+
+    async fn head_meta(&self, path: &Path) -> Result<Option<ObjectMeta>> {
+        let response = self.request(Method::GET, self.object_url(path)).await?.send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let object = check_response(response).await?.json::<GcsObject>().await?;
+        Ok(Some(self.object_meta(object)?))
+    }
+
+    fn object_meta(&self, object: GcsObject) -> Result<ObjectMeta> {
+        Ok(ObjectMeta {
+            path: object_path(&self.base, &object.name)?,
+            size: object.size.and_then(|size| size.parse().ok()),
+            last_modified: object.updated.as_deref().and_then(parse_last_modified),
+            etag: object.generation,
+            content_type: object.content_type,
+        })
+    }
+}
+
+impl std::fmt::Debug for GcsStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GcsStorage")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("base", &self.base)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("token_provider", &self.token_provider)
+            .field("chunk_size", &self.chunk_size)
+            .finish()
+    }
+}
+
+fn persisted_bytes(headers: &HeaderMap) -> u64 {
+    headers
+        .get(RANGE)
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.rsplit_once('-'))
+        .and_then(|(_, end)| end.parse::<u64>().ok())
+        .map_or(0, |end| end + 1)
+}
+
+async fn chunk_backoff(retries: u32) {
+    let backoff = CHUNK_RETRY_BACKOFF_MS.saturating_mul(1 << retries);
+    tokio::time::sleep(Duration::from_millis(backoff)).await;
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn default_endpoint() -> String {
+    String::from("https://storage.googleapis.com")
+}
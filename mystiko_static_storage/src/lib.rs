@@ -1,18 +1,46 @@
+#[cfg(feature = "testing")]
+pub mod conformance;
+
+#[cfg(feature = "azure")]
+mod azure;
 mod cache;
 mod config;
+mod error;
+mod factory;
 mod file;
+#[cfg(feature = "gcs")]
+mod gcs;
+mod memory;
+#[cfg(any(feature = "azure", feature = "gcs"))]
+mod rest;
 mod s3;
 
+#[cfg(feature = "azure")]
+pub use azure::*;
 pub use cache::*;
 pub use config::*;
+pub use error::*;
+pub use factory::*;
 pub use file::*;
+#[cfg(feature = "gcs")]
+pub use gcs::*;
+pub use memory::*;
+#[cfg(any(feature = "azure", feature = "gcs"))]
+pub use rest::MetadataTokenProvider;
 pub use s3::*;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use tokio::io::{AsyncRead, AsyncReadExt};
 use typed_builder::TypedBuilder;
 
+pub type StorageReader = Pin<Box<dyn AsyncRead + Send>>;
+
 #[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct ListFoldersRequest {
@@ -31,6 +59,10 @@ pub struct ListFilesRequest {
     pub path: PathBuf,
     #[builder(default = false)]
     pub non_recursively: bool,
+    #[builder(default, setter(strip_option))]
+    pub page_size: Option<i64>,
+    #[builder(default = false)]
+    pub with_metadata: bool,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
@@ -38,6 +70,34 @@ pub struct ListFilesRequest {
 pub struct ListFilesResponse {
     #[builder(default)]
     pub files: Vec<PathBuf>,
+    #[builder(default)]
+    pub objects: Vec<ObjectMeta>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct ObjectMeta {
+    pub path: PathBuf,
+    #[builder(default, setter(strip_option))]
+    pub size: Option<u64>,
+    #[builder(default, setter(strip_option))]
+    pub last_modified: Option<DateTime<Utc>>,
+    #[builder(default, setter(strip_option))]
+    pub etag: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct StatRequest {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct StatResponse {
+    pub meta: ObjectMeta,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
@@ -59,6 +119,10 @@ pub struct GetRequest {
     pub path: PathBuf,
     #[builder(default = false)]
     pub no_cache: bool,
+    #[builder(default, setter(strip_option))]
+    pub offset: Option<u64>,
+    #[builder(default, setter(strip_option))]
+    pub length: Option<u64>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
@@ -81,11 +145,79 @@ pub struct PutRequest {
     pub cache_control: Option<String>,
     #[builder(default, setter(strip_option))]
     pub acl: Option<String>,
+    /// Enforced atomically by the backend; backends that cannot do so, such as an
+    /// `S3Storage` without a raw client, reject the request with `StorageError::Unsupported`.
+    #[builder(default, setter(strip_option))]
+    pub if_match: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub if_none_match: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct PutResponse {
+    #[builder(default, setter(strip_option))]
+    pub etag: Option<String>,
+}
+
+#[derive(TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct GetStreamResponse {
+    pub reader: StorageReader,
+    #[builder(default, setter(strip_option))]
+    pub length: Option<u64>,
+}
+
+#[derive(TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct PutStreamRequest {
+    pub path: PathBuf,
+    pub reader: StorageReader,
+    pub length: u64,
+    #[builder(default = false)]
+    pub overwrite: bool,
+    #[builder(default, setter(strip_option))]
+    pub content_type: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub cache_control: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub acl: Option<String>,
+    /// Enforced atomically by the backend; backends that cannot do so, such as an
+    /// `S3Storage` without a raw client, reject the request with `StorageError::Unsupported`.
+    #[builder(default, setter(strip_option))]
+    pub if_match: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub if_none_match: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct CopyRequest {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    #[builder(default = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct CopyResponse {
+    #[builder(default, setter(strip_option))]
+    pub etag: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct RenameRequest {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    #[builder(default = false)]
+    pub overwrite: bool,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
-pub struct PutResponse {}
+pub struct RenameResponse {}
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
@@ -125,12 +257,87 @@ pub trait Storage: Send + Sync {
 
     async fn list_files(&self, request: ListFilesRequest) -> Result<ListFilesResponse>;
 
+    fn list_files_stream(&self, request: ListFilesRequest) -> BoxStream<'_, Result<ObjectMeta>> {
+        let with_metadata = request.with_metadata;
+        stream::once(self.list_files(request))
+            .map_ok(move |response| {
+                let objects = if with_metadata {
+                    response.objects
+                } else {
+                    response.files.into_iter().map(ObjectMeta::from).collect()
+                };
+                stream::iter(objects.into_iter().map(Ok))
+            })
+            .try_flatten()
+            .boxed()
+    }
+
     async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse>;
 
+    async fn stat(&self, request: StatRequest) -> Result<StatResponse>;
+
     async fn get(&self, request: GetRequest) -> Result<GetResponse>;
 
     async fn put(&self, request: PutRequest) -> Result<PutResponse>;
 
+    async fn get_stream(&self, request: GetRequest) -> Result<GetStreamResponse> {
+        let response = self.get(request).await?;
+        Ok(GetStreamResponse::from(response.data))
+    }
+
+    async fn put_stream(&self, mut request: PutStreamRequest) -> Result<PutResponse> {
+        let data = request.read_to_end().await?;
+        let put_request = PutRequest {
+            path: request.path,
+            data,
+            overwrite: request.overwrite,
+            content_type: request.content_type,
+            cache_control: request.cache_control,
+            acl: request.acl,
+            if_match: request.if_match,
+            if_none_match: request.if_none_match,
+        };
+        self.put(put_request).await
+    }
+
+    async fn copy(&self, request: CopyRequest) -> Result<CopyResponse> {
+        if !request.overwrite && self.exists(request.to.clone().into()).await?.exists {
+            return Err(StorageError::AlreadyExists(request.to.to_string_lossy().to_string()).into());
+        }
+        let source = self.get_stream(request.from.into()).await?;
+        let response = match source.length {
+            Some(length) => {
+                let put_request = PutStreamRequest::builder()
+                    .path(request.to)
+                    .reader(source.reader)
+                    .length(length)
+                    .overwrite(true)
+                    .build();
+                self.put_stream(put_request).await?
+            }
+            None => {
+                let put_request = PutRequest::builder()
+                    .path(request.to)
+                    .data(source.read_to_end().await?)
+                    .overwrite(true)
+                    .build();
+                self.put(put_request).await?
+            }
+        };
+        Ok(CopyResponse { etag: response.etag })
+    }
+
+    async fn rename(&self, request: RenameRequest) -> Result<RenameResponse> {
+        let copy_request = CopyRequest::builder()
+            .from(request.from.clone())
+            .to(request.to)
+            .overwrite(request.overwrite)
+            .build();
+        self.copy(copy_request).await?;
+        self.remove_file(request.from.into()).await?;
+        Ok(RenameResponse::builder().build())
+    }
+
     async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse>;
 
     async fn remove_file(&self, request: RemoveFileRequest) -> Result<RemoveFileResponse>;
@@ -148,10 +355,18 @@ impl Storage for Box<dyn Storage> {
         self.as_ref().list_files(request).await
     }
 
+    fn list_files_stream(&self, request: ListFilesRequest) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.as_ref().list_files_stream(request)
+    }
+
     async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse> {
         self.as_ref().exists(request).await
     }
 
+    async fn stat(&self, request: StatRequest) -> Result<StatResponse> {
+        self.as_ref().stat(request).await
+    }
+
     async fn get(&self, request: GetRequest) -> Result<GetResponse> {
         self.as_ref().get(request).await
     }
@@ -160,6 +375,22 @@ impl Storage for Box<dyn Storage> {
         self.as_ref().put(request).await
     }
 
+    async fn get_stream(&self, request: GetRequest) -> Result<GetStreamResponse> {
+        self.as_ref().get_stream(request).await
+    }
+
+    async fn put_stream(&self, request: PutStreamRequest) -> Result<PutResponse> {
+        self.as_ref().put_stream(request).await
+    }
+
+    async fn copy(&self, request: CopyRequest) -> Result<CopyResponse> {
+        self.as_ref().copy(request).await
+    }
+
+    async fn rename(&self, request: RenameRequest) -> Result<RenameResponse> {
+        self.as_ref().rename(request).await
+    }
+
     async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse> {
         self.as_ref().remove_files(request).await
     }
@@ -173,6 +404,46 @@ impl Storage for Box<dyn Storage> {
     }
 }
 
+impl GetRequest {
+    pub fn is_ranged(&self) -> bool {
+        self.offset.is_some() || self.length.is_some()
+    }
+}
+
+impl GetStreamResponse {
+    pub async fn read_to_end(mut self) -> Result<Vec<u8>> {
+        let mut data = vec![];
+        self.reader.read_to_end(&mut data).await?;
+        Ok(data)
+    }
+}
+
+impl From<Vec<u8>> for GetStreamResponse {
+    fn from(data: Vec<u8>) -> Self {
+        let length = data.len() as u64;
+        Self::builder()
+            .reader(Box::pin(Cursor::new(data)) as StorageReader)
+            .length(length)
+            .build()
+    }
+}
+
+impl PutStreamRequest {
+    pub async fn read_to_end(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(self.length as usize);
+        (&mut self.reader).take(self.length).read_to_end(&mut data).await?;
+        if data.len() as u64 != self.length {
+            return Err(anyhow::anyhow!(
+                "expected {} bytes for {} but read {}",
+                self.length,
+                self.path.to_string_lossy(),
+                data.len()
+            ));
+        }
+        Ok(data)
+    }
+}
+
 impl<P> From<P> for ListFoldersRequest
 where
     P: AsRef<Path> + Send + Clone,
@@ -191,6 +462,15 @@ where
     }
 }
 
+impl<P> From<P> for ObjectMeta
+where
+    P: AsRef<Path> + Send + Clone,
+{
+    fn from(path: P) -> Self {
+        Self::builder().path(path.as_ref().to_path_buf()).build()
+    }
+}
+
 impl<P> From<P> for ExistsRequest
 where
     P: AsRef<Path> + Send + Clone,
@@ -200,6 +480,15 @@ where
     }
 }
 
+impl<P> From<P> for StatRequest
+where
+    P: AsRef<Path> + Send + Clone,
+{
+    fn from(path: P) -> Self {
+        Self::builder().path(path.as_ref().to_path_buf()).build()
+    }
+}
+
 impl<P> From<P> for GetRequest
 where
     P: AsRef<Path> + Send + Clone,
@@ -218,6 +507,32 @@ where
     }
 }
 
+impl<P, Q> From<(P, Q)> for CopyRequest
+where
+    P: AsRef<Path> + Send + Clone,
+    Q: AsRef<Path> + Send + Clone,
+{
+    fn from((from, to): (P, Q)) -> Self {
+        Self::builder()
+            .from(from.as_ref().to_path_buf())
+            .to(to.as_ref().to_path_buf())
+            .build()
+    }
+}
+
+impl<P, Q> From<(P, Q)> for RenameRequest
+where
+    P: AsRef<Path> + Send + Clone,
+    Q: AsRef<Path> + Send + Clone,
+{
+    fn from((from, to): (P, Q)) -> Self {
+        Self::builder()
+            .from(from.as_ref().to_path_buf())
+            .to(to.as_ref().to_path_buf())
+            .build()
+    }
+}
+
 impl<V> From<V> for RemoveFilesRequest
 where
     V: Into<Vec<PathBuf>>,
@@ -244,3 +559,23 @@ where
         Self::builder().path(path.as_ref().to_path_buf()).build()
     }
 }
+
+pub(crate) fn range_header(request: &GetRequest) -> Option<String> {
+    let offset = request.offset.unwrap_or_default();
+    match request.length {
+        Some(0) => None,
+        Some(length) => match offset.checked_add(length - 1) {
+            Some(end) => Some(format!("bytes={}-{}", offset, end)),
+            None => Some(format!("bytes={}-", offset)),
+        },
+        None if offset > 0 => Some(format!("bytes={}-", offset)),
+        None => None,
+    }
+}
+
+pub(crate) fn parse_last_modified(last_modified: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(last_modified)
+        .or_else(|_| DateTime::parse_from_rfc2822(last_modified))
+        .ok()
+        .map(|last_modified| last_modified.with_timezone(&Utc))
+}
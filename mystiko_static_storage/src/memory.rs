@@ -0,0 +1,238 @@
+use crate::{
+    check_put_preconditions, CopyRequest, CopyResponse, ExistsRequest, ExistsResponse, GetRequest, GetResponse,
+    ListFilesRequest, ListFilesResponse, ListFoldersRequest, ListFoldersResponse, ObjectMeta, PutRequest, PutResponse,
+    RemoveFileRequest, RemoveFileResponse, RemoveFilesRequest, RemoveFilesResponse, RemoveFolderRequest,
+    RemoveFolderResponse, RenameRequest, RenameResponse, StatRequest, StatResponse, Storage, StorageError,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    objects: Arc<RwLock<BTreeMap<PathBuf, MemoryObject>>>,
+}
+
+#[derive(Debug, Clone)]
+struct MemoryObject {
+    data: Arc<Vec<u8>>,
+    last_modified: DateTime<Utc>,
+    etag: String,
+    content_type: Option<String>,
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn list_folders(&self, request: ListFoldersRequest) -> Result<ListFoldersResponse> {
+        let objects = self.read()?;
+        let mut folders = BTreeSet::new();
+        for path in objects.keys() {
+            if let Ok(relative_path) = path.strip_prefix(&request.path) {
+                let mut components = relative_path.components();
+                if let (Some(folder), Some(_)) = (components.next(), components.next()) {
+                    folders.insert(request.path.join(folder));
+                }
+            }
+        }
+        Ok(ListFoldersResponse::builder()
+            .folders(folders.into_iter().collect::<Vec<_>>())
+            .build())
+    }
+
+    async fn list_files(&self, request: ListFilesRequest) -> Result<ListFilesResponse> {
+        let objects = self.read()?;
+        let mut files = vec![];
+        let mut metas = vec![];
+        for (path, object) in objects.iter() {
+            let Ok(relative_path) = path.strip_prefix(&request.path) else {
+                continue;
+            };
+            if relative_path.as_os_str().is_empty()
+                || (request.non_recursively && relative_path.components().count() > 1)
+            {
+                continue;
+            }
+            if request.with_metadata {
+                metas.push(object.meta(path.clone()));
+            }
+            files.push(path.clone());
+        }
+        Ok(ListFilesResponse::builder().files(files).objects(metas).build())
+    }
+
+    async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse> {
+        let objects = self.read()?;
+        let exists = objects.keys().any(|path| path.starts_with(&request.path));
+        Ok(ExistsResponse::builder().exists(exists).build())
+    }
+
+    async fn stat(&self, request: StatRequest) -> Result<StatResponse> {
+        let objects = self.read()?;
+        let object = lookup(&objects, &request.path)?;
+        Ok(StatResponse::builder().meta(object.meta(request.path)).build())
+    }
+
+    async fn get(&self, request: GetRequest) -> Result<GetResponse> {
+        let data = lookup(&*self.read()?, &request.path)?.data.clone();
+        let size = data.len() as u64;
+        let offset = request.offset.unwrap_or_default().min(size);
+        let length = request.length.map_or(size - offset, |length| length.min(size - offset));
+        let data = data[offset as usize..(offset + length) as usize].to_vec();
+        Ok(GetResponse::builder().data(data).build())
+    }
+
+    async fn put(&self, request: PutRequest) -> Result<PutResponse> {
+        let mut objects = self.write()?;
+        check_file_path(&objects, &request.path)?;
+        let current = objects
+            .get(&request.path)
+            .map(|object| object.meta(request.path.clone()));
+        check_put_preconditions(
+            &request.path,
+            current.as_ref(),
+            request.if_match.as_deref(),
+            request.if_none_match.as_deref(),
+        )?;
+        let conditional = request.if_match.is_some() || request.if_none_match.is_some();
+        if !conditional && !request.overwrite {
+            if let Some(current) = current {
+                return Ok(PutResponse { etag: current.etag });
+            }
+        }
+        let object = MemoryObject::new(request.data, request.content_type);
+        let etag = object.etag.clone();
+        objects.insert(request.path, object);
+        Ok(PutResponse { etag: Some(etag) })
+    }
+
+    async fn copy(&self, request: CopyRequest) -> Result<CopyResponse> {
+        let mut objects = self.write()?;
+        let object = copy_object(&objects, &request.from, &request.to, request.overwrite)?;
+        let etag = object.etag.clone();
+        objects.insert(request.to, object);
+        Ok(CopyResponse { etag: Some(etag) })
+    }
+
+    async fn rename(&self, request: RenameRequest) -> Result<RenameResponse> {
+        let mut objects = self.write()?;
+        let object = copy_object(&objects, &request.from, &request.to, request.overwrite)?;
+        objects.remove(&request.from);
+        objects.insert(request.to, object);
+        Ok(RenameResponse::builder().build())
+    }
+
+    async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse> {
+        let mut objects = self.write()?;
+        for path in request.paths.iter() {
+            objects.remove(path);
+        }
+        Ok(RemoveFilesResponse::builder().build())
+    }
+
+    async fn remove_file(&self, request: RemoveFileRequest) -> Result<RemoveFileResponse> {
+        self.remove_files(vec![request.path].into()).await?;
+        Ok(RemoveFileResponse::builder().build())
+    }
+
+    async fn remove_folder(&self, request: RemoveFolderRequest) -> Result<RemoveFolderResponse> {
+        let mut objects = self.write()?;
+        let paths = objects
+            .keys()
+            .filter(|path| path.starts_with(&request.path) && path.as_path() != request.path)
+            .cloned()
+            .collect::<Vec<_>>();
+        if request.non_recursively && !paths.is_empty() {
+            return Err(anyhow::anyhow!(
+                "folder {} is not empty",
+                request.path.to_string_lossy()
+            ));
+        }
+        for path in paths.iter() {
+            objects.remove(path);
+        }
+        Ok(RemoveFolderResponse::builder().build())
+    }
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, BTreeMap<PathBuf, MemoryObject>>> {
+        self.objects
+            .read()
+            .map_err(|err| anyhow::anyhow!("memory storage lock poisoned: {}", err))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, BTreeMap<PathBuf, MemoryObject>>> {
+        self.objects
+            .write()
+            .map_err(|err| anyhow::anyhow!("memory storage lock poisoned: {}", err))
+    }
+}
+
+impl MemoryObject {
+    fn new(data: Vec<u8>, content_type: Option<String>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        Self {
+            etag: format!("{:016x}", hasher.finish()),
+            data: Arc::new(data),
+            last_modified: Utc::now(),
+            content_type,
+        }
+    }
+
+    fn meta(&self, path: PathBuf) -> ObjectMeta {
+        ObjectMeta {
+            path,
+            size: Some(self.data.len() as u64),
+            last_modified: Some(self.last_modified),
+            etag: Some(self.etag.clone()),
+            content_type: self.content_type.clone(),
+        }
+    }
+}
+
+fn lookup<'a>(objects: &'a BTreeMap<PathBuf, MemoryObject>, path: &Path) -> Result<&'a MemoryObject> {
+    objects.get(path).ok_or_else(|| {
+        if objects.keys().any(|key| key.starts_with(path)) {
+            anyhow::anyhow!("{} is a folder", path.to_string_lossy())
+        } else {
+            StorageError::NotFound(path.to_string_lossy().to_string()).into()
+        }
+    })
+}
+
+fn check_file_path(objects: &BTreeMap<PathBuf, MemoryObject>, path: &Path) -> Result<()> {
+    if path.as_os_str().is_empty() || objects.keys().any(|key| key.starts_with(path) && key != path) {
+        return Err(anyhow::anyhow!("{} is a folder", path.to_string_lossy()));
+    }
+    if let Some(parent) = path.ancestors().skip(1).find(|parent| objects.contains_key(*parent)) {
+        return Err(anyhow::anyhow!("{} is a file", parent.to_string_lossy()));
+    }
+    Ok(())
+}
+
+fn copy_object(
+    objects: &BTreeMap<PathBuf, MemoryObject>,
+    from: &Path,
+    to: &Path,
+    overwrite: bool,
+) -> Result<MemoryObject> {
+    let object = lookup(objects, from)?;
+    check_file_path(objects, to)?;
+    if !overwrite && objects.contains_key(to) {
+        return Err(StorageError::AlreadyExists(to.to_string_lossy().to_string()).into());
+    }
+    Ok(MemoryObject {
+        last_modified: Utc::now(),
+        ..object.clone()
+    })
+}
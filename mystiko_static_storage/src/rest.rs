@@ -0,0 +1,124 @@
+use crate::StorageReader;
+use anyhow::Result;
+use futures::TryStreamExt;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{Body, Client, Response};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::sync::Mutex;
+use tokio_util::io::{ReaderStream, StreamReader};
+use typed_builder::TypedBuilder;
+
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+#[cfg(feature = "azure")]
+const SEGMENTS: &AsciiSet = &COMPONENT.remove(b'/');
+
+pub(crate) fn encode_component(value: &str) -> String {
+    utf8_percent_encode(value, COMPONENT).to_string()
+}
+
+#[cfg(feature = "azure")]
+pub(crate) fn encode_segments(value: &str) -> String {
+    utf8_percent_encode(value, SEGMENTS).to_string()
+}
+
+pub(crate) fn object_key(base: &Path, path: &Path) -> String {
+    base.join(path).to_string_lossy().trim_start_matches('/').to_string()
+}
+
+pub(crate) fn object_prefix(base: &Path, path: &Path) -> String {
+    let key = object_key(base, path);
+    let key = key.trim_end_matches('/');
+    if key.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", key)
+    }
+}
+
+pub(crate) fn object_path(base: &Path, key: &str) -> Result<PathBuf> {
+    let base = base.to_string_lossy();
+    let path = Path::new(key.trim_end_matches('/')).strip_prefix(base.trim_start_matches('/'))?;
+    Ok(path.to_path_buf())
+}
+
+pub(crate) fn request_body(reader: StorageReader, length: u64) -> Body {
+    Body::wrap_stream(ReaderStream::new(reader.take(length)))
+}
+
+pub(crate) fn response_reader(response: Response) -> StorageReader {
+    let stream = response.bytes_stream().map_err(std::io::Error::other);
+    Box::pin(StreamReader::new(stream))
+}
+
+pub(crate) async fn check_response(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let path = response.url().path().to_string();
+    let text = response.text().await.unwrap_or_default();
+    Err(anyhow::anyhow!(
+        "request to {} failed with status {}: {}",
+        path,
+        status.as_u16(),
+        text
+    ))
+}
+
+// access tokens are reused until shortly before they expire.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct MetadataTokenProvider {
+    #[builder(default)]
+    pub client: Client,
+    pub url: String,
+    #[builder(default)]
+    pub headers: Vec<(String, String)>,
+    #[builder(default, setter(skip))]
+    cached: Mutex<Option<(String, Instant)>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<serde_json::Value>,
+}
+
+impl MetadataTokenProvider {
+    pub async fn token(&self) -> Result<String> {
+        let mut cached = self.cached.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if Instant::now() + TOKEN_REFRESH_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+        let mut builder = self.client.get(&self.url);
+        for (name, value) in self.headers.iter() {
+            builder = builder.header(name, value);
+        }
+        let response = check_response(builder.send().await?)
+            .await?
+            .json::<TokenResponse>()
+            .await?;
+        let expires_in = match response.expires_in {
+            Some(serde_json::Value::Number(number)) => number.as_u64(),
+            Some(serde_json::Value::String(text)) => text.parse().ok(),
+            _ => None,
+        };
+        let expires_at = Instant::now() + Duration::from_secs(expires_in.unwrap_or_default());
+        *cached = Some((response.access_token.clone(), expires_at));
+        Ok(response.access_token)
+    }
+}
+
+impl std::fmt::Debug for MetadataTokenProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetadataTokenProvider").field("url", &self.url).finish()
+    }
+}
@@ -1,20 +1,42 @@
 use crate::{
-    ExistsRequest, ExistsResponse, GetRequest, GetResponse, ListFilesRequest, ListFilesResponse, ListFoldersRequest,
-    ListFoldersResponse, PutRequest, PutResponse, RemoveFileRequest, RemoveFileResponse, RemoveFilesRequest,
-    RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse, S3StorageConfig, Storage,
+    parse_last_modified, range_header, CopyRequest, CopyResponse, ExistsRequest, ExistsResponse, GetRequest,
+    GetResponse, GetStreamResponse, ListFilesRequest, ListFilesResponse, ListFoldersRequest, ListFoldersResponse,
+    ObjectMeta, PutRequest, PutResponse, PutStreamRequest, RemoveFileRequest, RemoveFileResponse, RemoveFilesRequest,
+    RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse, S3CredentialsConfig, S3MultipartConfig,
+    S3StorageConfig, StatRequest, StatResponse, Storage, StorageError, StorageReader,
 };
 use anyhow::Result;
 use async_trait::async_trait;
-use rusoto_core::{Region, RusotoError};
+use futures::stream::{self, BoxStream, FuturesUnordered, StreamExt, TryStreamExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rusoto_core::credential::{DefaultCredentialsProvider, EnvironmentProvider, ProfileProvider, StaticProvider};
+use rusoto_core::signature::SignedRequest;
+use rusoto_core::{ByteStream, Client, HttpClient, Region, RusotoError};
 use rusoto_s3::{
-    DeleteObjectRequest, GetObjectRequest, HeadObjectError, HeadObjectRequest, ListObjectsV2Request, ObjectIdentifier,
-    PutObjectRequest, S3Client, S3,
+    AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload, CompletedPart,
+    CopyObjectRequest, CreateMultipartUploadRequest, DeleteObjectRequest, GetObjectError, GetObjectOutput,
+    GetObjectRequest, HeadObjectError, HeadObjectRequest, ListObjectsV2Request, ObjectIdentifier, PutObjectError,
+    PutObjectRequest, S3Client, UploadPartCopyRequest, UploadPartRequest, S3,
 };
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 use tokio::io::AsyncReadExt;
+use tokio_util::io::ReaderStream;
 use typed_builder::TypedBuilder;
 
+const MAX_DELETE_OBJECTS: usize = 1000;
+const MAX_COPY_OBJECT_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+const MAX_MULTIPART_PARTS: u64 = 10_000;
+const MIN_MULTIPART_PART_SIZE: u64 = 5 * 1024 * 1024;
+const COPY_SOURCE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'/');
+
 #[derive(TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct S3Storage<C: S3 + Send + Sync = S3Client> {
@@ -23,6 +45,23 @@ pub struct S3Storage<C: S3 + Send + Sync = S3Client> {
     pub s3_bucket: String,
     #[builder(default = default_base())]
     pub base: PathBuf,
+    #[builder(default)]
+    pub multipart: S3MultipartConfig,
+    #[builder(default, setter(strip_option))]
+    pub raw_client: Option<S3RawClient>,
+}
+
+/// Signs the requests rusoto does not model, such as conditional puts.
+#[derive(Clone)]
+pub struct S3RawClient {
+    client: Client,
+    region: Region,
+}
+
+impl S3RawClient {
+    pub fn new(client: Client, region: Region) -> Self {
+        S3RawClient { client, region }
+    }
 }
 
 #[async_trait]
@@ -36,7 +75,7 @@ where
         loop {
             let s3_request = ListObjectsV2Request {
                 bucket: self.s3_bucket.clone(),
-                prefix: Some(self.base.join(&request.path).to_string_lossy().to_string()),
+                prefix: Some(self.prefix(&request.path)),
                 continuation_token: continuation_token.clone(),
                 delimiter: Some("/".to_string()),
                 ..Default::default()
@@ -45,14 +84,15 @@ where
             if let Some(common_prefixes) = result.common_prefixes {
                 for prefix in common_prefixes {
                     if let Some(prefix) = prefix.prefix {
-                        paths.push(Path::new(&prefix).strip_prefix(&self.base)?.to_path_buf());
+                        let folder = Path::new(prefix.trim_end_matches('/')).strip_prefix(&self.base)?;
+                        paths.push(folder.to_path_buf());
                     }
                 }
             }
-            if result.continuation_token.is_none() {
+            if result.next_continuation_token.is_none() {
                 break;
             } else {
-                continuation_token = result.continuation_token;
+                continuation_token = result.next_continuation_token;
             }
         }
         Ok(ListFoldersResponse::builder().folders(paths).build())
@@ -60,61 +100,59 @@ where
 
     async fn list_files(&self, request: ListFilesRequest) -> Result<ListFilesResponse> {
         let mut paths = vec![];
+        let mut metadata = vec![];
         let mut continuation_token: Option<String> = None;
         loop {
-            let request = ListObjectsV2Request {
-                bucket: self.s3_bucket.clone(),
-                prefix: Some(self.base.join(&request.path).to_string_lossy().to_string()),
-                continuation_token: continuation_token.clone(),
-                delimiter: request.non_recursively.then(|| "/".to_string()),
-                ..Default::default()
-            };
-            let result = self.client.list_objects_v2(request).await?;
-            if let Some(contents) = result.contents {
-                for object in contents {
-                    if let Some(key) = object.key {
-                        paths.push(Path::new(&key).strip_prefix(&self.base)?.to_path_buf());
-                    }
-                }
+            let (objects, next_continuation_token) = self.list_objects_page(&request, continuation_token).await?;
+            paths.extend(objects.iter().map(|object| object.path.clone()));
+            if request.with_metadata {
+                metadata.extend(objects);
             }
-            if result.continuation_token.is_none() {
+            if next_continuation_token.is_none() {
                 break;
             } else {
-                continuation_token = result.continuation_token;
+                continuation_token = next_continuation_token;
             }
         }
-        Ok(ListFilesResponse::builder().files(paths).build())
+        Ok(ListFilesResponse::builder().files(paths).objects(metadata).build())
     }
 
-    async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse> {
-        let s3_request = HeadObjectRequest {
-            bucket: self.s3_bucket.clone(),
-            key: self.base.join(request.path).to_string_lossy().to_string(),
-            ..Default::default()
-        };
-        let exists = match self.client.head_object(s3_request).await {
-            Ok(_) => Ok(true),
-            Err(RusotoError::Service(HeadObjectError::NoSuchKey(_))) => Ok(false),
-            Err(RusotoError::Unknown(http_resp)) => {
-                if http_resp.status.as_u16() == 404 {
-                    Ok(false)
+    fn list_files_stream(&self, request: ListFilesRequest) -> BoxStream<'_, Result<ObjectMeta>> {
+        stream::try_unfold(Some(None), move |continuation_token: Option<Option<String>>| {
+            let request = request.clone();
+            async move {
+                if let Some(continuation_token) = continuation_token {
+                    let (objects, next_continuation_token) =
+                        self.list_objects_page(&request, continuation_token).await?;
+                    let objects = stream::iter(objects.into_iter().map(Ok));
+                    Ok::<_, anyhow::Error>(Some((objects, next_continuation_token.map(Some))))
                 } else {
-                    Err(RusotoError::<HeadObjectError>::Unknown(http_resp).into())
+                    Ok(None)
                 }
             }
-            Err(e) => Err(e.into()),
-        };
-        exists.map(|exists| ExistsResponse::builder().exists(exists).build())
+        })
+        .try_flatten()
+        .boxed()
+    }
+
+    async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse> {
+        let exists = self.head_meta(&request.path).await?.is_some();
+        Ok(ExistsResponse::builder().exists(exists).build())
+    }
+
+    async fn stat(&self, request: StatRequest) -> Result<StatResponse> {
+        let meta = self
+            .head_meta(&request.path)
+            .await?
+            .ok_or_else(|| StorageError::NotFound(request.path.to_string_lossy().to_string()))?;
+        Ok(StatResponse::builder().meta(meta).build())
     }
 
     async fn get(&self, request: GetRequest) -> Result<GetResponse> {
-        let s3_request = GetObjectRequest {
-            bucket: self.s3_bucket.clone(),
-            key: self.base.join(request.path).to_string_lossy().to_string(),
-            ..Default::default()
-        };
-        let result = self.client.get_object(s3_request).await?;
-        let data = if let Some(body) = result.body {
+        if request.length == Some(0) {
+            return Ok(GetResponse::builder().data(vec![]).build());
+        }
+        let data = if let Some(body) = self.get_object(&request).await?.and_then(|result| result.body) {
             let mut bytes = vec![];
             body.into_async_read().read_to_end(&mut bytes).await?;
             bytes
@@ -125,10 +163,40 @@ where
     }
 
     async fn put(&self, request: PutRequest) -> Result<PutResponse> {
+        if request.if_match.is_some() || request.if_none_match.is_some() {
+            let length = request.data.len() as u64;
+            let request = PutStreamRequest {
+                path: request.path,
+                reader: Box::pin(Cursor::new(request.data)) as StorageReader,
+                length,
+                overwrite: request.overwrite,
+                content_type: request.content_type,
+                cache_control: request.cache_control,
+                acl: request.acl,
+                if_match: request.if_match,
+                if_none_match: request.if_none_match,
+            };
+            return self.put_conditional(request).await;
+        }
         let path = self.base.join(&request.path);
         let key = path.to_string_lossy().to_string();
-        let exists = self.exists(request.path.clone().into()).await?;
-        if !exists.exists || request.overwrite {
+        if let Some(response) = self.check_put(&request.path, request.overwrite).await? {
+            return Ok(response);
+        }
+        if request.data.len() as u64 > self.multipart.threshold {
+            let create_request = CreateMultipartUploadRequest {
+                bucket: self.s3_bucket.clone(),
+                key,
+                content_type: request.content_type,
+                cache_control: request.cache_control,
+                acl: request.acl,
+                ..Default::default()
+            };
+            let length = request.data.len() as u64;
+            let reader = Box::pin(Cursor::new(request.data)) as StorageReader;
+            self.put_multipart(create_request, MultipartSource::Reader { reader, length })
+                .await
+        } else {
             let s3_request = PutObjectRequest {
                 bucket: self.s3_bucket.clone(),
                 key,
@@ -138,29 +206,136 @@ where
                 acl: request.acl,
                 ..Default::default()
             };
-            self.client.put_object(s3_request).await?;
+            let output = self.client.put_object(s3_request).await?;
+            Ok(PutResponse { etag: output.e_tag })
         }
-        Ok(PutResponse::builder().build())
     }
 
-    async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse> {
-        let mut objects = vec![];
-        for path in request.paths.into_iter() {
-            objects.push(ObjectIdentifier {
-                key: self.base.join(path).to_string_lossy().to_string(),
+    async fn get_stream(&self, request: GetRequest) -> Result<GetStreamResponse> {
+        if request.length == Some(0) {
+            return Ok(GetStreamResponse::from(vec![]));
+        }
+        let response = match self.get_object(&request).await? {
+            Some(GetObjectOutput {
+                body: Some(body),
+                content_length,
+                ..
+            }) => GetStreamResponse {
+                reader: Box::pin(body.into_async_read()) as StorageReader,
+                length: content_length.map(|length| length as u64),
+            },
+            _ => GetStreamResponse::from(vec![]),
+        };
+        Ok(response)
+    }
+
+    async fn put_stream(&self, request: PutStreamRequest) -> Result<PutResponse> {
+        if request.if_match.is_some() || request.if_none_match.is_some() {
+            return self.put_conditional(request).await;
+        }
+        let key = self.base.join(&request.path).to_string_lossy().to_string();
+        if let Some(response) = self.check_put(&request.path, request.overwrite).await? {
+            return Ok(response);
+        }
+        if request.length > self.multipart.threshold {
+            let create_request = CreateMultipartUploadRequest {
+                bucket: self.s3_bucket.clone(),
+                key,
+                content_type: request.content_type,
+                cache_control: request.cache_control,
+                acl: request.acl,
+                ..Default::default()
+            };
+            let source = MultipartSource::Reader {
+                reader: request.reader,
+                length: request.length,
+            };
+            self.put_multipart(create_request, source).await
+        } else {
+            let body = ReaderStream::new(request.reader.take(request.length));
+            let s3_request = PutObjectRequest {
+                bucket: self.s3_bucket.clone(),
+                key,
+                body: Some(ByteStream::new_with_size(body, request.length as usize)),
+                content_length: Some(request.length as i64),
+                content_type: request.content_type,
+                cache_control: request.cache_control,
+                acl: request.acl,
                 ..Default::default()
-            });
+            };
+            let output = self.client.put_object(s3_request).await?;
+            Ok(PutResponse { etag: output.e_tag })
+        }
+    }
+
+    async fn copy(&self, request: CopyRequest) -> Result<CopyResponse> {
+        if !request.overwrite && self.head_meta(&request.to).await?.is_some() {
+            return Err(StorageError::AlreadyExists(request.to.to_string_lossy().to_string()).into());
         }
-        self.client
-            .delete_objects(rusoto_s3::DeleteObjectsRequest {
+        let source = self
+            .head_meta(&request.from)
+            .await?
+            .ok_or_else(|| StorageError::NotFound(request.from.to_string_lossy().to_string()))?;
+        let copy_source = utf8_percent_encode(
+            &format!("{}/{}", self.s3_bucket, self.base.join(&request.from).to_string_lossy()),
+            COPY_SOURCE,
+        )
+        .to_string();
+        let key = self.base.join(&request.to).to_string_lossy().to_string();
+        let size = source.size.unwrap_or_default();
+        // CopyObject is limited to 5 GiB, larger objects have to be copied part by part.
+        if size > MAX_COPY_OBJECT_SIZE {
+            let create_request = CreateMultipartUploadRequest {
                 bucket: self.s3_bucket.clone(),
-                delete: rusoto_s3::Delete {
-                    objects,
-                    quiet: Some(true),
-                },
+                key,
+                content_type: source.content_type,
                 ..Default::default()
-            })
-            .await?;
+            };
+            let etag = self
+                .put_multipart(create_request, MultipartSource::Copy { copy_source, size })
+                .await?
+                .etag;
+            return Ok(CopyResponse { etag });
+        }
+        let s3_request = CopyObjectRequest {
+            bucket: self.s3_bucket.clone(),
+            copy_source,
+            key,
+            ..Default::default()
+        };
+        let result = self.client.copy_object(s3_request).await?;
+        let etag = result.copy_object_result.and_then(|result| result.e_tag);
+        Ok(CopyResponse { etag })
+    }
+
+    async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse> {
+        for paths in request.paths.chunks(MAX_DELETE_OBJECTS) {
+            let objects = paths
+                .iter()
+                .map(|path| ObjectIdentifier {
+                    key: self.base.join(path).to_string_lossy().to_string(),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>();
+            let result = self
+                .client
+                .delete_objects(rusoto_s3::DeleteObjectsRequest {
+                    bucket: self.s3_bucket.clone(),
+                    delete: rusoto_s3::Delete {
+                        objects,
+                        quiet: Some(true),
+                    },
+                    ..Default::default()
+                })
+                .await?;
+            if let Some(error) = result.errors.unwrap_or_default().into_iter().next() {
+                return Err(anyhow::anyhow!(
+                    "failed to remove {}: {}",
+                    error.key.unwrap_or_default(),
+                    error.message.or(error.code).unwrap_or_default()
+                ));
+            }
+        }
         Ok(RemoveFilesResponse::builder().build())
     }
 
@@ -175,19 +350,367 @@ where
     }
 
     async fn remove_folder(&self, request: RemoveFolderRequest) -> Result<RemoveFolderResponse> {
-        let objects = self.list_files(request.path.into()).await?;
+        let objects = self.list_files(request.path.clone().into()).await?;
+        if request.non_recursively && !objects.files.is_empty() {
+            return Err(anyhow::anyhow!(
+                "folder {} is not empty",
+                request.path.to_string_lossy()
+            ));
+        }
         self.remove_files(objects.files.into()).await?;
         Ok(RemoveFolderResponse::builder().build())
     }
 }
 
+impl<C> S3Storage<C>
+where
+    C: S3 + Send + Sync,
+{
+    fn prefix(&self, path: &Path) -> String {
+        let prefix = self.base.join(path).to_string_lossy().to_string();
+        let prefix = prefix.trim_end_matches('/');
+        if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", prefix)
+        }
+    }
+
+    async fn list_objects_page(
+        &self,
+        request: &ListFilesRequest,
+        continuation_token: Option<String>,
+    ) -> Result<(Vec<ObjectMeta>, Option<String>)> {
+        let s3_request = ListObjectsV2Request {
+            bucket: self.s3_bucket.clone(),
+            prefix: Some(self.prefix(&request.path)),
+            continuation_token,
+            delimiter: request.non_recursively.then(|| "/".to_string()),
+            max_keys: request.page_size,
+            ..Default::default()
+        };
+        let result = self.client.list_objects_v2(s3_request).await?;
+        let mut objects = vec![];
+        for object in result.contents.unwrap_or_default() {
+            if let Some(key) = object.key {
+                objects.push(ObjectMeta {
+                    path: Path::new(&key).strip_prefix(&self.base)?.to_path_buf(),
+                    size: object.size.map(|size| size as u64),
+                    last_modified: object.last_modified.as_deref().and_then(parse_last_modified),
+                    etag: object.e_tag,
+                    content_type: None,
+                });
+            }
+        }
+        Ok((objects, result.next_continuation_token))
+    }
+
+    // a range starting past the end of the object is answered with an empty body, like the other backends.
+    async fn get_object(&self, request: &GetRequest) -> Result<Option<GetObjectOutput>> {
+        let s3_request = GetObjectRequest {
+            bucket: self.s3_bucket.clone(),
+            key: self.base.join(&request.path).to_string_lossy().to_string(),
+            range: range_header(request),
+            ..Default::default()
+        };
+        match self.client.get_object(s3_request).await {
+            Ok(result) => Ok(Some(result)),
+            Err(RusotoError::Service(GetObjectError::NoSuchKey(_))) => {
+                Err(StorageError::NotFound(request.path.to_string_lossy().to_string()).into())
+            }
+            Err(RusotoError::Unknown(http_resp)) => match http_resp.status.as_u16() {
+                404 => Err(StorageError::NotFound(request.path.to_string_lossy().to_string()).into()),
+                416 => Ok(None),
+                _ => Err(RusotoError::<GetObjectError>::Unknown(http_resp).into()),
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn head_meta(&self, path: &Path) -> Result<Option<ObjectMeta>> {
+        let s3_request = HeadObjectRequest {
+            bucket: self.s3_bucket.clone(),
+            key: self.base.join(path).to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        match self.client.head_object(s3_request).await {
+            Ok(result) => Ok(Some(ObjectMeta {
+                path: path.to_path_buf(),
+                size: result.content_length.map(|size| size as u64),
+                last_modified: result.last_modified.as_deref().and_then(parse_last_modified),
+                etag: result.e_tag,
+                content_type: result.content_type,
+            })),
+            Err(RusotoError::Service(HeadObjectError::NoSuchKey(_))) => Ok(None),
+            Err(RusotoError::Unknown(http_resp)) => {
+                if http_resp.status.as_u16() == 404 {
+                    Ok(None)
+                } else {
+                    Err(RusotoError::<HeadObjectError>::Unknown(http_resp).into())
+                }
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn check_put(&self, path: &Path, overwrite: bool) -> Result<Option<PutResponse>> {
+        if overwrite {
+            return Ok(None);
+        }
+        Ok(self
+            .head_meta(path)
+            .await?
+            .map(|current| PutResponse { etag: current.etag }))
+    }
+
+    // rusoto does not expose If-Match/If-None-Match on PutObject, so the put is signed by hand and S3 evaluates
+    // the preconditions atomically. They cannot be added to CompleteMultipartUpload either, which limits
+    // conditional puts to a single PutObject.
+    async fn put_conditional(&self, request: PutStreamRequest) -> Result<PutResponse> {
+        let raw_client = self
+            .raw_client
+            .as_ref()
+            .ok_or_else(|| StorageError::Unsupported(String::from("conditional put without a raw S3 client")))?;
+        if request.length > MAX_COPY_OBJECT_SIZE {
+            return Err(StorageError::Unsupported(String::from("conditional put larger than 5 GiB")).into());
+        }
+        let key = self.base.join(&request.path).to_string_lossy().to_string();
+        let request_uri = format!("/{}/{}", self.s3_bucket, key);
+        let mut s3_request = SignedRequest::new("PUT", "s3", &raw_client.region, &request_uri);
+        s3_request.add_optional_header("If-Match", request.if_match.as_ref());
+        s3_request.add_optional_header("If-None-Match", request.if_none_match.as_ref());
+        s3_request.add_optional_header("Content-Type", request.content_type.as_ref());
+        s3_request.add_optional_header("Cache-Control", request.cache_control.as_ref());
+        s3_request.add_optional_header("x-amz-acl", request.acl.as_ref());
+        s3_request.add_header("Content-Length", &request.length.to_string());
+        let body = ReaderStream::new(request.reader.take(request.length));
+        s3_request.set_payload_stream(ByteStream::new_with_size(body, request.length as usize));
+        let mut response = raw_client
+            .client
+            .sign_and_dispatch(s3_request)
+            .await
+            .map_err(RusotoError::<PutObjectError>::from)?;
+        match response.status.as_u16() {
+            200..=299 => Ok(PutResponse {
+                etag: response.headers.get("ETag").cloned(),
+            }),
+            // 404 answers an if_match on a missing object, 409 a concurrent conditional put of the same key.
+            404 | 409 | 412 => Err(StorageError::PreconditionFailed(request.path.to_string_lossy().to_string()).into()),
+            _ => Err(RusotoError::<PutObjectError>::Unknown(response.buffer().await?).into()),
+        }
+    }
+
+    async fn put_multipart(
+        &self,
+        request: CreateMultipartUploadRequest,
+        source: MultipartSource,
+    ) -> Result<PutResponse> {
+        let key = request.key.clone();
+        let created = self.client.create_multipart_upload(request).await?;
+        let upload_id = created
+            .upload_id
+            .ok_or_else(|| anyhow::anyhow!("missing multipart upload id for {}", key))?;
+        let error = match self.complete_multipart(&key, &upload_id, source).await {
+            Ok(etag) => return Ok(PutResponse { etag }),
+            Err(error) => error,
+        };
+        let abort_request = AbortMultipartUploadRequest {
+            bucket: self.s3_bucket.clone(),
+            key,
+            upload_id,
+            ..Default::default()
+        };
+        if let Err(abort_error) = self.client.abort_multipart_upload(abort_request).await {
+            return Err(error.context(format!("failed to abort multipart upload: {}", abort_error)));
+        }
+        Err(error)
+    }
+
+    async fn complete_multipart(&self, key: &str, upload_id: &str, source: MultipartSource) -> Result<Option<String>> {
+        let parts = match source {
+            MultipartSource::Reader { reader, length } => self.upload_parts(key, upload_id, reader, length).await?,
+            MultipartSource::Copy { copy_source, size } => self.copy_parts(key, upload_id, &copy_source, size).await?,
+        };
+        let complete_request = CompleteMultipartUploadRequest {
+            bucket: self.s3_bucket.clone(),
+            key: key.to_string(),
+            upload_id: upload_id.to_string(),
+            multipart_upload: Some(CompletedMultipartUpload { parts: Some(parts) }),
+            ..Default::default()
+        };
+        let output = self.client.complete_multipart_upload(complete_request).await?;
+        Ok(output.e_tag)
+    }
+
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        mut reader: StorageReader,
+        length: u64,
+    ) -> Result<Vec<CompletedPart>> {
+        let part_size = self.part_size(length);
+        let concurrency = self.multipart.concurrency.max(1);
+        let mut parts = vec![];
+        let mut uploads = FuturesUnordered::new();
+        let mut remaining = length;
+        let mut part_number = 1;
+        while remaining > 0 {
+            let size = remaining.min(part_size);
+            let mut data = vec![0u8; size as usize];
+            reader.read_exact(&mut data).await?;
+            uploads.push(self.upload_part(key, upload_id, part_number, data));
+            remaining -= size;
+            part_number += 1;
+            if uploads.len() >= concurrency {
+                if let Some(part) = uploads.try_next().await? {
+                    parts.push(part);
+                }
+            }
+        }
+        while let Some(part) = uploads.try_next().await? {
+            parts.push(part);
+        }
+        parts.sort_by_key(|part| part.part_number);
+        Ok(parts)
+    }
+
+    // s3 rejects parts below 5 MiB (except the last), above 5 GiB, or more than 10,000 of them.
+    fn part_size(&self, length: u64) -> u64 {
+        self.multipart
+            .part_size
+            .max(length.div_ceil(MAX_MULTIPART_PARTS))
+            .clamp(MIN_MULTIPART_PART_SIZE, MAX_COPY_OBJECT_SIZE)
+    }
+
+    async fn copy_parts(&self, key: &str, upload_id: &str, copy_source: &str, size: u64) -> Result<Vec<CompletedPart>> {
+        let part_size = self.part_size(size);
+        let concurrency = self.multipart.concurrency.max(1);
+        let mut parts = stream::iter((0..size.div_ceil(part_size)).map(|index| {
+            let start = index * part_size;
+            let end = (start + part_size).min(size) - 1;
+            self.copy_part(key, upload_id, copy_source, index as i64 + 1, start, end)
+        }))
+        .buffer_unordered(concurrency)
+        .try_collect::<Vec<_>>()
+        .await?;
+        parts.sort_by_key(|part| part.part_number);
+        Ok(parts)
+    }
+
+    async fn copy_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        copy_source: &str,
+        part_number: i64,
+        start: u64,
+        end: u64,
+    ) -> Result<CompletedPart> {
+        let mut retries = 0;
+        loop {
+            let request = UploadPartCopyRequest {
+                bucket: self.s3_bucket.clone(),
+                key: key.to_string(),
+                upload_id: upload_id.to_string(),
+                part_number,
+                copy_source: copy_source.to_string(),
+                copy_source_range: Some(format!("bytes={}-{}", start, end)),
+                ..Default::default()
+            };
+            match self.client.upload_part_copy(request).await {
+                Ok(output) => {
+                    return Ok(CompletedPart {
+                        e_tag: output.copy_part_result.and_then(|result| result.e_tag),
+                        part_number: Some(part_number),
+                    })
+                }
+                Err(_) if retries < self.multipart.max_retries => {
+                    let backoff = self.multipart.retry_backoff_ms.saturating_mul(1 << retries.min(16));
+                    tokio::time::sleep(Duration::from_millis(backoff)).await;
+                    retries += 1;
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    async fn upload_part(&self, key: &str, upload_id: &str, part_number: i64, data: Vec<u8>) -> Result<CompletedPart> {
+        let mut retries = 0;
+        loop {
+            let request = UploadPartRequest {
+                bucket: self.s3_bucket.clone(),
+                key: key.to_string(),
+                upload_id: upload_id.to_string(),
+                part_number,
+                content_length: Some(data.len() as i64),
+                body: Some(data.clone().into()),
+                ..Default::default()
+            };
+            match self.client.upload_part(request).await {
+                Ok(output) => {
+                    return Ok(CompletedPart {
+                        e_tag: output.e_tag,
+                        part_number: Some(part_number),
+                    })
+                }
+                Err(_) if retries < self.multipart.max_retries => {
+                    let backoff = self.multipart.retry_backoff_ms.saturating_mul(1 << retries.min(16));
+                    tokio::time::sleep(Duration::from_millis(backoff)).await;
+                    retries += 1;
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+}
+
+enum MultipartSource {
+    Reader { reader: StorageReader, length: u64 },
+    Copy { copy_source: String, size: u64 },
+}
+
 impl S3Storage<S3Client> {
     pub fn from_config(config: &S3StorageConfig) -> Result<Self> {
-        let client = S3Client::new(Region::from_str(&config.region)?);
+        // rusoto always addresses buckets by path, which custom endpoints such as MinIO and R2 accept.
+        let region = match &config.endpoint {
+            Some(endpoint) => Region::Custom {
+                name: config.region.clone(),
+                endpoint: endpoint.clone(),
+            },
+            None => Region::from_str(&config.region)?,
+        };
+        let dispatcher = HttpClient::new()?;
+        let client = match &config.credentials {
+            S3CredentialsConfig::Default => Client::new_with(DefaultCredentialsProvider::new()?, dispatcher),
+            S3CredentialsConfig::Environment => Client::new_with(EnvironmentProvider::default(), dispatcher),
+            S3CredentialsConfig::Profile { profile } => {
+                let mut provider = ProfileProvider::new()?;
+                if let Some(profile) = profile {
+                    provider.set_profile(profile.clone());
+                }
+                Client::new_with(provider, dispatcher)
+            }
+            S3CredentialsConfig::Static {
+                access_key_id,
+                secret_access_key,
+                session_token,
+            } => {
+                let provider = StaticProvider::new(
+                    access_key_id.clone(),
+                    secret_access_key.clone(),
+                    session_token.clone(),
+                    None,
+                );
+                Client::new_with(provider, dispatcher)
+            }
+        };
         Ok(S3Storage::builder()
-            .client(client)
+            .client(S3Client::new_with_client(client.clone(), region.clone()))
             .s3_bucket(config.bucket.clone())
             .base(PathBuf::from(&config.base))
+            .multipart(config.multipart.clone())
+            .raw_client(S3RawClient::new(client, region))
             .build())
     }
 }
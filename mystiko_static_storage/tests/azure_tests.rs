@@ -0,0 +1,518 @@
+use mockito::{Matcher, Server, ServerGuard};
+use mystiko_static_storage::conformance::check_storage;
+use mystiko_static_storage::{
+    AzureBlobStorage, AzureBlobStorageConfig, AzureCredentialsConfig, CopyRequest, GetRequest, ListFilesRequest,
+    MetadataTokenProvider, PutRequest, PutStreamRequest, Storage, StorageError, StorageReader,
+};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_list_folders() {
+    let mut server = Server::new_async().await;
+    let first_page = server
+        .mock("GET", "/test-container")
+        .match_query(Matcher::Exact(
+            "sv=test&sig=token&restype=container&comp=list&prefix=base%2Fa%2F&delimiter=%2F".into(),
+        ))
+        .match_header("x-ms-version", Matcher::Any)
+        .with_body(list_body(
+            r#"<BlobPrefix><Name>base/a/b/</Name></BlobPrefix>"#,
+            Some("marker-2"),
+        ))
+        .expect(1)
+        .create_async()
+        .await;
+    let second_page = server
+        .mock("GET", "/test-container")
+        .match_query(Matcher::UrlEncoded("marker".into(), "marker-2".into()))
+        .with_body(list_body(r#"<BlobPrefix><Name>base/a/c/</Name></BlobPrefix>"#, None))
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let folders = storage.list_folders("a".into()).await.unwrap().folders;
+    assert_eq!(folders, vec![PathBuf::from("a/b"), PathBuf::from("a/c")]);
+    first_page.assert_async().await;
+    second_page.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_list_files() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/test-container")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("prefix".into(), "base/a/".into()),
+            Matcher::UrlEncoded("delimiter".into(), "/".into()),
+            Matcher::UrlEncoded("maxresults".into(), "10".into()),
+        ]))
+        .with_body(list_body(
+            r#"<Blob>
+                <Name>base/a/test.txt</Name>
+                <Properties>
+                    <Last-Modified>Tue, 02 Jan 2024 03:04:05 GMT</Last-Modified>
+                    <Etag>"0x8DC0000000000001"</Etag>
+                    <Content-Length>11</Content-Length>
+                    <Content-Type>text/plain</Content-Type>
+                </Properties>
+            </Blob>
+            <BlobPrefix><Name>base/a/b/</Name></BlobPrefix>
+            <Blob><Name>base/a/test2.txt</Name><Properties><Content-Length>2</Content-Length></Properties></Blob>"#,
+            None,
+        ))
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let request = ListFilesRequest::builder()
+        .path("a")
+        .non_recursively(true)
+        .page_size(10)
+        .with_metadata(true)
+        .build();
+    let response = storage.list_files(request).await.unwrap();
+    assert_eq!(
+        response.files,
+        vec![PathBuf::from("a/test.txt"), PathBuf::from("a/test2.txt")]
+    );
+    assert_eq!(response.objects[0].size, Some(11));
+    assert_eq!(response.objects[0].etag, Some("\"0x8DC0000000000001\"".to_string()));
+    assert_eq!(response.objects[0].content_type, Some("text/plain".to_string()));
+    assert!(response.objects[0].last_modified.is_some());
+    assert_eq!(response.objects[1].size, Some(2));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_exists_and_stat() {
+    let mut server = Server::new_async().await;
+    server
+        .mock("HEAD", "/test-container/base/a/test.txt")
+        .match_query(Matcher::Any)
+        .with_header("content-length", "2")
+        .with_header("etag", "\"0x1\"")
+        .with_header("last-modified", "Tue, 02 Jan 2024 03:04:05 GMT")
+        .create_async()
+        .await;
+    server
+        .mock("HEAD", "/test-container/base/a/missing.txt")
+        .match_query(Matcher::Any)
+        .with_status(404)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    assert!(storage.exists("a/test.txt".into()).await.unwrap().exists);
+    assert!(!storage.exists("a/missing.txt".into()).await.unwrap().exists);
+    let meta = storage.stat("a/test.txt".into()).await.unwrap().meta;
+    assert_eq!(meta.path, PathBuf::from("a/test.txt"));
+    assert_eq!(meta.size, Some(2));
+    assert_eq!(meta.etag, Some("\"0x1\"".to_string()));
+    assert!(meta.last_modified.is_some());
+    assert!(storage.stat("a/missing.txt".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_get() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/test-container/base/a/test%20file.txt")
+        .match_query(Matcher::Any)
+        .match_header("range", "bytes=6-")
+        .with_status(206)
+        .with_body("world")
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/test-container/base/a/missing.txt")
+        .match_query(Matcher::Any)
+        .with_status(404)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let request = GetRequest::builder().path("a/test file.txt").offset(6u64).build();
+    assert_eq!(storage.get(request).await.unwrap().data, b"world");
+    assert!(storage.get("a/missing.txt".into()).await.is_err());
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("PUT", "/test-container/base/a/test.txt")
+        .match_query(Matcher::Any)
+        .match_header("x-ms-blob-type", "BlockBlob")
+        .match_header("x-ms-blob-content-type", "text/plain")
+        .match_header("x-ms-blob-cache-control", "no-cache")
+        .match_header("if-none-match", Matcher::Missing)
+        .match_body("hello world")
+        .with_status(201)
+        .with_header("etag", "\"0x2\"")
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello world")
+        .content_type("text/plain")
+        .cache_control("no-cache")
+        .overwrite(true)
+        .build();
+    let response = storage.put(request).await.unwrap();
+    assert_eq!(response.etag, Some("\"0x2\"".to_string()));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put_conditional() {
+    let mut server = Server::new_async().await;
+    let create_mock = server
+        .mock("PUT", "/test-container/base/a/test.txt")
+        .match_query(Matcher::Any)
+        .match_header("if-none-match", "*")
+        .with_status(409)
+        .expect(2)
+        .create_async()
+        .await;
+    let update_mock = server
+        .mock("PUT", "/test-container/base/a/test.txt")
+        .match_query(Matcher::Any)
+        .match_header("if-match", "\"0x1\"")
+        .with_status(201)
+        .with_header("etag", "\"0x2\"")
+        .expect(1)
+        .create_async()
+        .await;
+    let stale_mock = server
+        .mock("PUT", "/test-container/base/a/test.txt")
+        .match_query(Matcher::Any)
+        .match_header("if-match", "\"stale\"")
+        .with_status(412)
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("HEAD", "/test-container/base/a/test.txt")
+        .match_query(Matcher::Any)
+        .with_header("etag", "\"0x1\"")
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello")
+        .if_none_match("*")
+        .build();
+    let error = storage.put(request).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::PreconditionFailed(_))
+    ));
+    let response = storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello").build())
+        .await
+        .unwrap();
+    assert_eq!(response.etag, Some("\"0x1\"".to_string()));
+    let request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello")
+        .if_match("\"stale\"")
+        .build();
+    let error = storage.put(request).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::PreconditionFailed(_))
+    ));
+    let request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello")
+        .if_match("\"0x1\"")
+        .build();
+    assert_eq!(storage.put(request).await.unwrap().etag, Some("\"0x2\"".to_string()));
+    create_mock.assert_async().await;
+    update_mock.assert_async().await;
+    stale_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_copy() {
+    let mut server = Server::new_async().await;
+    let source = format!("{}/test-container/base/a/test.txt?sv=test&sig=token", server.url());
+    let exists_mock = server
+        .mock("PUT", "/test-container/base/b/test.txt")
+        .match_query(Matcher::Any)
+        .match_header("x-ms-copy-source", source.as_str())
+        .match_header("if-none-match", "*")
+        .with_status(409)
+        .expect(1)
+        .create_async()
+        .await;
+    let copy_mock = server
+        .mock("PUT", "/test-container/base/b/test.txt")
+        .match_query(Matcher::Any)
+        .match_header("x-ms-copy-source", source.as_str())
+        .match_header("if-none-match", Matcher::Missing)
+        .with_status(202)
+        .with_header("x-ms-copy-status", "pending")
+        .expect(1)
+        .create_async()
+        .await;
+    let poll_mock = server
+        .mock("HEAD", "/test-container/base/b/test.txt")
+        .match_query(Matcher::Any)
+        .with_header("x-ms-copy-status", "success")
+        .with_header("etag", "\"0x3\"")
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let error = storage.copy(("a/test.txt", "b/test.txt").into()).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::AlreadyExists(_))
+    ));
+    let request = CopyRequest::builder()
+        .from("a/test.txt")
+        .to("b/test.txt")
+        .overwrite(true)
+        .build();
+    assert_eq!(storage.copy(request).await.unwrap().etag, Some("\"0x3\"".to_string()));
+    exists_mock.assert_async().await;
+    copy_mock.assert_async().await;
+    poll_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_remove_files() {
+    let mut server = Server::new_async().await;
+    let remove_mock = server
+        .mock("DELETE", "/test-container/base/a/test.txt")
+        .match_query(Matcher::Any)
+        .with_status(202)
+        .expect(1)
+        .create_async()
+        .await;
+    let missing_mock = server
+        .mock("DELETE", "/test-container/base/a/missing.txt")
+        .match_query(Matcher::Any)
+        .with_status(404)
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    storage
+        .remove_files(vec![PathBuf::from("a/test.txt"), PathBuf::from("a/missing.txt")].into())
+        .await
+        .unwrap();
+    remove_mock.assert_async().await;
+    missing_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put_blocks() {
+    let mut server = Server::new_async().await;
+    let block_mocks = [("YmxvY2stMDAwMDAw", "hello "), ("YmxvY2stMDAwMDAx", "world")]
+        .into_iter()
+        .map(|(block_id, body)| {
+            server
+                .mock("PUT", "/test-container/base/a/test.txt")
+                .match_query(Matcher::AllOf(vec![
+                    Matcher::UrlEncoded("comp".into(), "block".into()),
+                    Matcher::UrlEncoded("blockid".into(), block_id.into()),
+                ]))
+                .match_body(body)
+                .with_status(201)
+                .expect(1)
+                .create()
+        })
+        .collect::<Vec<_>>();
+    let commit_mock = server
+        .mock("PUT", "/test-container/base/a/test.txt")
+        .match_query(Matcher::UrlEncoded("comp".into(), "blocklist".into()))
+        .match_header("x-ms-blob-content-type", "text/plain")
+        .match_header("if-none-match", "*")
+        .match_body(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?><BlockList>\
+             <Latest>YmxvY2stMDAwMDAw</Latest><Latest>YmxvY2stMDAwMDAx</Latest></BlockList>",
+        )
+        .with_status(201)
+        .with_header("etag", "\"0x2\"")
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = AzureBlobStorage::builder()
+        .endpoint(server.url())
+        .container("test-container")
+        .base("base")
+        .block_size(6u64)
+        .build();
+    let request = PutStreamRequest::builder()
+        .path("a/test.txt")
+        .reader(Box::pin(Cursor::new(b"hello world".to_vec())) as StorageReader)
+        .length(11u64)
+        .content_type("text/plain")
+        .build();
+    assert_eq!(
+        storage.put_stream(request).await.unwrap().etag,
+        Some("\"0x2\"".to_string())
+    );
+    for mock in block_mocks {
+        mock.assert_async().await;
+    }
+    commit_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_copy_timeout() {
+    let mut server = Server::new_async().await;
+    server
+        .mock("PUT", "/test-container/base/b/test.txt")
+        .match_header("x-ms-copy-source", Matcher::Regex("base/a/test.txt".into()))
+        .with_status(202)
+        .with_header("x-ms-copy-status", "pending")
+        .with_header("x-ms-copy-id", "test-copy")
+        .create_async()
+        .await;
+    server
+        .mock("HEAD", "/test-container/base/b/test.txt")
+        .with_header("x-ms-copy-status", "pending")
+        .with_header("x-ms-copy-id", "test-copy")
+        .create_async()
+        .await;
+    let abort_mock = server
+        .mock("PUT", "/test-container/base/b/test.txt")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("comp".into(), "copy".into()),
+            Matcher::UrlEncoded("copyid".into(), "test-copy".into()),
+        ]))
+        .match_header("x-ms-copy-action", "abort")
+        .with_status(204)
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = AzureBlobStorage::builder()
+        .endpoint(server.url())
+        .container("test-container")
+        .base("base")
+        .copy_timeout(Duration::from_millis(300))
+        .build();
+    let request = CopyRequest::builder()
+        .from("a/test.txt")
+        .to("b/test.txt")
+        .overwrite(true)
+        .build();
+    let error = storage.copy(request).await.unwrap_err();
+    assert!(error.to_string().contains("did not complete"));
+    abort_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_managed_identity() {
+    let mut server = Server::new_async().await;
+    let token_mock = server
+        .mock("GET", "/token")
+        .match_header("metadata", "true")
+        .with_body(r#"{"access_token":"test-access-token","expires_in":"3599"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let blob_mock = server
+        .mock("HEAD", "/test-container/base/a/test.txt")
+        .match_header("authorization", "Bearer test-access-token")
+        .with_header("content-length", "2")
+        .expect(2)
+        .create_async()
+        .await;
+    let token_provider = MetadataTokenProvider::builder()
+        .url(format!("{}/token", server.url()))
+        .headers(vec![("Metadata".to_string(), "true".to_string())])
+        .build();
+    let storage = AzureBlobStorage::builder()
+        .endpoint(server.url())
+        .container("test-container")
+        .base("base")
+        .token_provider(Arc::new(token_provider))
+        .build();
+    assert!(storage.exists("a/test.txt".into()).await.unwrap().exists);
+    assert!(storage.exists("a/test.txt".into()).await.unwrap().exists);
+    token_mock.assert_async().await;
+    blob_mock.assert_async().await;
+}
+
+#[test]
+fn test_from_config() {
+    let config = AzureBlobStorageConfig::builder()
+        .account("mystiko")
+        .base("base")
+        .credentials(AzureCredentialsConfig::Sas {
+            token: "sv=test&sig=token".to_string(),
+        })
+        .build();
+    let storage = AzureBlobStorage::from_config(&config).unwrap();
+    assert_eq!(storage.endpoint, "https://mystiko.blob.core.windows.net");
+    assert_eq!(storage.container, "static");
+    assert_eq!(storage.base, PathBuf::from("base"));
+    assert_eq!(storage.sas_token, Some("sv=test&sig=token".to_string()));
+    assert!(storage.token_provider.is_none());
+    assert!(!format!("{:?}", storage).contains("sig=token"));
+    let config = AzureBlobStorageConfig::builder()
+        .account("mystiko")
+        .credentials(AzureCredentialsConfig::ManagedIdentity {
+            endpoint: Some("http://localhost:8081/token".to_string()),
+            client_id: Some("test-client".to_string()),
+        })
+        .build();
+    let storage = AzureBlobStorage::from_config(&config).unwrap();
+    assert!(storage.sas_token.is_none());
+    assert_eq!(
+        storage.token_provider.unwrap().url,
+        "http://localhost:8081/token?api-version=2018-02-01&resource=https%3A%2F%2Fstorage.azure.com%2F&client_id=test-client"
+    );
+}
+
+// Azurite with a container created up front and a container SAS token, e.g.
+// MYSTIKO_AZURITE_ENDPOINT=http://127.0.0.1:10000/devstoreaccount1 MYSTIKO_AZURITE_SAS_TOKEN=sv=...
+#[tokio::test]
+#[ignore]
+async fn test_conformance_emulator() {
+    let mut config = AzureBlobStorageConfig::builder()
+        .account("devstoreaccount1")
+        .container(std::env::var("MYSTIKO_AZURITE_CONTAINER").unwrap_or_else(|_| String::from("test-container")))
+        .base("mystiko")
+        .endpoint(
+            std::env::var("MYSTIKO_AZURITE_ENDPOINT")
+                .unwrap_or_else(|_| String::from("http://127.0.0.1:10000/devstoreaccount1")),
+        )
+        .build();
+    if let Ok(token) = std::env::var("MYSTIKO_AZURITE_SAS_TOKEN") {
+        config.credentials = AzureCredentialsConfig::Sas { token };
+    }
+    let storage = AzureBlobStorage::from_config(&config).unwrap();
+    check_storage(&storage).await.unwrap();
+}
+
+fn storage(server: &ServerGuard) -> AzureBlobStorage {
+    AzureBlobStorage::builder()
+        .endpoint(server.url())
+        .container("test-container")
+        .base("base")
+        .sas_token("?sv=test&sig=token")
+        .build()
+}
+
+fn list_body(entries: &str, next_marker: Option<&str>) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults ContainerName="test-container">
+    <Blobs>{}</Blobs>
+    <NextMarker>{}</NextMarker>
+</EnumerationResults>"#,
+        entries,
+        next_marker.unwrap_or_default()
+    )
+}
@@ -2,9 +2,10 @@ use anyhow::Result;
 use async_trait::async_trait;
 use mockall::mock;
 use mystiko_static_storage::{
-    ExistsRequest, ExistsResponse, GetRequest, GetResponse, ListFilesRequest, ListFilesResponse, ListFoldersRequest,
-    ListFoldersResponse, PutRequest, PutResponse, RemoveFileRequest, RemoveFileResponse, RemoveFilesRequest,
-    RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse, Storage as StorageTrait,
+    CopyRequest, CopyResponse, ExistsRequest, ExistsResponse, GetRequest, GetResponse, ListFilesRequest,
+    ListFilesResponse, ListFoldersRequest, ListFoldersResponse, PutRequest, PutResponse, RemoveFileRequest,
+    RemoveFileResponse, RemoveFilesRequest, RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse,
+    RenameRequest, RenameResponse, StatRequest, StatResponse, Storage as StorageTrait,
 };
 use std::sync::Arc;
 
@@ -17,8 +18,11 @@ mock! {
         async fn list_folders(&self, request: ListFoldersRequest) -> Result<ListFoldersResponse>;
         async fn list_files(&self, request: ListFilesRequest) -> Result<ListFilesResponse>;
         async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse>;
+        async fn stat(&self, request: StatRequest) -> Result<StatResponse>;
         async fn get(&self, request: GetRequest) -> Result<GetResponse>;
         async fn put(&self, request: PutRequest) -> Result<PutResponse>;
+        async fn copy(&self, request: CopyRequest) -> Result<CopyResponse>;
+        async fn rename(&self, request: RenameRequest) -> Result<RenameResponse>;
         async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse>;
         async fn remove_file(&self, request: RemoveFileRequest) -> Result<RemoveFileResponse>;
         async fn remove_folder(&self, request: RemoveFolderRequest) -> Result<RemoveFolderResponse>;
@@ -40,8 +44,20 @@ async fn test_box_impl() {
         .expect_exists()
         .times(1)
         .returning(|_| Ok(ExistsResponse::default()));
+    storage
+        .expect_stat()
+        .times(1)
+        .returning(|_| Ok(StatResponse::default()));
     storage.expect_get().times(1).returning(|_| Ok(GetResponse::default()));
     storage.expect_put().times(1).returning(|_| Ok(PutResponse::default()));
+    storage
+        .expect_copy()
+        .times(1)
+        .returning(|_| Ok(CopyResponse::default()));
+    storage
+        .expect_rename()
+        .times(1)
+        .returning(|_| Ok(RenameResponse::default()));
     storage
         .expect_remove_files()
         .times(1)
@@ -67,6 +83,10 @@ async fn test_box_impl() {
         storage.exists(ExistsRequest::default()).await.unwrap(),
         ExistsResponse::default()
     );
+    assert_eq!(
+        storage.stat(StatRequest::default()).await.unwrap(),
+        StatResponse::default()
+    );
     assert_eq!(
         storage.get(GetRequest::default()).await.unwrap(),
         GetResponse::default()
@@ -75,6 +95,14 @@ async fn test_box_impl() {
         storage.put(PutRequest::default()).await.unwrap(),
         PutResponse::default()
     );
+    assert_eq!(
+        storage.copy(CopyRequest::default()).await.unwrap(),
+        CopyResponse::default()
+    );
+    assert_eq!(
+        storage.rename(RenameRequest::default()).await.unwrap(),
+        RenameResponse::default()
+    );
     assert_eq!(
         storage.remove_files(RemoveFilesRequest::default()).await.unwrap(),
         RemoveFilesResponse::default()
@@ -1,6 +1,15 @@
-use mystiko_static_storage::{CachedStorage, CachedStorageOptions, FileStorage, GetRequest, PutRequest, Storage};
-use std::path::PathBuf;
+use anyhow::Result;
+use async_trait::async_trait;
+use mystiko_static_storage::{
+    CachedStorage, CachedStorageOptions, CopyRequest, ExistsRequest, ExistsResponse, FileStorage, GetRequest,
+    GetResponse, GetStreamResponse, ListFilesRequest, ListFilesResponse, ListFoldersRequest, ListFoldersResponse,
+    PutRequest, PutResponse, PutStreamRequest, RemoveFileRequest, RemoveFileResponse, RemoveFilesRequest,
+    RemoveFilesResponse, RemoveFolderRequest, RemoveFolderResponse, StatRequest, StatResponse, Storage, StorageReader,
+};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
 
 #[tokio::test]
 async fn test_list_folders() {
@@ -80,6 +89,18 @@ async fn test_exists() {
     assert!(!cache.exists("a/d/file.txt".into()).await.unwrap().exists);
 }
 
+#[tokio::test]
+async fn test_stat() {
+    let (raw, cache, _) = setup().await;
+    raw.put(PutRequest::builder().path("a/file.txt").data(vec![1u8, 2u8]).build())
+        .await
+        .unwrap();
+    let meta = cache.stat("a/file.txt".into()).await.unwrap().meta;
+    assert_eq!(meta.size, Some(2));
+    assert_eq!(meta, raw.stat("a/file.txt".into()).await.unwrap().meta);
+    assert!(cache.stat("a/d/file.txt".into()).await.is_err());
+}
+
 #[tokio::test]
 async fn test_get() {
     let (raw, cache, _) = setup().await;
@@ -106,6 +127,95 @@ async fn test_get() {
     assert_eq!(response.data, b"hello world #2");
 }
 
+#[tokio::test]
+async fn test_get_range() {
+    let (raw, cache, dirs) = setup().await;
+    raw.put(PutRequest::builder().path("a/file.txt").data("hello world #1").build())
+        .await
+        .unwrap();
+    let response = cache
+        .get(
+            GetRequest::builder()
+                .path("a/file.txt")
+                .offset(6u64)
+                .length(5u64)
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.data, b"world");
+    let cached = std::fs::read(dirs[1].path().join("a/file.txt")).unwrap();
+    assert_eq!(cached, b"hello world #1");
+    raw.put(
+        PutRequest::builder()
+            .path("a/file.txt")
+            .data("hello world #2")
+            .overwrite(true)
+            .build(),
+    )
+    .await
+    .unwrap();
+    let response = cache
+        .get(GetRequest::builder().path("a/file.txt").offset(12u64).build())
+        .await
+        .unwrap();
+    assert_eq!(response.data, b"#1");
+    let response = cache
+        .get_stream(GetRequest::builder().path("a/file.txt").length(5u64).build())
+        .await
+        .unwrap();
+    assert_eq!(response.read_to_end().await.unwrap(), b"hello");
+    raw.put(PutRequest::builder().path("b/file.txt").data("hello world #3").build())
+        .await
+        .unwrap();
+    let response = cache
+        .get_stream(GetRequest::builder().path("b/file.txt").offset(12u64).build())
+        .await
+        .unwrap();
+    assert_eq!(response.read_to_end().await.unwrap(), b"#3");
+    assert_eq!(cache.get("b/file.txt".into()).await.unwrap().data, b"hello world #3");
+}
+
+#[tokio::test]
+async fn test_get_stream() {
+    let (raw, cache, _) = setup().await;
+    raw.put(PutRequest::builder().path("a/file.txt").data("hello world #1").build())
+        .await
+        .unwrap();
+    let response = cache.get_stream("a/file.txt".into()).await.unwrap();
+    assert_eq!(response.length, Some(14));
+    assert_eq!(response.read_to_end().await.unwrap(), b"hello world #1");
+    raw.put(
+        PutRequest::builder()
+            .path("a/file.txt")
+            .data("hello world #2")
+            .overwrite(true)
+            .build(),
+    )
+    .await
+    .unwrap();
+    let response = cache.get_stream("a/file.txt".into()).await.unwrap();
+    assert_eq!(response.read_to_end().await.unwrap(), b"hello world #1");
+    let response = cache
+        .get_stream(GetRequest::builder().path("a/file.txt").no_cache(true).build())
+        .await
+        .unwrap();
+    assert_eq!(response.read_to_end().await.unwrap(), b"hello world #2");
+}
+
+#[tokio::test]
+async fn test_put_stream() {
+    let (raw, cache, _) = setup().await;
+    let request = PutStreamRequest::builder()
+        .path("a/file.txt")
+        .reader(Box::pin(std::io::Cursor::new(b"hello world".to_vec())) as StorageReader)
+        .length(11u64)
+        .build();
+    cache.put_stream(request).await.unwrap();
+    assert_eq!(raw.get("a/file.txt".into()).await.unwrap().data, b"hello world");
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello world");
+}
+
 #[tokio::test]
 async fn test_put() {
     let (raw, cache, _) = setup().await;
@@ -126,6 +236,77 @@ async fn test_put() {
     assert_eq!(get_response.data, b"hello world #2");
 }
 
+#[tokio::test]
+async fn test_put_conditional() {
+    let (raw, cache, _) = setup().await;
+    let etag = raw
+        .put(PutRequest::builder().path("a/file.txt").data("hello world #1").build())
+        .await
+        .unwrap()
+        .etag
+        .unwrap();
+    let put_request = PutRequest::builder()
+        .path("a/file.txt")
+        .data("hello world #2")
+        .if_none_match("*")
+        .build();
+    assert!(cache.put(put_request).await.is_err());
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello world #1");
+    let put_request = PutRequest::builder()
+        .path("a/file.txt")
+        .data("hello world #3")
+        .if_match(etag)
+        .build();
+    assert!(cache.put(put_request).await.unwrap().etag.is_some());
+    assert_eq!(raw.get("a/file.txt".into()).await.unwrap().data, b"hello world #3");
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello world #3");
+}
+
+#[tokio::test]
+async fn test_copy() {
+    let (raw, cache, _) = setup().await;
+    raw.put(PutRequest::builder().path("a/file.txt").data("hello world #1").build())
+        .await
+        .unwrap();
+    raw.put(PutRequest::builder().path("b/file.txt").data("hello world #2").build())
+        .await
+        .unwrap();
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello world #1");
+    assert_eq!(cache.get("b/file.txt".into()).await.unwrap().data, b"hello world #2");
+    let copy_request = CopyRequest::builder()
+        .from("a/file.txt")
+        .to("b/file.txt")
+        .overwrite(true)
+        .build();
+    cache.copy(copy_request).await.unwrap();
+    assert_eq!(raw.get("b/file.txt".into()).await.unwrap().data, b"hello world #1");
+    assert_eq!(cache.get("b/file.txt".into()).await.unwrap().data, b"hello world #1");
+    raw.put(PutRequest::builder().path("c/file.txt").data("hello world #3").build())
+        .await
+        .unwrap();
+    let copy_request = CopyRequest::builder()
+        .from("c/file.txt")
+        .to("b/file.txt")
+        .overwrite(true)
+        .build();
+    cache.copy(copy_request).await.unwrap();
+    assert_eq!(cache.get("b/file.txt".into()).await.unwrap().data, b"hello world #3");
+}
+
+#[tokio::test]
+async fn test_rename() {
+    let (raw, cache, _) = setup().await;
+    cache
+        .put(PutRequest::builder().path("a/file.txt").data("hello world #1").build())
+        .await
+        .unwrap();
+    cache.rename(("a/file.txt", "b/file.txt").into()).await.unwrap();
+    assert!(!raw.exists("a/file.txt".into()).await.unwrap().exists);
+    assert!(cache.get("a/file.txt".into()).await.is_err());
+    assert_eq!(raw.get("b/file.txt".into()).await.unwrap().data, b"hello world #1");
+    assert_eq!(cache.get("b/file.txt".into()).await.unwrap().data, b"hello world #1");
+}
+
 #[tokio::test]
 async fn test_remove_files() {
     let (raw, cache, _) = setup().await;
@@ -210,6 +391,242 @@ async fn test_remove_folder() {
     assert!(cache.get("a/file.txt".into()).await.is_err());
 }
 
+#[tokio::test]
+async fn test_max_size_eviction() {
+    let (raw, cache, dirs) = setup_with_options(Some(8), None, false).await;
+    for path in ["a.txt", "b.txt", "c.txt"] {
+        raw.put(PutRequest::builder().path(path).data("data").build())
+            .await
+            .unwrap();
+    }
+    cache.get("a.txt".into()).await.unwrap();
+    cache.get("b.txt".into()).await.unwrap();
+    cache.get("a.txt".into()).await.unwrap();
+    cache.get("c.txt".into()).await.unwrap();
+    let cache_dir = dirs[1].path();
+    assert!(cache_dir.join("a.txt").exists());
+    assert!(!cache_dir.join("b.txt").exists());
+    assert!(cache_dir.join("c.txt").exists());
+    assert_eq!(cache.get("b.txt".into()).await.unwrap().data, b"data");
+    assert!(!cache_dir.join("a.txt").exists());
+}
+
+#[tokio::test]
+async fn test_max_size_on_startup() {
+    let raw_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache_files = FileStorage::new(cache_dir.path()).await.unwrap();
+    for path in ["a.txt", "b.txt", "c.txt"] {
+        cache_files
+            .put(PutRequest::builder().path(path).data("data").build())
+            .await
+            .unwrap();
+    }
+    let options = CachedStorageOptions::<PathBuf, FileStorage> {
+        cache_folder: PathBuf::from(cache_dir.path()),
+        raw: Arc::new(FileStorage::new(raw_dir.path()).await.unwrap()),
+        max_size: Some(8),
+        ttl: None,
+        revalidate: false,
+    };
+    CachedStorage::new(options).await.unwrap();
+    let files = cache_files.list_files(PathBuf::default().into()).await.unwrap().files;
+    assert_eq!(files.len(), 2);
+}
+
+#[tokio::test]
+async fn test_startup_skips_temp_files() {
+    let raw_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache_files = FileStorage::new(cache_dir.path()).await.unwrap();
+    for path in ["a.txt", "b.txt"] {
+        cache_files
+            .put(PutRequest::builder().path(path).data("data").build())
+            .await
+            .unwrap();
+    }
+    std::fs::write(cache_dir.path().join(".c.txt.1.0.tmp"), vec![0u8; 64]).unwrap();
+    let options = CachedStorageOptions::<PathBuf, FileStorage> {
+        cache_folder: PathBuf::from(cache_dir.path()),
+        raw: Arc::new(FileStorage::new(raw_dir.path()).await.unwrap()),
+        max_size: Some(8),
+        ttl: None,
+        revalidate: false,
+    };
+    CachedStorage::new(options).await.unwrap();
+    assert!(cache_dir.path().join("a.txt").exists());
+    assert!(cache_dir.path().join("b.txt").exists());
+}
+
+#[tokio::test]
+async fn test_startup_with_relative_folder() {
+    let raw_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir_in(".").unwrap();
+    let cache_folder = cache_dir
+        .path()
+        .strip_prefix(std::env::current_dir().unwrap())
+        .unwrap_or(cache_dir.path())
+        .to_path_buf();
+    let cache_files = FileStorage::new(&cache_folder).await.unwrap();
+    for path in ["a/b/a.txt", "a/b.txt", "c.txt"] {
+        cache_files
+            .put(PutRequest::builder().path(path).data("data").build())
+            .await
+            .unwrap();
+    }
+    let options = CachedStorageOptions::<PathBuf, FileStorage> {
+        cache_folder,
+        raw: Arc::new(FileStorage::new(raw_dir.path()).await.unwrap()),
+        max_size: Some(8),
+        ttl: None,
+        revalidate: false,
+    };
+    CachedStorage::new(options).await.unwrap();
+    let files = cache_files.list_files(PathBuf::default().into()).await.unwrap().files;
+    assert_eq!(files.len(), 2);
+}
+
+#[tokio::test]
+async fn test_ttl() {
+    let (raw, cache, _) = setup_with_options(None, Some(Duration::ZERO), false).await;
+    raw.put(PutRequest::builder().path("a/file.txt").data("hello world #1").build())
+        .await
+        .unwrap();
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello world #1");
+    raw.put(
+        PutRequest::builder()
+            .path("a/file.txt")
+            .data("hello world #2")
+            .overwrite(true)
+            .build(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello world #2");
+}
+
+#[tokio::test]
+async fn test_revalidate() {
+    let (raw, cache, dirs) = setup_with_options(None, None, true).await;
+    raw.put(PutRequest::builder().path("a/file.txt").data("hello world").build())
+        .await
+        .unwrap();
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello world");
+    std::fs::write(dirs[1].path().join("a/file.txt"), "cached world").unwrap();
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"cached world");
+    raw.put(
+        PutRequest::builder()
+            .path("a/file.txt")
+            .data("hello mystiko")
+            .overwrite(true)
+            .build(),
+    )
+    .await
+    .unwrap();
+    let response = cache.get_stream("a/file.txt".into()).await.unwrap();
+    assert_eq!(response.read_to_end().await.unwrap(), b"hello mystiko");
+}
+
+#[tokio::test]
+async fn test_revalidate_after_restart() {
+    let (raw, cache, dirs) = setup_with_options(None, None, true).await;
+    raw.put(PutRequest::builder().path("a/file.txt").data("hello world").build())
+        .await
+        .unwrap();
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello world");
+    let cached_path = dirs[1].path().join("a/file.txt");
+    let modified = std::fs::metadata(&cached_path).unwrap().modified().unwrap();
+    let options = CachedStorageOptions::<PathBuf, FileStorage> {
+        cache_folder: PathBuf::from(dirs[1].path()),
+        raw: raw.clone(),
+        max_size: None,
+        ttl: None,
+        revalidate: true,
+    };
+    let cache = CachedStorage::new(options).await.unwrap();
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello world");
+    assert_eq!(std::fs::metadata(&cached_path).unwrap().modified().unwrap(), modified);
+    raw.put(
+        PutRequest::builder()
+            .path("a/file.txt")
+            .data("hello mystiko")
+            .overwrite(true)
+            .build(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(cache.get("a/file.txt".into()).await.unwrap().data, b"hello mystiko");
+}
+
+#[tokio::test]
+async fn test_concurrent_reads_with_eviction() {
+    let (raw, cache, _) = setup_with_options(Some(8), None, false).await;
+    let paths = (0..16).map(|index| format!("{}.txt", index)).collect::<Vec<_>>();
+    for path in paths.iter() {
+        raw.put(PutRequest::builder().path(path.as_str()).data(path.clone()).build())
+            .await
+            .unwrap();
+    }
+    let reads = paths.iter().map(|path| async {
+        let response = cache.get_stream(path.as_str().into()).await.unwrap();
+        response.read_to_end().await.unwrap()
+    });
+    let contents = futures::future::join_all(reads).await;
+    for (path, content) in paths.iter().zip(contents.into_iter()) {
+        assert_eq!(content, path.as_bytes());
+    }
+}
+
+#[tokio::test]
+async fn test_slow_fetch_does_not_block_eviction() {
+    let raw_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let raw = Arc::new(FileStorage::new(raw_dir.path()).await.unwrap());
+    for path in ["a.txt", "b.txt", "slow.txt"] {
+        raw.put(PutRequest::builder().path(path).data("data").build())
+            .await
+            .unwrap();
+    }
+    let gate = Arc::new(Notify::new());
+    let gated = GatedStorage {
+        raw,
+        gate: gate.clone(),
+    };
+    let options = CachedStorageOptions::<PathBuf, GatedStorage> {
+        cache_folder: PathBuf::from(cache_dir.path()),
+        raw: Arc::new(gated),
+        max_size: Some(4),
+        ttl: None,
+        revalidate: false,
+    };
+    let cache = CachedStorage::new(options).await.unwrap();
+    let slow = cache.get("slow.txt".into());
+    let fast = async {
+        cache.get("a.txt".into()).await.unwrap();
+        cache.get("b.txt".into()).await.unwrap();
+        gate.notify_one();
+    };
+    let (slow, _) = tokio::join!(slow, fast);
+    assert_eq!(slow.unwrap().data, b"data");
+    assert!(!cache_dir.path().join("a.txt").exists());
+}
+
+#[tokio::test]
+async fn test_warm() {
+    let (raw, cache, dirs) = setup().await;
+    for path in ["a/1.txt", "a/b/2.txt", "c/3.txt"] {
+        raw.put(PutRequest::builder().path(path).data("data").build())
+            .await
+            .unwrap();
+    }
+    assert_eq!(cache.warm("a").await.unwrap(), 2);
+    let cache_dir = dirs[1].path();
+    assert!(cache_dir.join("a/1.txt").exists());
+    assert!(cache_dir.join("a/b/2.txt").exists());
+    assert!(!cache_dir.join("c/3.txt").exists());
+    assert_eq!(cache.warm("a").await.unwrap(), 0);
+}
+
 async fn setup() -> (Arc<FileStorage>, CachedStorage<FileStorage>, Vec<tempfile::TempDir>) {
     let raw_dir = tempfile::tempdir().unwrap();
     let cache_dir = tempfile::tempdir().unwrap();
@@ -224,3 +641,74 @@ async fn setup() -> (Arc<FileStorage>, CachedStorage<FileStorage>, Vec<tempfile:
     .unwrap();
     (raw, cache, vec![raw_dir, cache_dir])
 }
+
+async fn setup_with_options(
+    max_size: Option<u64>,
+    ttl: Option<Duration>,
+    revalidate: bool,
+) -> (Arc<FileStorage>, CachedStorage<FileStorage>, Vec<tempfile::TempDir>) {
+    let raw_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let raw = Arc::new(FileStorage::new(raw_dir.path()).await.unwrap());
+    let options = CachedStorageOptions::<PathBuf, FileStorage> {
+        cache_folder: PathBuf::from(cache_dir.path()),
+        raw: raw.clone(),
+        max_size,
+        ttl,
+        revalidate,
+    };
+    let cache = CachedStorage::new(options).await.unwrap();
+    (raw, cache, vec![raw_dir, cache_dir])
+}
+
+// holds reads of slow.txt until the gate opens.
+struct GatedStorage {
+    raw: Arc<FileStorage>,
+    gate: Arc<Notify>,
+}
+
+#[async_trait]
+impl Storage for GatedStorage {
+    async fn list_folders(&self, request: ListFoldersRequest) -> Result<ListFoldersResponse> {
+        self.raw.list_folders(request).await
+    }
+
+    async fn list_files(&self, request: ListFilesRequest) -> Result<ListFilesResponse> {
+        self.raw.list_files(request).await
+    }
+
+    async fn exists(&self, request: ExistsRequest) -> Result<ExistsResponse> {
+        self.raw.exists(request).await
+    }
+
+    async fn stat(&self, request: StatRequest) -> Result<StatResponse> {
+        self.raw.stat(request).await
+    }
+
+    async fn get(&self, request: GetRequest) -> Result<GetResponse> {
+        self.raw.get(request).await
+    }
+
+    async fn put(&self, request: PutRequest) -> Result<PutResponse> {
+        self.raw.put(request).await
+    }
+
+    async fn get_stream(&self, request: GetRequest) -> Result<GetStreamResponse> {
+        if request.path == Path::new("slow.txt") {
+            self.gate.notified().await;
+        }
+        self.raw.get_stream(request).await
+    }
+
+    async fn remove_files(&self, request: RemoveFilesRequest) -> Result<RemoveFilesResponse> {
+        self.raw.remove_files(request).await
+    }
+
+    async fn remove_file(&self, request: RemoveFileRequest) -> Result<RemoveFileResponse> {
+        self.raw.remove_file(request).await
+    }
+
+    async fn remove_folder(&self, request: RemoveFolderRequest) -> Result<RemoveFolderResponse> {
+        self.raw.remove_folder(request).await
+    }
+}
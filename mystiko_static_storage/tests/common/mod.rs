@@ -0,0 +1,527 @@
+// the mocked rusoto traits fix the error types, so they cannot be boxed.
+#![allow(clippy::result_large_err)]
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use futures::executor::block_on;
+use mockall::mock;
+use percent_encoding::percent_decode_str;
+use rusoto_core::request::BufferedHttpResponse;
+use rusoto_core::{ByteStream, RusotoError};
+use rusoto_s3::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncReadExt;
+
+type Objects = Arc<Mutex<BTreeMap<String, Vec<u8>>>>;
+
+pub fn fake_s3_client() -> MockS3Client {
+    let objects: Objects = Arc::default();
+    let mut client = MockS3Client::new();
+    let store = objects.clone();
+    client
+        .expect_list_objects_v2()
+        .returning(move |request| Ok(list_objects(&store, request)));
+    let store = objects.clone();
+    client.expect_head_object().returning(move |request| {
+        let objects = store.lock().unwrap();
+        match objects.get(&request.key) {
+            Some(data) => Ok(HeadObjectOutput {
+                content_length: Some(data.len() as i64),
+                e_tag: Some(etag(data)),
+                ..Default::default()
+            }),
+            None => Err(not_found()),
+        }
+    });
+    let store = objects.clone();
+    client.expect_get_object().returning(move |request| {
+        let objects = store.lock().unwrap();
+        let data = objects
+            .get(&request.key)
+            .ok_or_else(|| RusotoError::Service(GetObjectError::NoSuchKey(request.key.clone())))?;
+        let data = match request.range.as_deref().and_then(|range| range.strip_prefix("bytes=")) {
+            Some(range) => {
+                let (start, end) = range.split_once('-').unwrap();
+                let start = start.parse::<usize>().unwrap();
+                if start >= data.len() {
+                    return Err(unknown_error(http::status::StatusCode::RANGE_NOT_SATISFIABLE));
+                }
+                let end = end.parse::<usize>().map_or(data.len(), |end| (end + 1).min(data.len()));
+                data[start..end.max(start)].to_vec()
+            }
+            None => data.clone(),
+        };
+        Ok(GetObjectOutput {
+            content_length: Some(data.len() as i64),
+            e_tag: Some(etag(&data)),
+            body: Some(ByteStream::from(data)),
+            ..Default::default()
+        })
+    });
+    let store = objects.clone();
+    client.expect_put_object().returning(move |request| {
+        let mut data = vec![];
+        if let Some(body) = request.body {
+            block_on(body.into_async_read().read_to_end(&mut data)).unwrap();
+        }
+        let e_tag = etag(&data);
+        store.lock().unwrap().insert(request.key, data);
+        Ok(PutObjectOutput {
+            e_tag: Some(e_tag),
+            ..Default::default()
+        })
+    });
+    let store = objects.clone();
+    client.expect_copy_object().returning(move |request| {
+        let mut objects = store.lock().unwrap();
+        let copy_source = percent_decode_str(&request.copy_source).decode_utf8_lossy();
+        let source = copy_source
+            .strip_prefix(&format!("{}/", request.bucket))
+            .unwrap_or_default();
+        let data = objects.get(source).cloned().ok_or_else(not_found)?;
+        let e_tag = etag(&data);
+        objects.insert(request.key, data);
+        Ok(CopyObjectOutput {
+            copy_object_result: Some(CopyObjectResult {
+                e_tag: Some(e_tag),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    });
+    let store = objects.clone();
+    client.expect_delete_object().returning(move |request| {
+        store.lock().unwrap().remove(&request.key);
+        Ok(DeleteObjectOutput::default())
+    });
+    let store = objects;
+    client.expect_delete_objects().returning(move |request| {
+        let mut objects = store.lock().unwrap();
+        for object in request.delete.objects.iter() {
+            objects.remove(&object.key);
+        }
+        Ok(DeleteObjectsOutput::default())
+    });
+    client
+}
+
+fn list_objects(objects: &Objects, request: ListObjectsV2Request) -> ListObjectsV2Output {
+    let objects = objects.lock().unwrap();
+    let prefix = request.prefix.unwrap_or_default();
+    let mut contents = vec![];
+    let mut common_prefixes = BTreeSet::new();
+    for (key, data) in objects.iter().filter(|(key, _)| key.starts_with(&prefix)) {
+        let rest = &key[prefix.len()..];
+        match (request.delimiter.as_deref(), rest.find('/')) {
+            (Some("/"), Some(index)) => {
+                common_prefixes.insert(format!("{}{}", prefix, &rest[..=index]));
+            }
+            _ => contents.push(Object {
+                key: Some(key.clone()),
+                size: Some(data.len() as i64),
+                e_tag: Some(etag(data)),
+                ..Default::default()
+            }),
+        }
+    }
+    let start = request
+        .continuation_token
+        .map_or(0, |token| token.parse::<usize>().unwrap());
+    let end = request.max_keys.map_or(contents.len(), |max_keys| {
+        (start + max_keys as usize).min(contents.len())
+    });
+    ListObjectsV2Output {
+        common_prefixes: Some(
+            common_prefixes
+                .into_iter()
+                .map(|prefix| CommonPrefix { prefix: Some(prefix) })
+                .collect(),
+        ),
+        next_continuation_token: (end < contents.len()).then(|| end.to_string()),
+        contents: Some(contents[start..end].to_vec()),
+        ..Default::default()
+    }
+}
+
+fn etag(data: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+fn not_found<E>() -> RusotoError<E> {
+    unknown_error(http::status::StatusCode::NOT_FOUND)
+}
+
+fn unknown_error<E>(status: http::status::StatusCode) -> RusotoError<E> {
+    RusotoError::Unknown(BufferedHttpResponse {
+        status,
+        body: bytes::Bytes::default(),
+        headers: http::HeaderMap::default(),
+    })
+}
+
+mock! {
+    #[derive(Debug)]
+    pub S3Client {}
+
+    #[async_trait]
+    impl S3 for S3Client {
+        async fn abort_multipart_upload(
+            &self,
+            input: AbortMultipartUploadRequest,
+        ) -> Result<AbortMultipartUploadOutput, RusotoError<AbortMultipartUploadError>>;
+        async fn complete_multipart_upload(
+            &self,
+            input: CompleteMultipartUploadRequest,
+        ) -> Result<CompleteMultipartUploadOutput, RusotoError<CompleteMultipartUploadError>>;
+        async fn copy_object(&self, input: CopyObjectRequest) -> Result<CopyObjectOutput, RusotoError<CopyObjectError>>;
+        async fn create_bucket(
+            &self,
+            input: CreateBucketRequest,
+        ) -> Result<CreateBucketOutput, RusotoError<CreateBucketError>>;
+        async fn create_multipart_upload(
+            &self,
+            input: CreateMultipartUploadRequest,
+        ) -> Result<CreateMultipartUploadOutput, RusotoError<CreateMultipartUploadError>>;
+        async fn delete_bucket(&self, input: DeleteBucketRequest) -> Result<(), RusotoError<DeleteBucketError>>;
+        async fn delete_bucket_analytics_configuration(
+            &self,
+            input: DeleteBucketAnalyticsConfigurationRequest,
+        ) -> Result<(), RusotoError<DeleteBucketAnalyticsConfigurationError>>;
+        async fn delete_bucket_cors(
+            &self,
+            input: DeleteBucketCorsRequest,
+        ) -> Result<(), RusotoError<DeleteBucketCorsError>>;
+        async fn delete_bucket_encryption(
+            &self,
+            input: DeleteBucketEncryptionRequest,
+        ) -> Result<(), RusotoError<DeleteBucketEncryptionError>>;
+        async fn delete_bucket_intelligent_tiering_configuration(
+            &self,
+            input: DeleteBucketIntelligentTieringConfigurationRequest,
+        ) -> Result<(), RusotoError<DeleteBucketIntelligentTieringConfigurationError>>;
+        async fn delete_bucket_inventory_configuration(
+            &self,
+            input: DeleteBucketInventoryConfigurationRequest,
+        ) -> Result<(), RusotoError<DeleteBucketInventoryConfigurationError>>;
+        async fn delete_bucket_lifecycle(
+            &self,
+            input: DeleteBucketLifecycleRequest,
+        ) -> Result<(), RusotoError<DeleteBucketLifecycleError>>;
+        async fn delete_bucket_metrics_configuration(
+            &self,
+            input: DeleteBucketMetricsConfigurationRequest,
+        ) -> Result<(), RusotoError<DeleteBucketMetricsConfigurationError>>;
+        async fn delete_bucket_ownership_controls(
+            &self,
+            input: DeleteBucketOwnershipControlsRequest,
+        ) -> Result<(), RusotoError<DeleteBucketOwnershipControlsError>>;
+        async fn delete_bucket_policy(
+            &self,
+            input: DeleteBucketPolicyRequest,
+        ) -> Result<(), RusotoError<DeleteBucketPolicyError>>;
+        async fn delete_bucket_replication(
+            &self,
+            input: DeleteBucketReplicationRequest,
+        ) -> Result<(), RusotoError<DeleteBucketReplicationError>>;
+        async fn delete_bucket_tagging(
+            &self,
+            input: DeleteBucketTaggingRequest,
+        ) -> Result<(), RusotoError<DeleteBucketTaggingError>>;
+        async fn delete_bucket_website(
+            &self,
+            input: DeleteBucketWebsiteRequest,
+        ) -> Result<(), RusotoError<DeleteBucketWebsiteError>>;
+        async fn delete_object(
+            &self,
+            input: DeleteObjectRequest,
+        ) -> Result<DeleteObjectOutput, RusotoError<DeleteObjectError>>;
+        async fn delete_object_tagging(
+            &self,
+            input: DeleteObjectTaggingRequest,
+        ) -> Result<DeleteObjectTaggingOutput, RusotoError<DeleteObjectTaggingError>>;
+        async fn delete_objects(
+            &self,
+            input: DeleteObjectsRequest,
+        ) -> Result<DeleteObjectsOutput, RusotoError<DeleteObjectsError>>;
+        async fn delete_public_access_block(
+            &self,
+            input: DeletePublicAccessBlockRequest,
+        ) -> Result<(), RusotoError<DeletePublicAccessBlockError>>;
+        async fn get_bucket_accelerate_configuration(
+            &self,
+            input: GetBucketAccelerateConfigurationRequest,
+        ) -> Result<GetBucketAccelerateConfigurationOutput, RusotoError<GetBucketAccelerateConfigurationError>>;
+        async fn get_bucket_acl(
+            &self,
+            input: GetBucketAclRequest,
+        ) -> Result<GetBucketAclOutput, RusotoError<GetBucketAclError>>;
+        async fn get_bucket_analytics_configuration(
+            &self,
+            input: GetBucketAnalyticsConfigurationRequest,
+        ) -> Result<GetBucketAnalyticsConfigurationOutput, RusotoError<GetBucketAnalyticsConfigurationError>>;
+        async fn get_bucket_cors(
+            &self,
+            input: GetBucketCorsRequest,
+        ) -> Result<GetBucketCorsOutput, RusotoError<GetBucketCorsError>>;
+        async fn get_bucket_encryption(
+            &self,
+            input: GetBucketEncryptionRequest,
+        ) -> Result<GetBucketEncryptionOutput, RusotoError<GetBucketEncryptionError>>;
+        async fn get_bucket_intelligent_tiering_configuration(
+            &self,
+            input: GetBucketIntelligentTieringConfigurationRequest,
+        ) -> Result<
+            GetBucketIntelligentTieringConfigurationOutput,
+            RusotoError<GetBucketIntelligentTieringConfigurationError>,
+        >;
+        async fn get_bucket_inventory_configuration(
+            &self,
+            input: GetBucketInventoryConfigurationRequest,
+        ) -> Result<GetBucketInventoryConfigurationOutput, RusotoError<GetBucketInventoryConfigurationError>>;
+        async fn get_bucket_lifecycle(
+            &self,
+            input: GetBucketLifecycleRequest,
+        ) -> Result<GetBucketLifecycleOutput, RusotoError<GetBucketLifecycleError>>;
+        async fn get_bucket_lifecycle_configuration(
+            &self,
+            input: GetBucketLifecycleConfigurationRequest,
+        ) -> Result<GetBucketLifecycleConfigurationOutput, RusotoError<GetBucketLifecycleConfigurationError>>;
+        async fn get_bucket_location(
+            &self,
+            input: GetBucketLocationRequest,
+        ) -> Result<GetBucketLocationOutput, RusotoError<GetBucketLocationError>>;
+        async fn get_bucket_logging(
+            &self,
+            input: GetBucketLoggingRequest,
+        ) -> Result<GetBucketLoggingOutput, RusotoError<GetBucketLoggingError>>;
+        async fn get_bucket_metrics_configuration(
+            &self,
+            input: GetBucketMetricsConfigurationRequest,
+        ) -> Result<GetBucketMetricsConfigurationOutput, RusotoError<GetBucketMetricsConfigurationError>>;
+        async fn get_bucket_notification(
+            &self,
+            input: GetBucketNotificationConfigurationRequest,
+        ) -> Result<NotificationConfigurationDeprecated, RusotoError<GetBucketNotificationError>>;
+        async fn get_bucket_notification_configuration(
+            &self,
+            input: GetBucketNotificationConfigurationRequest,
+        ) -> Result<NotificationConfiguration, RusotoError<GetBucketNotificationConfigurationError>>;
+        async fn get_bucket_ownership_controls(
+            &self,
+            input: GetBucketOwnershipControlsRequest,
+        ) -> Result<GetBucketOwnershipControlsOutput, RusotoError<GetBucketOwnershipControlsError>>;
+        async fn get_bucket_policy(
+            &self,
+            input: GetBucketPolicyRequest,
+        ) -> Result<GetBucketPolicyOutput, RusotoError<GetBucketPolicyError>>;
+        async fn get_bucket_policy_status(
+            &self,
+            input: GetBucketPolicyStatusRequest,
+        ) -> Result<GetBucketPolicyStatusOutput, RusotoError<GetBucketPolicyStatusError>>;
+        async fn get_bucket_replication(
+            &self,
+            input: GetBucketReplicationRequest,
+        ) -> Result<GetBucketReplicationOutput, RusotoError<GetBucketReplicationError>>;
+        async fn get_bucket_request_payment(
+            &self,
+            input: GetBucketRequestPaymentRequest,
+        ) -> Result<GetBucketRequestPaymentOutput, RusotoError<GetBucketRequestPaymentError>>;
+        async fn get_bucket_tagging(
+            &self,
+            input: GetBucketTaggingRequest,
+        ) -> Result<GetBucketTaggingOutput, RusotoError<GetBucketTaggingError>>;
+        async fn get_bucket_versioning(
+            &self,
+            input: GetBucketVersioningRequest,
+        ) -> Result<GetBucketVersioningOutput, RusotoError<GetBucketVersioningError>>;
+        async fn get_bucket_website(
+            &self,
+            input: GetBucketWebsiteRequest,
+        ) -> Result<GetBucketWebsiteOutput, RusotoError<GetBucketWebsiteError>>;
+        async fn get_object(&self, input: GetObjectRequest) -> Result<GetObjectOutput, RusotoError<GetObjectError>>;
+        async fn get_object_acl(
+            &self,
+            input: GetObjectAclRequest,
+        ) -> Result<GetObjectAclOutput, RusotoError<GetObjectAclError>>;
+        async fn get_object_legal_hold(
+            &self,
+            input: GetObjectLegalHoldRequest,
+        ) -> Result<GetObjectLegalHoldOutput, RusotoError<GetObjectLegalHoldError>>;
+        async fn get_object_lock_configuration(
+            &self,
+            input: GetObjectLockConfigurationRequest,
+        ) -> Result<GetObjectLockConfigurationOutput, RusotoError<GetObjectLockConfigurationError>>;
+        async fn get_object_retention(
+            &self,
+            input: GetObjectRetentionRequest,
+        ) -> Result<GetObjectRetentionOutput, RusotoError<GetObjectRetentionError>>;
+        async fn get_object_tagging(
+            &self,
+            input: GetObjectTaggingRequest,
+        ) -> Result<GetObjectTaggingOutput, RusotoError<GetObjectTaggingError>>;
+        async fn get_object_torrent(
+            &self,
+            input: GetObjectTorrentRequest,
+        ) -> Result<GetObjectTorrentOutput, RusotoError<GetObjectTorrentError>>;
+        async fn get_public_access_block(
+            &self,
+            input: GetPublicAccessBlockRequest,
+        ) -> Result<GetPublicAccessBlockOutput, RusotoError<GetPublicAccessBlockError>>;
+        async fn head_bucket(&self, input: HeadBucketRequest) -> Result<(), RusotoError<HeadBucketError>>;
+        async fn head_object(&self, input: HeadObjectRequest) -> Result<HeadObjectOutput, RusotoError<HeadObjectError>>;
+        async fn list_bucket_analytics_configurations(
+            &self,
+            input: ListBucketAnalyticsConfigurationsRequest,
+        ) -> Result<ListBucketAnalyticsConfigurationsOutput, RusotoError<ListBucketAnalyticsConfigurationsError>>;
+        async fn list_bucket_intelligent_tiering_configurations(
+            &self,
+            input: ListBucketIntelligentTieringConfigurationsRequest,
+        ) -> Result<
+            ListBucketIntelligentTieringConfigurationsOutput,
+            RusotoError<ListBucketIntelligentTieringConfigurationsError>,
+        >;
+        async fn list_bucket_inventory_configurations(
+            &self,
+            input: ListBucketInventoryConfigurationsRequest,
+        ) -> Result<ListBucketInventoryConfigurationsOutput, RusotoError<ListBucketInventoryConfigurationsError>>;
+        async fn list_bucket_metrics_configurations(
+            &self,
+            input: ListBucketMetricsConfigurationsRequest,
+        ) -> Result<ListBucketMetricsConfigurationsOutput, RusotoError<ListBucketMetricsConfigurationsError>>;
+        async fn list_buckets(&self) -> Result<ListBucketsOutput, RusotoError<ListBucketsError>>;
+        async fn list_multipart_uploads(
+            &self,
+            input: ListMultipartUploadsRequest,
+        ) -> Result<ListMultipartUploadsOutput, RusotoError<ListMultipartUploadsError>>;
+        async fn list_object_versions(
+            &self,
+            input: ListObjectVersionsRequest,
+        ) -> Result<ListObjectVersionsOutput, RusotoError<ListObjectVersionsError>>;
+        async fn list_objects(&self, input: ListObjectsRequest)
+                              -> Result<ListObjectsOutput, RusotoError<ListObjectsError>>;
+        async fn list_objects_v2(
+            &self,
+            input: ListObjectsV2Request,
+        ) -> Result<ListObjectsV2Output, RusotoError<ListObjectsV2Error>>;
+        async fn list_parts(&self, input: ListPartsRequest) -> Result<ListPartsOutput, RusotoError<ListPartsError>>;
+        async fn put_bucket_accelerate_configuration(
+            &self,
+            input: PutBucketAccelerateConfigurationRequest,
+        ) -> Result<(), RusotoError<PutBucketAccelerateConfigurationError>>;
+        async fn put_bucket_acl(&self, input: PutBucketAclRequest) -> Result<(), RusotoError<PutBucketAclError>>;
+        async fn put_bucket_analytics_configuration(
+            &self,
+            input: PutBucketAnalyticsConfigurationRequest,
+        ) -> Result<(), RusotoError<PutBucketAnalyticsConfigurationError>>;
+        async fn put_bucket_cors(&self, input: PutBucketCorsRequest) -> Result<(), RusotoError<PutBucketCorsError>>;
+        async fn put_bucket_encryption(
+            &self,
+            input: PutBucketEncryptionRequest,
+        ) -> Result<(), RusotoError<PutBucketEncryptionError>>;
+        async fn put_bucket_intelligent_tiering_configuration(
+            &self,
+            input: PutBucketIntelligentTieringConfigurationRequest,
+        ) -> Result<(), RusotoError<PutBucketIntelligentTieringConfigurationError>>;
+        async fn put_bucket_inventory_configuration(
+            &self,
+            input: PutBucketInventoryConfigurationRequest,
+        ) -> Result<(), RusotoError<PutBucketInventoryConfigurationError>>;
+        async fn put_bucket_lifecycle(
+            &self,
+            input: PutBucketLifecycleRequest,
+        ) -> Result<(), RusotoError<PutBucketLifecycleError>>;
+        async fn put_bucket_lifecycle_configuration(
+            &self,
+            input: PutBucketLifecycleConfigurationRequest,
+        ) -> Result<(), RusotoError<PutBucketLifecycleConfigurationError>>;
+        async fn put_bucket_logging(
+            &self,
+            input: PutBucketLoggingRequest,
+        ) -> Result<(), RusotoError<PutBucketLoggingError>>;
+        async fn put_bucket_metrics_configuration(
+            &self,
+            input: PutBucketMetricsConfigurationRequest,
+        ) -> Result<(), RusotoError<PutBucketMetricsConfigurationError>>;
+        async fn put_bucket_notification(
+            &self,
+            input: PutBucketNotificationRequest,
+        ) -> Result<(), RusotoError<PutBucketNotificationError>>;
+        async fn put_bucket_notification_configuration(
+            &self,
+            input: PutBucketNotificationConfigurationRequest,
+        ) -> Result<(), RusotoError<PutBucketNotificationConfigurationError>>;
+        async fn put_bucket_ownership_controls(
+            &self,
+            input: PutBucketOwnershipControlsRequest,
+        ) -> Result<(), RusotoError<PutBucketOwnershipControlsError>>;
+        async fn put_bucket_policy(&self, input: PutBucketPolicyRequest) -> Result<(), RusotoError<PutBucketPolicyError>>;
+        async fn put_bucket_replication(
+            &self,
+            input: PutBucketReplicationRequest,
+        ) -> Result<(), RusotoError<PutBucketReplicationError>>;
+        async fn put_bucket_request_payment(
+            &self,
+            input: PutBucketRequestPaymentRequest,
+        ) -> Result<(), RusotoError<PutBucketRequestPaymentError>>;
+        async fn put_bucket_tagging(
+            &self,
+            input: PutBucketTaggingRequest,
+        ) -> Result<(), RusotoError<PutBucketTaggingError>>;
+        async fn put_bucket_versioning(
+            &self,
+            input: PutBucketVersioningRequest,
+        ) -> Result<(), RusotoError<PutBucketVersioningError>>;
+        async fn put_bucket_website(
+            &self,
+            input: PutBucketWebsiteRequest,
+        ) -> Result<(), RusotoError<PutBucketWebsiteError>>;
+        async fn put_object(&self, input: PutObjectRequest) -> Result<PutObjectOutput, RusotoError<PutObjectError>>;
+        async fn put_object_acl(
+            &self,
+            input: PutObjectAclRequest,
+        ) -> Result<PutObjectAclOutput, RusotoError<PutObjectAclError>>;
+        async fn put_object_legal_hold(
+            &self,
+            input: PutObjectLegalHoldRequest,
+        ) -> Result<PutObjectLegalHoldOutput, RusotoError<PutObjectLegalHoldError>>;
+        async fn put_object_lock_configuration(
+            &self,
+            input: PutObjectLockConfigurationRequest,
+        ) -> Result<PutObjectLockConfigurationOutput, RusotoError<PutObjectLockConfigurationError>>;
+        async fn put_object_retention(
+            &self,
+            input: PutObjectRetentionRequest,
+        ) -> Result<PutObjectRetentionOutput, RusotoError<PutObjectRetentionError>>;
+        async fn put_object_tagging(
+            &self,
+            input: PutObjectTaggingRequest,
+        ) -> Result<PutObjectTaggingOutput, RusotoError<PutObjectTaggingError>>;
+        async fn put_public_access_block(
+            &self,
+            input: PutPublicAccessBlockRequest,
+        ) -> Result<(), RusotoError<PutPublicAccessBlockError>>;
+        async fn restore_object(
+            &self,
+            input: RestoreObjectRequest,
+        ) -> Result<RestoreObjectOutput, RusotoError<RestoreObjectError>>;
+        async fn select_object_content(
+            &self,
+            input: SelectObjectContentRequest,
+        ) -> Result<SelectObjectContentOutput, RusotoError<SelectObjectContentError>>;
+        async fn upload_part(&self, input: UploadPartRequest) -> Result<UploadPartOutput, RusotoError<UploadPartError>>;
+        async fn upload_part_copy(
+            &self,
+            input: UploadPartCopyRequest,
+        ) -> Result<UploadPartCopyOutput, RusotoError<UploadPartCopyError>>;
+        async fn write_get_object_response(
+            &self,
+            input: WriteGetObjectResponseRequest,
+        ) -> Result<(), RusotoError<WriteGetObjectResponseError>>;
+    }
+
+}
@@ -1,4 +1,7 @@
-use mystiko_static_storage::{FileStorageConfig, S3StorageConfig, StorageCacheConfig, StorageType};
+use mystiko_static_storage::{
+    AzureBlobStorageConfig, AzureCredentialsConfig, FileStorageConfig, GcsCredentialsConfig, GcsStorageConfig,
+    S3CredentialsConfig, S3MultipartConfig, S3StorageConfig, StorageCacheConfig, StorageType,
+};
 
 #[test]
 fn test_default_storage_config() {
@@ -12,6 +15,53 @@ fn test_default_s3_storage_config() {
     assert_eq!(config.bucket, "static.mystiko.network".to_string());
     assert_eq!(config.region, "us-east-1".to_string());
     assert_eq!(config.base, "/".to_string());
+    assert!(config.endpoint.is_none());
+    assert_eq!(config.credentials, S3CredentialsConfig::Default);
+    assert_eq!(config.multipart, S3MultipartConfig::default());
+}
+
+#[test]
+fn test_s3_compatible_storage_config() {
+    let config: S3StorageConfig = serde_json::from_str(
+        r#"{
+            "endpoint": "http://localhost:9000",
+            "credentials": { "source": "static", "access_key_id": "minio", "secret_access_key": "minio123" }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(config.endpoint, Some("http://localhost:9000".to_string()));
+    assert_eq!(
+        config.credentials,
+        S3CredentialsConfig::Static {
+            access_key_id: "minio".to_string(),
+            secret_access_key: "minio123".to_string(),
+            session_token: None,
+        }
+    );
+    assert!(!format!("{:?}", config).contains("minio123"));
+    let config: S3StorageConfig = serde_json::from_str(r#"{"credentials":{"source":"env"}}"#).unwrap();
+    assert_eq!(config.credentials, S3CredentialsConfig::Environment);
+    let config: S3StorageConfig =
+        serde_json::from_str(r#"{"credentials":{"source":"profile","profile":"r2"}}"#).unwrap();
+    assert_eq!(
+        config.credentials,
+        S3CredentialsConfig::Profile {
+            profile: Some("r2".to_string())
+        }
+    );
+}
+
+#[test]
+fn test_default_s3_multipart_config() {
+    let config = S3MultipartConfig::default();
+    assert_eq!(config.threshold, 64 * 1024 * 1024);
+    assert_eq!(config.part_size, 16 * 1024 * 1024);
+    assert_eq!(config.concurrency, 4);
+    assert_eq!(config.max_retries, 3);
+    assert_eq!(config.retry_backoff_ms, 200);
+    let config: S3StorageConfig = serde_json::from_str(r#"{"multipart":{"threshold":1024}}"#).unwrap();
+    assert_eq!(config.multipart.threshold, 1024);
+    assert_eq!(config.multipart.part_size, 16 * 1024 * 1024);
 }
 
 #[test]
@@ -25,4 +75,80 @@ fn test_default_storage_cache_config() {
     let config = StorageCacheConfig::default();
     assert!(!config.enabled);
     assert!(config.path.is_none());
+    assert!(config.max_size.is_none());
+    assert!(config.ttl_seconds.is_none());
+    assert!(!config.revalidate);
+}
+
+#[test]
+fn test_default_gcs_storage_config() {
+    let config = GcsStorageConfig::default();
+    assert_eq!(config.bucket, "static.mystiko.network".to_string());
+    assert_eq!(config.base, "".to_string());
+    assert_eq!(config.endpoint, "https://storage.googleapis.com".to_string());
+    assert_eq!(config.credentials, GcsCredentialsConfig::Anonymous);
+    assert_eq!(config.chunk_size, 8 * 1024 * 1024);
+    let config: GcsStorageConfig =
+        serde_json::from_str(r#"{"credentials":{"source":"static","token":"gcs-secret"}}"#).unwrap();
+    assert_eq!(
+        config.credentials,
+        GcsCredentialsConfig::Static {
+            token: "gcs-secret".to_string()
+        }
+    );
+    assert!(!format!("{:?}", config).contains("gcs-secret"));
+    let config: GcsStorageConfig = serde_json::from_str(r#"{"credentials":{"source":"metadata"}}"#).unwrap();
+    assert_eq!(config.credentials, GcsCredentialsConfig::Metadata { endpoint: None });
+}
+
+#[test]
+fn test_default_azure_blob_storage_config() {
+    let config: AzureBlobStorageConfig = serde_json::from_str(r#"{"account":"mystiko"}"#).unwrap();
+    assert_eq!(config.container, "static".to_string());
+    assert_eq!(config.endpoint(), "https://mystiko.blob.core.windows.net".to_string());
+    assert_eq!(config.credentials, AzureCredentialsConfig::Anonymous);
+    assert_eq!(config.block_size, 8 * 1024 * 1024);
+    assert_eq!(config.copy_timeout_seconds, 600);
+    let config: AzureBlobStorageConfig =
+        serde_json::from_str(r#"{"account":"mystiko","credentials":{"source":"sas","token":"sv=x&sig=azure-secret"}}"#)
+            .unwrap();
+    assert_eq!(
+        config.credentials,
+        AzureCredentialsConfig::Sas {
+            token: "sv=x&sig=azure-secret".to_string()
+        }
+    );
+    assert!(!format!("{:?}", config).contains("azure-secret"));
+    let config: AzureBlobStorageConfig = serde_json::from_str(
+        r#"{"account":"mystiko","credentials":{"source":"managed_identity","client_id":"test-client"}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.credentials,
+        AzureCredentialsConfig::ManagedIdentity {
+            endpoint: None,
+            client_id: Some("test-client".to_string())
+        }
+    );
+    let config = AzureBlobStorageConfig::builder()
+        .account("devstoreaccount1")
+        .endpoint("http://127.0.0.1:10000/devstoreaccount1")
+        .build();
+    assert_eq!(config.endpoint(), "http://127.0.0.1:10000/devstoreaccount1".to_string());
+}
+
+#[test]
+fn test_storage_type_serde() {
+    let types: Vec<StorageType> = serde_json::from_str(r#"["s3","file","gcs","azure_blob"]"#).unwrap();
+    let error = serde_json::from_str::<StorageType>(r#""ftp""#).unwrap_err();
+    assert!(error.to_string().contains("unknown variant `ftp`"));
+    assert_eq!(
+        types,
+        vec![
+            StorageType::S3,
+            StorageType::File,
+            StorageType::Gcs,
+            StorageType::AzureBlob
+        ]
+    );
 }
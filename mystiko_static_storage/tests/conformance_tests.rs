@@ -0,0 +1,60 @@
+mod common;
+
+use common::{fake_s3_client, MockS3Client};
+use mystiko_static_storage::conformance::check_storage;
+use mystiko_static_storage::{CachedStorage, CachedStorageOptions, FileStorage, MemoryStorage, S3Storage};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_file_storage() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = FileStorage::new(dir.path()).await.unwrap();
+    check_storage(&storage).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_file_storage_relative_base() {
+    let dir = tempfile::tempdir_in(".").unwrap();
+    let base = dir
+        .path()
+        .strip_prefix(std::env::current_dir().unwrap())
+        .unwrap_or(dir.path());
+    assert!(base.is_relative());
+    let storage = FileStorage::new(base).await.unwrap();
+    check_storage(&storage).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_memory_storage() {
+    check_storage(&MemoryStorage::new()).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_s3_storage() {
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(fake_s3_client())
+        .build();
+    check_storage(&storage).await.unwrap();
+    let storage = S3Storage::<MockS3Client>::builder()
+        .s3_bucket("test-bucket")
+        .client(fake_s3_client())
+        .build();
+    check_storage(&storage).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_cached_storage() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let storage = CachedStorage::new(
+        CachedStorageOptions::<PathBuf, MemoryStorage>::builder()
+            .raw(Arc::new(MemoryStorage::new()))
+            .cache_folder(PathBuf::from(cache_dir.path()))
+            .build(),
+    )
+    .await
+    .unwrap();
+    check_storage(&storage).await.unwrap();
+}
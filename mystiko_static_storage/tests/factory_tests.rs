@@ -0,0 +1,74 @@
+use mystiko_static_storage::{
+    build_storage, AzureBlobStorageConfig, FileStorageConfig, GcsStorageConfig, PutRequest, StorageCacheConfig,
+    StorageConfig, StorageType,
+};
+
+#[tokio::test]
+async fn test_build_file_storage() {
+    let storage_dir = tempfile::tempdir().unwrap();
+    let config = StorageConfig::builder()
+        .storage_type(StorageType::File)
+        .file(
+            FileStorageConfig::builder()
+                .path(storage_dir.path().to_string_lossy().to_string())
+                .build(),
+        )
+        .build();
+    let storage = build_storage(&config).await.unwrap();
+    storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello world").build())
+        .await
+        .unwrap();
+    assert!(storage_dir.path().join("a/test.txt").exists());
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello world");
+}
+
+#[tokio::test]
+async fn test_build_cached_storage() {
+    let storage_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let config: StorageConfig = serde_json::from_value(serde_json::json!({
+        "type": "file",
+        "file": { "path": storage_dir.path() },
+        "cache": { "enabled": true, "path": cache_dir.path() },
+    }))
+    .unwrap();
+    let storage = build_storage(&config).await.unwrap();
+    storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello world").build())
+        .await
+        .unwrap();
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello world");
+    assert!(storage_dir.path().join("a/test.txt").exists());
+    assert!(cache_dir.path().join("a/test.txt").exists());
+}
+
+#[tokio::test]
+async fn test_build_remote_storage() {
+    let config = StorageConfig::builder().storage_type(StorageType::S3).build();
+    assert!(build_storage(&config).await.is_ok());
+    let config = StorageConfig::builder().storage_type(StorageType::Gcs).build();
+    assert!(build_storage(&config).await.is_err());
+    let config = StorageConfig::builder()
+        .storage_type(StorageType::Gcs)
+        .gcs(GcsStorageConfig::builder().bucket("mystiko").build())
+        .build();
+    assert!(build_storage(&config).await.is_ok());
+    let config = StorageConfig::builder().storage_type(StorageType::AzureBlob).build();
+    assert!(build_storage(&config).await.is_err());
+    let config = StorageConfig::builder()
+        .storage_type(StorageType::AzureBlob)
+        .azure_blob(AzureBlobStorageConfig::builder().account("mystiko").build())
+        .build();
+    assert!(build_storage(&config).await.is_ok());
+}
+
+#[test]
+fn test_default_storage_config() {
+    let config: StorageConfig = serde_json::from_str("{}").unwrap();
+    assert_eq!(config, StorageConfig::default());
+    assert_eq!(config.storage_type, StorageType::S3);
+    assert_eq!(config.cache, StorageCacheConfig::default());
+    assert!(config.gcs.is_none());
+    assert!(config.azure_blob.is_none());
+}
@@ -1,11 +1,16 @@
-use mystiko_static_storage::{FileStorage, ListFilesRequest, PutRequest, RemoveFolderRequest, Storage};
+use futures::TryStreamExt;
+use mystiko_static_storage::{
+    CopyRequest, FileStorage, GetRequest, ListFilesRequest, PutRequest, PutStreamRequest, RemoveFolderRequest,
+    RenameRequest, Storage, StorageError, StorageReader,
+};
+use std::io::Cursor;
 use std::path::PathBuf;
 use tokio::fs;
 
 #[tokio::test]
 async fn test_list_folders() {
     let (_, storage) = setup().await;
-    assert!(storage.list_folders("a".into()).await.is_err());
+    assert!(storage.list_folders("a".into()).await.unwrap().folders.is_empty());
     storage.put("a/test.txt".into()).await.unwrap();
     let folders = storage
         .list_folders(PathBuf::default().into())
@@ -33,7 +38,7 @@ async fn test_list_folders() {
 #[tokio::test]
 async fn test_list_files() {
     let (_, storage) = setup().await;
-    assert!(storage.list_files("a".into()).await.is_err());
+    assert!(storage.list_files("a".into()).await.unwrap().files.is_empty());
     storage.put("a/test.txt".into()).await.unwrap();
     storage.put("a/b/test.txt".into()).await.unwrap();
     storage.put("a/b/c/test.txt".into()).await.unwrap();
@@ -58,6 +63,106 @@ async fn test_list_files() {
     assert_eq!(files, vec!["a/test.txt"]);
 }
 
+#[tokio::test]
+async fn test_list_files_stream() {
+    let (_, storage) = setup().await;
+    assert!(storage
+        .list_files_stream("a".into())
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+        .is_empty());
+    storage.put("a/test.txt".into()).await.unwrap();
+    storage.put("a/b/test.txt".into()).await.unwrap();
+    let mut files = storage
+        .list_files_stream(ListFilesRequest::builder().path("a").page_size(1).build())
+        .map_ok(|object| object.path.to_string_lossy().to_string())
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    files.sort();
+    assert_eq!(files, vec!["a/b/test.txt", "a/test.txt"]);
+}
+
+#[tokio::test]
+async fn test_list_files_with_metadata() {
+    let (_, storage) = setup().await;
+    storage
+        .put(PutRequest::builder().path("a/test.txt").data(vec![1u8, 2u8]).build())
+        .await
+        .unwrap();
+    storage
+        .put(PutRequest::builder().path("a/b/test.txt").data(vec![1u8]).build())
+        .await
+        .unwrap();
+    let response = storage
+        .list_files(ListFilesRequest::builder().path("a").with_metadata(true).build())
+        .await
+        .unwrap();
+    let mut objects = response
+        .objects
+        .into_iter()
+        .map(|object| (object.path.to_string_lossy().to_string(), object.size.unwrap()))
+        .collect::<Vec<_>>();
+    objects.sort();
+    assert_eq!(
+        objects,
+        vec![("a/b/test.txt".to_string(), 1), ("a/test.txt".to_string(), 2)]
+    );
+    assert!(storage.list_files("a".into()).await.unwrap().objects.is_empty());
+}
+
+#[tokio::test]
+async fn test_stat() {
+    let (_, storage) = setup().await;
+    assert!(storage.stat("a/test.txt".into()).await.is_err());
+    storage
+        .put(
+            PutRequest::builder()
+                .path("a/test.txt")
+                .data(vec![1u8, 2u8, 3u8])
+                .build(),
+        )
+        .await
+        .unwrap();
+    let meta = storage.stat("a/test.txt".into()).await.unwrap().meta;
+    assert_eq!(meta.path, PathBuf::from("a/test.txt"));
+    assert_eq!(meta.size, Some(3));
+    assert!(meta.last_modified.is_some());
+    assert!(meta.etag.is_some());
+    assert!(storage.stat("a".into()).await.is_err());
+    storage
+        .put(
+            PutRequest::builder()
+                .path("a/test.txt")
+                .data(vec![1u8])
+                .overwrite(true)
+                .build(),
+        )
+        .await
+        .unwrap();
+    let updated = storage.stat("a/test.txt".into()).await.unwrap().meta;
+    assert_eq!(updated.size, Some(1));
+    assert_ne!(updated.etag, meta.etag);
+}
+
+#[tokio::test]
+async fn test_stat_out_of_band() {
+    let (temp_dir, storage) = setup().await;
+    fs::create_dir_all(temp_dir.path().join("a")).await.unwrap();
+    fs::write(temp_dir.path().join("a/test.txt"), "hello").await.unwrap();
+    let etag = storage.stat("a/test.txt".into()).await.unwrap().meta.etag;
+    assert!(etag.is_some());
+    let request = ListFilesRequest::builder().path("a").with_metadata(true).build();
+    let objects = storage.list_files(request).await.unwrap().objects;
+    assert_eq!(objects[0].etag, etag);
+    assert!(!fs::try_exists(temp_dir.path().join("a/.test.txt.etag")).await.unwrap());
+    fs::write(temp_dir.path().join("a/test.txt"), "hello mystiko")
+        .await
+        .unwrap();
+    assert_ne!(storage.stat("a/test.txt".into()).await.unwrap().meta.etag, etag);
+}
+
 #[tokio::test]
 async fn test_exists() {
     let (_, storage) = setup().await;
@@ -72,6 +177,75 @@ async fn test_get() {
     assert!(storage.get("a/test.txt".into()).await.is_err());
 }
 
+#[tokio::test]
+async fn test_get_range() {
+    let (_, storage) = setup().await;
+    storage
+        .put(PutRequest::builder().path("test.txt").data("hello world").build())
+        .await
+        .unwrap();
+    let get = |offset: Option<u64>, length: Option<u64>| {
+        let request = GetRequest {
+            path: PathBuf::from("test.txt"),
+            no_cache: false,
+            offset,
+            length,
+        };
+        storage.get(request)
+    };
+    assert_eq!(get(Some(6), None).await.unwrap().data, b"world");
+    assert_eq!(get(None, Some(5)).await.unwrap().data, b"hello");
+    assert_eq!(get(Some(4), Some(3)).await.unwrap().data, b"o w");
+    assert_eq!(get(Some(6), Some(100)).await.unwrap().data, b"world");
+    assert!(get(Some(100), None).await.unwrap().data.is_empty());
+    let response = storage
+        .get_stream(GetRequest::builder().path("test.txt").offset(6u64).length(3u64).build())
+        .await
+        .unwrap();
+    assert_eq!(response.length, Some(3));
+    assert_eq!(response.read_to_end().await.unwrap(), b"wor");
+}
+
+#[tokio::test]
+async fn test_get_stream() {
+    let (_, storage) = setup().await;
+    assert!(storage.get_stream("test.txt".into()).await.is_err());
+    storage
+        .put(PutRequest::builder().path("test.txt").data("hello world").build())
+        .await
+        .unwrap();
+    let response = storage.get_stream("test.txt".into()).await.unwrap();
+    assert_eq!(response.length, Some(11));
+    assert_eq!(response.read_to_end().await.unwrap(), b"hello world");
+}
+
+#[tokio::test]
+async fn test_put_stream() {
+    let (_, storage) = setup().await;
+    storage
+        .put_stream(stream_request("a/test.txt", "hello world", false))
+        .await
+        .unwrap();
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello world");
+    storage
+        .put_stream(stream_request("a/test.txt", "hello mystiko", false))
+        .await
+        .unwrap();
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello world");
+    storage
+        .put_stream(stream_request("a/test.txt", "hello mystiko", true))
+        .await
+        .unwrap();
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello mystiko");
+    let truncated = PutStreamRequest::builder()
+        .path("a/truncated.txt")
+        .reader(Box::pin(Cursor::new(b"hello".to_vec())) as StorageReader)
+        .length(10u64)
+        .build();
+    assert!(storage.put_stream(truncated).await.is_err());
+    assert!(!storage.exists("a/truncated.txt".into()).await.unwrap().exists);
+}
+
 #[tokio::test]
 async fn test_put() {
     let (_, storage) = setup().await;
@@ -105,6 +279,158 @@ async fn test_put_overwrite() {
     );
 }
 
+#[tokio::test]
+async fn test_put_conditional() {
+    let (_, storage) = setup().await;
+    let create_request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello world!")
+        .if_none_match("*")
+        .build();
+    let etag = storage.put(create_request.clone()).await.unwrap().etag.unwrap();
+    assert_eq!(
+        storage.stat("a/test.txt".into()).await.unwrap().meta.etag,
+        Some(etag.clone())
+    );
+    let error = storage.put(create_request).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::PreconditionFailed(_))
+    ));
+    let stale_request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello")
+        .if_match("stale-etag")
+        .build();
+    assert!(storage.put(stale_request).await.is_err());
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello world!");
+    let update_request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello mystiko")
+        .if_match(etag.clone())
+        .build();
+    let new_etag = storage.put(update_request).await.unwrap().etag.unwrap();
+    assert_ne!(new_etag, etag);
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello mystiko");
+    let files = storage.list_files("a".into()).await.unwrap().files;
+    assert_eq!(files, vec![PathBuf::from("a/test.txt")]);
+}
+
+#[tokio::test]
+async fn test_put_conditional_same_length() {
+    let (_, storage) = setup().await;
+    let etag = storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello").build())
+        .await
+        .unwrap()
+        .etag
+        .unwrap();
+    let update_request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("world")
+        .if_match(etag.clone())
+        .build();
+    let new_etag = storage.put(update_request).await.unwrap().etag.unwrap();
+    assert_ne!(new_etag, etag);
+    let stale_request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("again")
+        .if_match(etag)
+        .build();
+    let error = storage.put(stale_request).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::PreconditionFailed(_))
+    ));
+    let request = ListFilesRequest::builder().path("a").with_metadata(true).build();
+    let objects = storage.list_files(request).await.unwrap().objects;
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].etag, Some(new_etag));
+}
+
+#[tokio::test]
+async fn test_put_conditional_concurrent() {
+    let (_, storage) = setup().await;
+    let etag = storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello").build())
+        .await
+        .unwrap()
+        .etag
+        .unwrap();
+    let requests = (0..8).map(|index| {
+        let request = PutRequest::builder()
+            .path("a/test.txt")
+            .data(format!("hello {}", index))
+            .if_match(etag.clone())
+            .build();
+        storage.put(request)
+    });
+    let results = futures::future::join_all(requests).await;
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+    let files = storage.list_files("a".into()).await.unwrap().files;
+    assert_eq!(files, vec![PathBuf::from("a/test.txt")]);
+}
+
+#[tokio::test]
+async fn test_copy() {
+    let (_, storage) = setup().await;
+    let etag = storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello world!").build())
+        .await
+        .unwrap()
+        .etag;
+    let response = storage.copy(("a/test.txt", "b/test.txt").into()).await.unwrap();
+    assert!(response.etag.is_some());
+    assert_eq!(storage.get("b/test.txt".into()).await.unwrap().data, b"hello world!");
+    assert_eq!(storage.stat("a/test.txt".into()).await.unwrap().meta.etag, etag);
+    storage
+        .put(PutRequest::builder().path("c/test.txt").data("hello mystiko").build())
+        .await
+        .unwrap();
+    let error = storage.copy(("c/test.txt", "b/test.txt").into()).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::AlreadyExists(_))
+    ));
+    let copy_request = CopyRequest::builder()
+        .from("c/test.txt")
+        .to("b/test.txt")
+        .overwrite(true)
+        .build();
+    storage.copy(copy_request).await.unwrap();
+    assert_eq!(storage.get("b/test.txt".into()).await.unwrap().data, b"hello mystiko");
+    assert!(storage.copy(("a", "d").into()).await.is_err());
+    assert!(storage.copy(("a/missing.txt", "d/test.txt").into()).await.is_err());
+    let files = storage.list_files("b".into()).await.unwrap().files;
+    assert_eq!(files, vec![PathBuf::from("b/test.txt")]);
+}
+
+#[tokio::test]
+async fn test_rename() {
+    let (_, storage) = setup().await;
+    storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello world!").build())
+        .await
+        .unwrap();
+    storage.rename(("a/test.txt", "b/test.txt").into()).await.unwrap();
+    assert!(!storage.exists("a/test.txt".into()).await.unwrap().exists);
+    assert_eq!(storage.get("b/test.txt".into()).await.unwrap().data, b"hello world!");
+    storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello mystiko").build())
+        .await
+        .unwrap();
+    assert!(storage.rename(("a/test.txt", "b/test.txt").into()).await.is_err());
+    assert!(storage.exists("a/test.txt".into()).await.unwrap().exists);
+    let rename_request = RenameRequest::builder()
+        .from("a/test.txt")
+        .to("b/test.txt")
+        .overwrite(true)
+        .build();
+    storage.rename(rename_request).await.unwrap();
+    assert!(!storage.exists("a/test.txt".into()).await.unwrap().exists);
+    assert_eq!(storage.get("b/test.txt".into()).await.unwrap().data, b"hello mystiko");
+}
+
 #[tokio::test]
 async fn test_remove_files() {
     let (_, storage) = setup().await;
@@ -117,6 +443,9 @@ async fn test_remove_files() {
     assert!(!storage.exists("a/test.txt".into()).await.unwrap().exists);
     assert!(!storage.exists("a/b/test.txt".into()).await.unwrap().exists);
     assert!(!storage.exists("a/b/c/test.txt".into()).await.unwrap().exists);
+    assert!(!storage.exists("a".into()).await.unwrap().exists);
+    storage.remove_file("a/missing.txt".into()).await.unwrap();
+    storage.remove_folder("a/missing".into()).await.unwrap();
 }
 
 #[tokio::test]
@@ -149,6 +478,15 @@ async fn test_construct_with_non_existing_path() {
     fs::remove_dir_all(path).await.unwrap();
 }
 
+fn stream_request(path: &str, data: &str, overwrite: bool) -> PutStreamRequest {
+    PutStreamRequest::builder()
+        .path(path)
+        .reader(Box::pin(Cursor::new(data.as_bytes().to_vec())) as StorageReader)
+        .length(data.len() as u64)
+        .overwrite(overwrite)
+        .build()
+}
+
 async fn setup() -> (tempfile::TempDir, FileStorage) {
     let temp_dir = tempfile::tempdir().unwrap();
     let base = PathBuf::from(temp_dir.path());
@@ -0,0 +1,497 @@
+use mockito::{Matcher, Server, ServerGuard};
+use mystiko_static_storage::conformance::check_storage;
+use mystiko_static_storage::{
+    CopyRequest, GcsCredentialsConfig, GcsStorage, GcsStorageConfig, GetRequest, ListFilesRequest,
+    MetadataTokenProvider, PutRequest, PutStreamRequest, Storage, StorageError, StorageReader,
+};
+use serde_json::json;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_list_folders() {
+    let mut server = Server::new_async().await;
+    let first_page = server
+        .mock("GET", "/storage/v1/b/test-bucket/o")
+        .match_query(Matcher::Exact("prefix=base%2Fa%2F&delimiter=%2F".into()))
+        .match_header("authorization", "Bearer test-token")
+        .with_body(json!({ "prefixes": ["base/a/b/"], "nextPageToken": "page-2" }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let second_page = server
+        .mock("GET", "/storage/v1/b/test-bucket/o")
+        .match_query(Matcher::UrlEncoded("pageToken".into(), "page-2".into()))
+        .with_body(json!({ "prefixes": ["base/a/c/"] }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let folders = storage.list_folders("a".into()).await.unwrap().folders;
+    assert_eq!(folders, vec![PathBuf::from("a/b"), PathBuf::from("a/c")]);
+    first_page.assert_async().await;
+    second_page.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_list_files() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/storage/v1/b/test-bucket/o")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("prefix".into(), "base/a/".into()),
+            Matcher::UrlEncoded("maxResults".into(), "10".into()),
+        ]))
+        .with_body(
+            json!({
+                "items": [
+                    {
+                        "name": "base/a/b/test.txt",
+                        "size": "11",
+                        "updated": "2024-01-02T03:04:05.000Z",
+                        "generation": "1700000000000001",
+                        "contentType": "text/plain"
+                    },
+                    { "name": "base/a/test.txt", "size": "2", "generation": "1700000000000002" }
+                ]
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let request = ListFilesRequest::builder()
+        .path("a")
+        .page_size(10)
+        .with_metadata(true)
+        .build();
+    let response = storage.list_files(request).await.unwrap();
+    assert_eq!(
+        response.files,
+        vec![PathBuf::from("a/b/test.txt"), PathBuf::from("a/test.txt")]
+    );
+    assert_eq!(response.objects[0].size, Some(11));
+    assert_eq!(response.objects[0].etag, Some("1700000000000001".to_string()));
+    assert_eq!(response.objects[0].content_type, Some("text/plain".to_string()));
+    assert!(response.objects[0].last_modified.is_some());
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_exists_and_stat() {
+    let mut server = Server::new_async().await;
+    server
+        .mock("GET", "/storage/v1/b/test-bucket/o/base%2Fa%2Ftest.txt")
+        .with_body(json!({ "name": "base/a/test.txt", "size": "2", "generation": "1" }).to_string())
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/storage/v1/b/test-bucket/o/base%2Fa%2Fmissing.txt")
+        .with_status(404)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    assert!(storage.exists("a/test.txt".into()).await.unwrap().exists);
+    assert!(!storage.exists("a/missing.txt".into()).await.unwrap().exists);
+    let meta = storage.stat("a/test.txt".into()).await.unwrap().meta;
+    assert_eq!(meta.path, PathBuf::from("a/test.txt"));
+    assert_eq!(meta.size, Some(2));
+    assert_eq!(meta.etag, Some("1".to_string()));
+    assert!(storage.stat("a/missing.txt".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_get() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/storage/v1/b/test-bucket/o/base%2Fa%2Ftest.txt")
+        .match_query(Matcher::UrlEncoded("alt".into(), "media".into()))
+        .match_header("range", "bytes=6-10")
+        .with_status(206)
+        .with_body("world")
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/storage/v1/b/test-bucket/o/base%2Fa%2Fmissing.txt")
+        .match_query(Matcher::Any)
+        .with_status(404)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let request = GetRequest::builder()
+        .path("a/test.txt")
+        .offset(6u64)
+        .length(5u64)
+        .build();
+    assert_eq!(storage.get(request).await.unwrap().data, b"world");
+    assert!(storage.get("a/missing.txt".into()).await.is_err());
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload/storage/v1/b/test-bucket/o")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("uploadType".into(), "media".into()),
+            Matcher::UrlEncoded("name".into(), "base/a/test.txt".into()),
+            Matcher::UrlEncoded("predefinedAcl".into(), "publicRead".into()),
+        ]))
+        .match_header("content-type", "text/plain")
+        .match_body("hello world")
+        .with_body(json!({ "name": "base/a/test.txt", "generation": "2" }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello world")
+        .content_type("text/plain")
+        .acl("publicRead")
+        .overwrite(true)
+        .build();
+    let response = storage.put(request).await.unwrap();
+    assert_eq!(response.etag, Some("2".to_string()));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put_conditional() {
+    let mut server = Server::new_async().await;
+    let create_mock = server
+        .mock("POST", "/upload/storage/v1/b/test-bucket/o")
+        .match_query(Matcher::UrlEncoded("ifGenerationMatch".into(), "0".into()))
+        .with_status(412)
+        .expect(2)
+        .create_async()
+        .await;
+    let update_mock = server
+        .mock("POST", "/upload/storage/v1/b/test-bucket/o")
+        .match_query(Matcher::UrlEncoded("ifGenerationMatch".into(), "1".into()))
+        .with_body(json!({ "name": "base/a/test.txt", "generation": "2" }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/storage/v1/b/test-bucket/o/base%2Fa%2Ftest.txt")
+        .with_body(json!({ "name": "base/a/test.txt", "generation": "1" }).to_string())
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello")
+        .if_none_match("*")
+        .build();
+    let error = storage.put(request).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::PreconditionFailed(_))
+    ));
+    let response = storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello").build())
+        .await
+        .unwrap();
+    assert_eq!(response.etag, Some("1".to_string()));
+    let request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello")
+        .if_match("1")
+        .build();
+    assert_eq!(storage.put(request).await.unwrap().etag, Some("2".to_string()));
+    create_mock.assert_async().await;
+    update_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put_resumable() {
+    let chunk_size = 256 * 1024;
+    let data = (0..chunk_size + 1000)
+        .map(|index| (index % 251) as u8)
+        .collect::<Vec<_>>();
+    let length = data.len();
+    let mut server = Server::new_async().await;
+    let session = format!("{}/upload/session", server.url());
+    let init_mock = server
+        .mock("POST", "/upload/storage/v1/b/test-bucket/o")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("uploadType".into(), "resumable".into()),
+            Matcher::UrlEncoded("name".into(), "base/a/test.bin".into()),
+            Matcher::UrlEncoded("ifGenerationMatch".into(), "0".into()),
+        ]))
+        .match_header("x-upload-content-type", "application/octet-stream")
+        .match_header("x-upload-content-length", length.to_string().as_str())
+        .with_header("location", &session)
+        .expect(1)
+        .create_async()
+        .await;
+    let first_mock = server
+        .mock("PUT", "/upload/session")
+        .match_header(
+            "content-range",
+            format!("bytes 0-{}/{}", chunk_size - 1, length).as_str(),
+        )
+        .match_body(data[..chunk_size].to_vec())
+        .with_status(308)
+        .with_header("range", format!("bytes=0-{}", chunk_size - 1).as_str())
+        .expect(1)
+        .create_async()
+        .await;
+    let failed_mock = server
+        .mock("PUT", "/upload/session")
+        .match_header(
+            "content-range",
+            format!("bytes {}-{}/{}", chunk_size, length - 1, length).as_str(),
+        )
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    let status_mock = server
+        .mock("PUT", "/upload/session")
+        .match_header("content-range", format!("bytes */{}", length).as_str())
+        .with_status(308)
+        .with_header("range", format!("bytes=0-{}", chunk_size + 499).as_str())
+        .expect(1)
+        .create_async()
+        .await;
+    let resumed_mock = server
+        .mock("PUT", "/upload/session")
+        .match_header(
+            "content-range",
+            format!("bytes {}-{}/{}", chunk_size + 500, length - 1, length).as_str(),
+        )
+        .match_body(data[chunk_size + 500..].to_vec())
+        .with_body(json!({ "name": "base/a/test.bin", "generation": "5" }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = GcsStorage::builder()
+        .endpoint(server.url())
+        .bucket("test-bucket")
+        .base("base")
+        .chunk_size(chunk_size as u64)
+        .build();
+    let request = PutStreamRequest::builder()
+        .path("a/test.bin")
+        .reader(Box::pin(Cursor::new(data.clone())) as StorageReader)
+        .length(length as u64)
+        .build();
+    assert_eq!(storage.put_stream(request).await.unwrap().etag, Some("5".to_string()));
+    init_mock.assert_async().await;
+    first_mock.assert_async().await;
+    failed_mock.assert_async().await;
+    status_mock.assert_async().await;
+    resumed_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put_resumable_no_progress() {
+    let data = vec![7u8; 1000];
+    let mut server = Server::new_async().await;
+    let session = format!("{}/upload/session", server.url());
+    let init_mock = server
+        .mock("POST", "/upload/storage/v1/b/test-bucket/o")
+        .match_query(Matcher::Any)
+        .with_header("location", &session)
+        .expect(1)
+        .create_async()
+        .await;
+    let chunk_mock = server
+        .mock("PUT", "/upload/session")
+        .match_header("content-range", "bytes 0-999/1000")
+        .with_status(308)
+        .expect(4)
+        .create_async()
+        .await;
+    let storage = GcsStorage::builder()
+        .endpoint(server.url())
+        .bucket("test-bucket")
+        .base("base")
+        .chunk_size(500u64)
+        .build();
+    let request = PutStreamRequest::builder()
+        .path("a/test.bin")
+        .reader(Box::pin(Cursor::new(data)) as StorageReader)
+        .length(1000u64)
+        .build();
+    assert!(storage.put_stream(request).await.is_err());
+    init_mock.assert_async().await;
+    chunk_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_metadata_token() {
+    let mut server = Server::new_async().await;
+    let token_mock = server
+        .mock("GET", "/token")
+        .match_header("metadata-flavor", "Google")
+        .with_body(json!({ "access_token": "first-token", "expires_in": 30 }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let refreshed_mock = server
+        .mock("GET", "/token")
+        .with_body(json!({ "access_token": "second-token", "expires_in": 3600 }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let first_mock = server
+        .mock("GET", "/storage/v1/b/test-bucket/o/base%2Fa%2Ftest.txt")
+        .match_header("authorization", "Bearer first-token")
+        .with_body(json!({ "name": "base/a/test.txt", "generation": "1" }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let second_mock = server
+        .mock("GET", "/storage/v1/b/test-bucket/o/base%2Fa%2Ftest.txt")
+        .match_header("authorization", "Bearer second-token")
+        .with_body(json!({ "name": "base/a/test.txt", "generation": "1" }).to_string())
+        .expect(2)
+        .create_async()
+        .await;
+    let token_provider = MetadataTokenProvider::builder()
+        .url(format!("{}/token", server.url()))
+        .headers(vec![("Metadata-Flavor".to_string(), "Google".to_string())])
+        .build();
+    let storage = GcsStorage::builder()
+        .endpoint(server.url())
+        .bucket("test-bucket")
+        .base("base")
+        .token_provider(Arc::new(token_provider))
+        .build();
+    for _ in 0..3 {
+        assert!(storage.exists("a/test.txt".into()).await.unwrap().exists);
+    }
+    token_mock.assert_async().await;
+    refreshed_mock.assert_async().await;
+    first_mock.assert_async().await;
+    second_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_copy() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock(
+            "POST",
+            "/storage/v1/b/test-bucket/o/base%2Fa%2Ftest.txt/copyTo/b/test-bucket/o/base%2Fb%2Ftest.txt",
+        )
+        .match_query(Matcher::UrlEncoded("ifGenerationMatch".into(), "0".into()))
+        .with_status(412)
+        .expect(1)
+        .create_async()
+        .await;
+    let overwrite_mock = server
+        .mock(
+            "POST",
+            "/storage/v1/b/test-bucket/o/base%2Fa%2Ftest.txt/copyTo/b/test-bucket/o/base%2Fb%2Ftest.txt",
+        )
+        .match_query(Matcher::Missing)
+        .with_body(json!({ "name": "base/b/test.txt", "generation": "3" }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    let error = storage.copy(("a/test.txt", "b/test.txt").into()).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::AlreadyExists(_))
+    ));
+    let request = CopyRequest::builder()
+        .from("a/test.txt")
+        .to("b/test.txt")
+        .overwrite(true)
+        .build();
+    assert_eq!(storage.copy(request).await.unwrap().etag, Some("3".to_string()));
+    mock.assert_async().await;
+    overwrite_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_remove_files() {
+    let mut server = Server::new_async().await;
+    let remove_mock = server
+        .mock("DELETE", "/storage/v1/b/test-bucket/o/base%2Fa%2Ftest.txt")
+        .with_status(204)
+        .expect(1)
+        .create_async()
+        .await;
+    let missing_mock = server
+        .mock("DELETE", "/storage/v1/b/test-bucket/o/base%2Fa%2Fmissing.txt")
+        .with_status(404)
+        .expect(1)
+        .create_async()
+        .await;
+    let storage = storage(&server);
+    storage
+        .remove_files(vec![PathBuf::from("a/test.txt"), PathBuf::from("a/missing.txt")].into())
+        .await
+        .unwrap();
+    remove_mock.assert_async().await;
+    missing_mock.assert_async().await;
+}
+
+#[test]
+fn test_from_config() {
+    let config = GcsStorageConfig::builder()
+        .bucket("test-bucket")
+        .base("base")
+        .endpoint("http://localhost:4443")
+        .build();
+    let storage = GcsStorage::from_config(&config).unwrap();
+    assert_eq!(storage.bucket, "test-bucket");
+    assert_eq!(storage.base, PathBuf::from("base"));
+    assert_eq!(storage.endpoint, "http://localhost:4443");
+    assert!(storage.token.is_none());
+    assert!(storage.token_provider.is_none());
+    let config = GcsStorageConfig::builder()
+        .bucket("test-bucket")
+        .credentials(GcsCredentialsConfig::Static {
+            token: "gcs-secret".to_string(),
+        })
+        .build();
+    let storage = GcsStorage::from_config(&config).unwrap();
+    assert_eq!(storage.token, Some("gcs-secret".to_string()));
+    assert!(!format!("{:?}", storage).contains("gcs-secret"));
+    let config = GcsStorageConfig::builder()
+        .bucket("test-bucket")
+        .credentials(GcsCredentialsConfig::Metadata { endpoint: None })
+        .build();
+    let storage = GcsStorage::from_config(&config).unwrap();
+    assert!(storage.token.is_none());
+    assert_eq!(
+        storage.token_provider.unwrap().url,
+        "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token"
+    );
+}
+
+// fake-gcs-server -scheme http -port 4443 with the bucket created up front, e.g.
+// MYSTIKO_GCS_EMULATOR_ENDPOINT=http://localhost:4443 MYSTIKO_GCS_EMULATOR_BUCKET=test-bucket
+#[tokio::test]
+#[ignore]
+async fn test_conformance_emulator() {
+    let config = GcsStorageConfig::builder()
+        .bucket(std::env::var("MYSTIKO_GCS_EMULATOR_BUCKET").unwrap_or_else(|_| String::from("test-bucket")))
+        .base("mystiko")
+        .endpoint(
+            std::env::var("MYSTIKO_GCS_EMULATOR_ENDPOINT").unwrap_or_else(|_| String::from("http://localhost:4443")),
+        )
+        .build();
+    let storage = GcsStorage::from_config(&config).unwrap();
+    check_storage(&storage).await.unwrap();
+}
+
+fn storage(server: &ServerGuard) -> GcsStorage {
+    GcsStorage::builder()
+        .endpoint(server.url())
+        .bucket("test-bucket")
+        .base("base")
+        .token("test-token")
+        .build()
+}
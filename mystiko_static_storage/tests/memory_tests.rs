@@ -0,0 +1,237 @@
+use futures::TryStreamExt;
+use mystiko_static_storage::{
+    CopyRequest, GetRequest, ListFilesRequest, MemoryStorage, PutRequest, PutStreamRequest, RemoveFolderRequest,
+    Storage, StorageError, StorageReader,
+};
+use std::io::Cursor;
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn test_list_folders() {
+    let storage = MemoryStorage::new();
+    assert!(storage.list_folders("a".into()).await.unwrap().folders.is_empty());
+    storage.put("a/test.txt".into()).await.unwrap();
+    let folders = storage
+        .list_folders(PathBuf::default().into())
+        .await
+        .unwrap()
+        .folders
+        .into_iter()
+        .map(|f| f.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(folders, vec!["a"]);
+    assert!(storage.list_folders("a".into()).await.unwrap().folders.is_empty());
+    storage.put("a/b/test.txt".into()).await.unwrap();
+    storage.put("a/b/c/test.txt".into()).await.unwrap();
+    storage.put("ab/test.txt".into()).await.unwrap();
+    let folders = storage
+        .list_folders("a".into())
+        .await
+        .unwrap()
+        .folders
+        .into_iter()
+        .map(|f| f.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(folders, vec!["a/b"]);
+}
+
+#[tokio::test]
+async fn test_list_files() {
+    let storage = MemoryStorage::new();
+    assert!(storage.list_files("a".into()).await.unwrap().files.is_empty());
+    storage.put("a/test.txt".into()).await.unwrap();
+    storage.put("a/b/test.txt".into()).await.unwrap();
+    storage.put("a/b/c/test.txt".into()).await.unwrap();
+    storage.put("ab/test.txt".into()).await.unwrap();
+    let files = storage
+        .list_files("a".into())
+        .await
+        .unwrap()
+        .files
+        .into_iter()
+        .map(|f| f.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(files, vec!["a/b/c/test.txt", "a/b/test.txt", "a/test.txt"]);
+    let files = storage
+        .list_files(ListFilesRequest::builder().path("a").non_recursively(true).build())
+        .await
+        .unwrap()
+        .files
+        .into_iter()
+        .map(|f| f.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(files, vec!["a/test.txt"]);
+    let files = storage
+        .list_files_stream(ListFilesRequest::builder().path("a/b").with_metadata(true).build())
+        .map_ok(|object| (object.path.to_string_lossy().to_string(), object.size.unwrap()))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(
+        files,
+        vec![("a/b/c/test.txt".to_string(), 0), ("a/b/test.txt".to_string(), 0)]
+    );
+}
+
+#[tokio::test]
+async fn test_exists_and_stat() {
+    let storage = MemoryStorage::new();
+    storage
+        .put(
+            PutRequest::builder()
+                .path("a/test.txt")
+                .data(vec![1u8, 2u8])
+                .content_type("text/plain")
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert!(storage.exists("a/test.txt".into()).await.unwrap().exists);
+    assert!(storage.exists("a".into()).await.unwrap().exists);
+    assert!(!storage.exists("a/b".into()).await.unwrap().exists);
+    let meta = storage.stat("a/test.txt".into()).await.unwrap().meta;
+    assert_eq!(meta.path, PathBuf::from("a/test.txt"));
+    assert_eq!(meta.size, Some(2));
+    assert_eq!(meta.content_type, Some("text/plain".to_string()));
+    assert!(meta.etag.is_some());
+    assert!(meta.last_modified.is_some());
+    assert!(storage.stat("a".into()).await.is_err());
+    assert!(storage.stat("a/b.txt".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_get_and_put() {
+    let storage = MemoryStorage::new();
+    assert!(storage.get("a/test.txt".into()).await.is_err());
+    storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello world!").build())
+        .await
+        .unwrap();
+    storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello mystiko").build())
+        .await
+        .unwrap();
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello world!");
+    let response = storage
+        .get(
+            GetRequest::builder()
+                .path("a/test.txt")
+                .offset(6u64)
+                .length(5u64)
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.data, b"world");
+    let response = storage
+        .get(GetRequest::builder().path("a/test.txt").offset(20u64).build())
+        .await
+        .unwrap();
+    assert!(response.data.is_empty());
+    storage
+        .put(
+            PutRequest::builder()
+                .path("a/test.txt")
+                .data("hello mystiko")
+                .overwrite(true)
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello mystiko");
+    assert!(storage.put("a".into()).await.is_err());
+    assert!(storage.put("a/test.txt/b.txt".into()).await.is_err());
+    let cloned = storage.clone();
+    assert_eq!(cloned.get("a/test.txt".into()).await.unwrap().data, b"hello mystiko");
+}
+
+#[tokio::test]
+async fn test_put_conditional() {
+    let storage = MemoryStorage::new();
+    let create_request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello world!")
+        .if_none_match("*")
+        .build();
+    let etag = storage.put(create_request.clone()).await.unwrap().etag.unwrap();
+    let error = storage.put(create_request).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::PreconditionFailed(_))
+    ));
+    let stale_request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello")
+        .if_match("stale-etag")
+        .build();
+    assert!(storage.put(stale_request).await.is_err());
+    let update_request = PutRequest::builder()
+        .path("a/test.txt")
+        .data("hello mystiko")
+        .if_match(etag.clone())
+        .build();
+    let new_etag = storage.put(update_request).await.unwrap().etag.unwrap();
+    assert_ne!(new_etag, etag);
+    assert_eq!(storage.get("a/test.txt".into()).await.unwrap().data, b"hello mystiko");
+}
+
+#[tokio::test]
+async fn test_stream() {
+    let storage = MemoryStorage::new();
+    let request = PutStreamRequest::builder()
+        .path("a/test.txt")
+        .reader(Box::pin(Cursor::new(b"hello world".to_vec())) as StorageReader)
+        .length(11u64)
+        .build();
+    storage.put_stream(request).await.unwrap();
+    let response = storage.get_stream("a/test.txt".into()).await.unwrap();
+    assert_eq!(response.length, Some(11));
+    assert_eq!(response.read_to_end().await.unwrap(), b"hello world");
+}
+
+#[tokio::test]
+async fn test_copy_and_rename() {
+    let storage = MemoryStorage::new();
+    storage
+        .put(PutRequest::builder().path("a/test.txt").data("hello world!").build())
+        .await
+        .unwrap();
+    storage.copy(("a/test.txt", "b/test.txt").into()).await.unwrap();
+    assert_eq!(storage.get("b/test.txt".into()).await.unwrap().data, b"hello world!");
+    let error = storage.copy(("a/test.txt", "b/test.txt").into()).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::AlreadyExists(_))
+    ));
+    let copy_request = CopyRequest::builder()
+        .from("a/test.txt")
+        .to("b/test.txt")
+        .overwrite(true)
+        .build();
+    storage.copy(copy_request).await.unwrap();
+    storage.rename(("b/test.txt", "c/test.txt").into()).await.unwrap();
+    assert!(!storage.exists("b/test.txt".into()).await.unwrap().exists);
+    assert_eq!(storage.get("c/test.txt".into()).await.unwrap().data, b"hello world!");
+    assert!(storage.rename(("a/missing.txt", "d/test.txt").into()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_remove() {
+    let storage = MemoryStorage::new();
+    storage.put("a/test.txt".into()).await.unwrap();
+    storage.put("a/b/test.txt".into()).await.unwrap();
+    storage.put("a/b/c/test.txt".into()).await.unwrap();
+    storage.put("ab/test.txt".into()).await.unwrap();
+    storage
+        .remove_files(vec![PathBuf::from("a/test.txt"), PathBuf::from("a/missing.txt")].into())
+        .await
+        .unwrap();
+    assert!(!storage.exists("a/test.txt".into()).await.unwrap().exists);
+    storage.remove_file("a/b/test.txt".into()).await.unwrap();
+    assert!(!storage.exists("a/b/test.txt".into()).await.unwrap().exists);
+    let request = RemoveFolderRequest::builder().path("a").non_recursively(true).build();
+    assert!(storage.remove_folder(request).await.is_err());
+    storage.remove_folder("a".into()).await.unwrap();
+    assert!(!storage.exists("a".into()).await.unwrap().exists);
+    assert!(storage.exists("ab/test.txt".into()).await.unwrap().exists);
+}
@@ -1,8 +1,18 @@
-use async_trait::async_trait;
-use mockall::mock;
-use mystiko_static_storage::{PutRequest, S3Storage, Storage};
+// the mocked rusoto traits fix the error types, so they cannot be boxed.
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::MockS3Client;
+use futures::{StreamExt, TryStreamExt};
+use mystiko_static_storage::{
+    CopyRequest, GetRequest, ListFilesRequest, ObjectMeta, PutRequest, PutStreamRequest, S3CredentialsConfig,
+    S3MultipartConfig, S3RawClient, S3Storage, S3StorageConfig, Storage, StorageError, StorageReader,
+};
 use rusoto_core::request::BufferedHttpResponse;
-use rusoto_core::{ByteStream, RusotoError};
+use rusoto_core::{ByteStream, Client, Region, RusotoError};
+use rusoto_mock::{MockCredentialsProvider, MockRequestDispatcher};
 use rusoto_s3::*;
 use std::path::PathBuf;
 
@@ -13,7 +23,7 @@ async fn test_list_folders() {
         .expect_list_objects_v2()
         .withf(|request| {
             request.bucket == "test-bucket"
-                && request.prefix == Some("test-base/test-path".to_string())
+                && request.prefix == Some("test-base/test-path/".to_string())
                 && request.delimiter == Some("/".to_string())
         })
         .times(1)
@@ -21,10 +31,10 @@ async fn test_list_folders() {
             Ok(ListObjectsV2Output {
                 common_prefixes: Some(vec![
                     CommonPrefix {
-                        prefix: Some("test-base/test-path/test-folder-1".to_string()),
+                        prefix: Some("test-base/test-path/test-folder-1/".to_string()),
                     },
                     CommonPrefix {
-                        prefix: Some("test-base/test-path/test-folder-2".to_string()),
+                        prefix: Some("test-base/test-path/test-folder-2/".to_string()),
                     },
                 ]),
                 ..Default::default()
@@ -53,7 +63,7 @@ async fn test_list_folders_continuation_token() {
         .expect_list_objects_v2()
         .withf(|request| {
             request.bucket == "test-bucket"
-                && request.prefix == Some("test-base/test-path".to_string())
+                && request.prefix == Some("test-base/test-path/".to_string())
                 && request.delimiter == Some("/".to_string())
         })
         .times(2)
@@ -61,15 +71,16 @@ async fn test_list_folders_continuation_token() {
             if request.continuation_token.is_none() {
                 Ok(ListObjectsV2Output {
                     common_prefixes: Some(vec![CommonPrefix {
-                        prefix: Some("test-base/test-path/test-folder-1".to_string()),
+                        prefix: Some("test-base/test-path/test-folder-1/".to_string()),
                     }]),
-                    continuation_token: Some("test-continuation-token".to_string()),
+                    next_continuation_token: Some("test-continuation-token".to_string()),
                     ..Default::default()
                 })
             } else {
+                assert_eq!(request.continuation_token, Some("test-continuation-token".to_string()));
                 Ok(ListObjectsV2Output {
                     common_prefixes: Some(vec![CommonPrefix {
-                        prefix: Some("test-base/test-path/test-folder-2".to_string()),
+                        prefix: Some("test-base/test-path/test-folder-2/".to_string()),
                     }]),
                     ..Default::default()
                 })
@@ -98,7 +109,7 @@ async fn test_list_files() {
         .expect_list_objects_v2()
         .withf(|request| {
             request.bucket == "test-bucket"
-                && request.prefix == Some("test-base/test-path".to_string())
+                && request.prefix == Some("test-base/test-path/".to_string())
                 && request.delimiter.is_none()
         })
         .times(1)
@@ -140,7 +151,7 @@ async fn test_list_files_continuation_token() {
         .expect_list_objects_v2()
         .withf(|request| {
             request.bucket == "test-bucket"
-                && request.prefix == Some("test-base/test-path".to_string())
+                && request.prefix == Some("test-base/test-path/".to_string())
                 && request.delimiter.is_none()
         })
         .times(2)
@@ -151,10 +162,11 @@ async fn test_list_files_continuation_token() {
                         key: Some("test-base/test-path/test-file-1".to_string()),
                         ..Default::default()
                     }]),
-                    continuation_token: Some("test-continuation-token".to_string()),
+                    next_continuation_token: Some("test-continuation-token".to_string()),
                     ..Default::default()
                 })
             } else {
+                assert_eq!(request.continuation_token, Some("test-continuation-token".to_string()));
                 Ok(ListObjectsV2Output {
                     contents: Some(vec![Object {
                         key: Some("test-base/test-path/test-file-2".to_string()),
@@ -180,6 +192,165 @@ async fn test_list_files_continuation_token() {
     assert_eq!(result, vec!["test-path/test-file-1", "test-path/test-file-2"]);
 }
 
+#[tokio::test]
+async fn test_list_files_stream() {
+    let mut client = MockS3Client::new();
+    client
+        .expect_list_objects_v2()
+        .withf(|request| {
+            request.bucket == "test-bucket"
+                && request.prefix == Some("test-base/test-path/".to_string())
+                && request.max_keys == Some(1)
+        })
+        .times(2)
+        .returning(|request| {
+            if request.continuation_token.is_none() {
+                Ok(ListObjectsV2Output {
+                    contents: Some(vec![Object {
+                        key: Some("test-base/test-path/test-file-1".to_string()),
+                        size: Some(10),
+                        ..Default::default()
+                    }]),
+                    next_continuation_token: Some("test-continuation-token".to_string()),
+                    is_truncated: Some(true),
+                    ..Default::default()
+                })
+            } else {
+                assert_eq!(request.continuation_token, Some("test-continuation-token".to_string()));
+                Ok(ListObjectsV2Output {
+                    contents: Some(vec![Object {
+                        key: Some("test-base/test-path/test-file-2".to_string()),
+                        size: Some(20),
+                        ..Default::default()
+                    }]),
+                    continuation_token: Some("test-continuation-token".to_string()),
+                    is_truncated: Some(false),
+                    ..Default::default()
+                })
+            }
+        });
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let objects = storage
+        .list_files_stream(ListFilesRequest::builder().path("test-path").page_size(1).build())
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(
+        objects,
+        vec![
+            ObjectMeta::builder().path("test-path/test-file-1").size(10u64).build(),
+            ObjectMeta::builder().path("test-path/test-file-2").size(20u64).build(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_list_files_stream_error() {
+    let mut client = MockS3Client::new();
+    client.expect_list_objects_v2().times(1).returning(|_| {
+        Err(RusotoError::Service(ListObjectsV2Error::NoSuchBucket(
+            "test-error".to_string(),
+        )))
+    });
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let mut objects = storage.list_files_stream("test-path".into());
+    assert!(objects.next().await.unwrap().is_err());
+    assert!(objects.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_list_files_with_metadata() {
+    let mut client = MockS3Client::new();
+    client.expect_list_objects_v2().times(1).returning(|_| {
+        Ok(ListObjectsV2Output {
+            contents: Some(vec![Object {
+                key: Some("test-base/test-path/test-file-1".to_string()),
+                size: Some(10),
+                e_tag: Some("\"test-etag\"".to_string()),
+                last_modified: Some("2023-08-01T12:30:00.000Z".to_string()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        })
+    });
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let response = storage
+        .list_files(
+            ListFilesRequest::builder()
+                .path("test-path")
+                .with_metadata(true)
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.files, vec![PathBuf::from("test-path/test-file-1")]);
+    assert_eq!(
+        response.objects,
+        vec![ObjectMeta::builder()
+            .path("test-path/test-file-1")
+            .size(10u64)
+            .etag("\"test-etag\"")
+            .last_modified(Utc.with_ymd_and_hms(2023, 8, 1, 12, 30, 0).unwrap())
+            .build()]
+    );
+}
+
+#[tokio::test]
+async fn test_stat() {
+    let mut client = MockS3Client::new();
+    client
+        .expect_head_object()
+        .withf(|request| request.bucket == "test-bucket" && request.key == "test-base/test-path/test-file1")
+        .times(1)
+        .returning(|_| {
+            Ok(HeadObjectOutput {
+                content_length: Some(1024),
+                content_type: Some("application/json".to_string()),
+                e_tag: Some("\"test-etag\"".to_string()),
+                last_modified: Some("Tue, 01 Aug 2023 12:30:00 GMT".to_string()),
+                ..Default::default()
+            })
+        });
+    client
+        .expect_head_object()
+        .withf(|request| request.bucket == "test-bucket" && request.key == "test-base/test-path/test-file2")
+        .times(1)
+        .returning(|_| {
+            Err(RusotoError::Service(HeadObjectError::NoSuchKey(
+                "test-error".to_string(),
+            )))
+        });
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let meta = storage.stat("test-path/test-file1".into()).await.unwrap().meta;
+    assert_eq!(
+        meta,
+        ObjectMeta::builder()
+            .path("test-path/test-file1")
+            .size(1024u64)
+            .content_type("application/json")
+            .etag("\"test-etag\"")
+            .last_modified(Utc.with_ymd_and_hms(2023, 8, 1, 12, 30, 0).unwrap())
+            .build()
+    );
+    assert!(storage.stat("test-path/test-file2".into()).await.is_err());
+}
+
 #[tokio::test]
 async fn test_exists() {
     let mut client = MockS3Client::new();
@@ -276,6 +447,250 @@ async fn test_get() {
         .is_empty());
 }
 
+#[tokio::test]
+async fn test_get_range() {
+    let mut client = MockS3Client::new();
+    client
+        .expect_get_object()
+        .withf(|request| {
+            request.key == "test-base/test-path/test-file1" && request.range == Some("bytes=6-10".to_string())
+        })
+        .times(1)
+        .returning(|_| {
+            Ok(GetObjectOutput {
+                body: Some(ByteStream::from("world".as_bytes().to_vec())),
+                content_length: Some(5),
+                ..Default::default()
+            })
+        });
+    client
+        .expect_get_object()
+        .withf(|request| {
+            request.key == "test-base/test-path/test-file1" && request.range == Some("bytes=6-".to_string())
+        })
+        .times(2)
+        .returning(|_| {
+            Ok(GetObjectOutput {
+                body: Some(ByteStream::from("world".as_bytes().to_vec())),
+                content_length: Some(5),
+                ..Default::default()
+            })
+        });
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let response = storage
+        .get(
+            GetRequest::builder()
+                .path("test-path/test-file1")
+                .offset(6u64)
+                .length(5u64)
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.data, b"world");
+    let response = storage
+        .get_stream(GetRequest::builder().path("test-path/test-file1").offset(6u64).build())
+        .await
+        .unwrap();
+    assert_eq!(response.length, Some(5));
+    assert_eq!(response.read_to_end().await.unwrap(), b"world");
+    let response = storage
+        .get(
+            GetRequest::builder()
+                .path("test-path/test-file1")
+                .offset(6u64)
+                .length(u64::MAX)
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.data, b"world");
+    let response = storage
+        .get(GetRequest::builder().path("test-path/test-file1").length(0u64).build())
+        .await
+        .unwrap();
+    assert!(response.data.is_empty());
+}
+
+#[tokio::test]
+async fn test_get_stream() {
+    let mut client = MockS3Client::new();
+    client
+        .expect_get_object()
+        .withf(|request| request.bucket == "test-bucket" && request.key == "test-base/test-path/test-file1")
+        .times(1)
+        .returning(|_| {
+            Ok(GetObjectOutput {
+                body: Some(ByteStream::from("test-data1".as_bytes().to_vec())),
+                content_length: Some(10),
+                ..Default::default()
+            })
+        });
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let response = storage.get_stream("test-path/test-file1".into()).await.unwrap();
+    assert_eq!(response.length, Some(10));
+    assert_eq!(response.read_to_end().await.unwrap(), b"test-data1");
+}
+
+#[tokio::test]
+async fn test_put_stream() {
+    let mut client = MockS3Client::new();
+    client.expect_head_object().times(1).returning(|_| {
+        Err(RusotoError::Service(HeadObjectError::NoSuchKey(
+            "test-error".to_string(),
+        )))
+    });
+    client
+        .expect_put_object()
+        .withf(|request| {
+            request.bucket == "test-bucket"
+                && request.key == "test-base/test-path/test-file1"
+                && request.content_length == Some(10)
+                && request.content_type == Some("text/plain".to_string())
+                && request.body.is_some()
+        })
+        .times(1)
+        .returning(|_| Ok(PutObjectOutput::default()));
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let request = PutStreamRequest::builder()
+        .path("test-path/test-file1")
+        .reader(Box::pin(std::io::Cursor::new(b"test-data1".to_vec())) as StorageReader)
+        .length(10u64)
+        .content_type("text/plain")
+        .build();
+    storage.put_stream(request).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_copy_encoded_source() {
+    let mut client = MockS3Client::new();
+    client
+        .expect_head_object()
+        .withf(|request| request.key == "test-base/test path/test+file 1.txt")
+        .times(1)
+        .returning(|_| {
+            Ok(HeadObjectOutput {
+                content_length: Some(12),
+                ..Default::default()
+            })
+        });
+    client
+        .expect_copy_object()
+        .withf(|request| {
+            request.copy_source == "test-bucket/test-base/test%20path/test%2Bfile%201.txt"
+                && request.key == "test-base/test path/test+file 2.txt"
+        })
+        .times(1)
+        .returning(|_| Ok(CopyObjectOutput::default()));
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let request = CopyRequest::builder()
+        .from("test path/test+file 1.txt")
+        .to("test path/test+file 2.txt")
+        .overwrite(true)
+        .build();
+    storage.copy(request).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_copy_multipart() {
+    let size = 6 * 1024 * 1024 * 1024u64;
+    let mut client = MockS3Client::new();
+    client
+        .expect_head_object()
+        .withf(|request| request.key == "test-base/test-path/test-file1")
+        .times(1)
+        .returning(move |_| {
+            Ok(HeadObjectOutput {
+                content_length: Some(size as i64),
+                content_type: Some("application/octet-stream".to_string()),
+                ..Default::default()
+            })
+        });
+    client.expect_copy_object().never();
+    client
+        .expect_create_multipart_upload()
+        .withf(|request| {
+            request.key == "test-base/test-path/test-file2"
+                && request.content_type.as_deref() == Some("application/octet-stream")
+        })
+        .times(1)
+        .returning(|_| {
+            Ok(CreateMultipartUploadOutput {
+                upload_id: Some("test-upload".to_string()),
+                ..Default::default()
+            })
+        });
+    client
+        .expect_upload_part_copy()
+        .withf(move |request| {
+            let range = request.copy_source_range.clone().unwrap();
+            request.upload_id == "test-upload"
+                && request.copy_source == "test-bucket/test-base/test-path/test-file1"
+                && match request.part_number {
+                    1 => range == "bytes=0-4294967295",
+                    2 => range == format!("bytes=4294967296-{}", size - 1),
+                    _ => false,
+                }
+        })
+        .times(2)
+        .returning(|request| {
+            Ok(UploadPartCopyOutput {
+                copy_part_result: Some(CopyPartResult {
+                    e_tag: Some(format!("etag-{}", request.part_number)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        });
+    client
+        .expect_complete_multipart_upload()
+        .withf(|request| {
+            let parts = request.multipart_upload.clone().unwrap().parts.unwrap();
+            parts.len() == 2
+                && parts[0].e_tag.as_deref() == Some("etag-1")
+                && parts[1].e_tag.as_deref() == Some("etag-2")
+        })
+        .times(1)
+        .returning(|_| {
+            Ok(CompleteMultipartUploadOutput {
+                e_tag: Some("test-etag".to_string()),
+                ..Default::default()
+            })
+        });
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .multipart(
+            S3MultipartConfig::builder()
+                .part_size(4 * 1024 * 1024 * 1024u64)
+                .build(),
+        )
+        .build();
+    let request = CopyRequest::builder()
+        .from("test-path/test-file1")
+        .to("test-path/test-file2")
+        .overwrite(true)
+        .build();
+    assert_eq!(storage.copy(request).await.unwrap().etag, Some("test-etag".to_string()));
+}
+
 #[tokio::test]
 async fn test_put() {
     let mut client = MockS3Client::new();
@@ -302,7 +717,7 @@ async fn test_put() {
     client
         .expect_head_object()
         .withf(|request| request.bucket == "test-bucket" && request.key == "test-base/test-path/test-file2")
-        .times(2)
+        .times(1)
         .returning(|_| Ok(HeadObjectOutput::default()));
     client
         .expect_put_object()
@@ -340,6 +755,353 @@ async fn test_put() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn test_put_conditional() {
+    let dispatcher = MockRequestDispatcher::with_status(200)
+        .with_header("ETag", "\"test-etag2\"")
+        .with_request_checker(|request| {
+            assert_eq!(request.method(), "PUT");
+            assert_eq!(request.path(), "/test-bucket/test-base/test-path/test-file1");
+            assert_eq!(request.headers().get("if-match"), Some(&vec![b"test-etag".to_vec()]));
+            assert_eq!(
+                request.headers().get("content-type"),
+                Some(&vec![b"text/plain".to_vec()])
+            );
+            assert_eq!(request.headers().get("content-length"), Some(&vec![b"1".to_vec()]));
+        });
+    let storage = conditional_storage(dispatcher);
+    let request = PutRequest::builder()
+        .path("test-path/test-file1")
+        .data(vec![1u8])
+        .content_type("text/plain")
+        .if_match("test-etag")
+        .build();
+    let response = storage.put(request).await.unwrap();
+    assert_eq!(response.etag, Some("\"test-etag2\"".to_string()));
+
+    let dispatcher = MockRequestDispatcher::with_status(200).with_request_checker(|request| {
+        assert_eq!(request.headers().get("if-none-match"), Some(&vec![b"*".to_vec()]));
+        assert!(request.headers().get("if-match").is_none());
+    });
+    let storage = conditional_storage(dispatcher);
+    let request = PutStreamRequest::builder()
+        .path("test-path/test-file1")
+        .reader(Box::pin(std::io::Cursor::new(vec![1u8])) as StorageReader)
+        .length(1u64)
+        .if_none_match("*")
+        .build();
+    storage.put_stream(request).await.unwrap();
+
+    for status in [404, 409, 412] {
+        let storage = conditional_storage(MockRequestDispatcher::with_status(status));
+        let request = PutRequest::builder()
+            .path("test-path/test-file1")
+            .if_match("test-etag")
+            .build();
+        let error = storage.put(request).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<StorageError>(),
+            Some(StorageError::PreconditionFailed(_))
+        ));
+    }
+
+    let storage = conditional_storage(MockRequestDispatcher::with_status(500));
+    let request = PutRequest::builder()
+        .path("test-path/test-file1")
+        .if_none_match("*")
+        .build();
+    let error = storage.put(request).await.unwrap_err();
+    assert!(error.downcast_ref::<StorageError>().is_none());
+}
+
+#[tokio::test]
+async fn test_put_conditional_unsupported() {
+    let mut client = MockS3Client::new();
+    client.expect_head_object().never();
+    client.expect_put_object().never();
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let requests = vec![
+        PutRequest::builder()
+            .path("test-path/test-file1")
+            .if_match("test-etag")
+            .build(),
+        PutRequest::builder()
+            .path("test-path/test-file1")
+            .if_none_match("*")
+            .overwrite(true)
+            .build(),
+    ];
+    for request in requests {
+        let error = storage.put(request).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<StorageError>(),
+            Some(StorageError::Unsupported(_))
+        ));
+    }
+    let storage = conditional_storage(MockRequestDispatcher::with_status(200));
+    let request = PutStreamRequest::builder()
+        .path("test-path/test-file1")
+        .reader(Box::pin(std::io::Cursor::new(vec![1u8])) as StorageReader)
+        .length(6 * 1024 * 1024 * 1024u64)
+        .if_match("test-etag")
+        .build();
+    let error = storage.put_stream(request).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::Unsupported(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_put_multipart() {
+    let mut client = MockS3Client::new();
+    client.expect_head_object().times(2).returning(|_| {
+        Err(RusotoError::Service(HeadObjectError::NoSuchKey(
+            "test-error".to_string(),
+        )))
+    });
+    client
+        .expect_create_multipart_upload()
+        .withf(|request| {
+            request.bucket == "test-bucket"
+                && request.key == "test-base/test-path/test-file1"
+                && request.content_type == Some("application/octet-stream".to_string())
+        })
+        .times(2)
+        .returning(|_| {
+            Ok(CreateMultipartUploadOutput {
+                upload_id: Some("test-upload-id".to_string()),
+                ..Default::default()
+            })
+        });
+    client
+        .expect_upload_part()
+        .withf(|request| {
+            request.upload_id == "test-upload-id"
+                && request.key == "test-base/test-path/test-file1"
+                && request.content_length == Some(if request.part_number == 3 { 2 } else { PART_SIZE as i64 })
+        })
+        .times(6)
+        .returning(|request| {
+            Ok(UploadPartOutput {
+                e_tag: Some(format!("test-etag-{}", request.part_number)),
+                ..Default::default()
+            })
+        });
+    client
+        .expect_complete_multipart_upload()
+        .withf(|request| {
+            let parts = request.multipart_upload.as_ref().unwrap().parts.as_ref().unwrap();
+            request.upload_id == "test-upload-id"
+                && parts
+                    .iter()
+                    .map(|part| (part.part_number.unwrap(), part.e_tag.clone().unwrap()))
+                    .collect::<Vec<_>>()
+                    == vec![
+                        (1, "test-etag-1".to_string()),
+                        (2, "test-etag-2".to_string()),
+                        (3, "test-etag-3".to_string()),
+                    ]
+        })
+        .times(2)
+        .returning(|_| {
+            Ok(CompleteMultipartUploadOutput {
+                e_tag: Some("test-etag".to_string()),
+                ..Default::default()
+            })
+        });
+    client.expect_put_object().never();
+    client.expect_abort_multipart_upload().never();
+    let storage = multipart_storage(client, 0);
+    let response = storage
+        .put(
+            PutRequest::builder()
+                .path("test-path/test-file1")
+                .data(multipart_data())
+                .content_type("application/octet-stream")
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.etag, Some("test-etag".to_string()));
+    let request = PutStreamRequest::builder()
+        .path("test-path/test-file1")
+        .reader(Box::pin(std::io::Cursor::new(multipart_data())) as StorageReader)
+        .length(multipart_data().len() as u64)
+        .content_type("application/octet-stream")
+        .build();
+    let response = storage.put_stream(request).await.unwrap();
+    assert_eq!(response.etag, Some("test-etag".to_string()));
+}
+
+#[tokio::test]
+async fn test_put_multipart_retry() {
+    let mut client = MockS3Client::new();
+    client.expect_head_object().times(1).returning(|_| {
+        Err(RusotoError::Service(HeadObjectError::NoSuchKey(
+            "test-error".to_string(),
+        )))
+    });
+    client.expect_create_multipart_upload().times(1).returning(|_| {
+        Ok(CreateMultipartUploadOutput {
+            upload_id: Some("test-upload-id".to_string()),
+            ..Default::default()
+        })
+    });
+    let mut failures = 2;
+    client.expect_upload_part().times(5).returning(move |request| {
+        if request.part_number == 2 && failures > 0 {
+            failures -= 1;
+            return Err(RusotoError::Validation("test-error".to_string()));
+        }
+        Ok(UploadPartOutput {
+            e_tag: Some(format!("test-etag-{}", request.part_number)),
+            ..Default::default()
+        })
+    });
+    client
+        .expect_complete_multipart_upload()
+        .times(1)
+        .returning(|_| Ok(CompleteMultipartUploadOutput::default()));
+    client.expect_abort_multipart_upload().never();
+    let storage = multipart_storage(client, 2);
+    storage
+        .put(
+            PutRequest::builder()
+                .path("test-path/test-file1")
+                .data(multipart_data())
+                .build(),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_put_multipart_abort() {
+    let mut client = MockS3Client::new();
+    client.expect_head_object().times(2).returning(|_| {
+        Err(RusotoError::Service(HeadObjectError::NoSuchKey(
+            "test-error".to_string(),
+        )))
+    });
+    client.expect_create_multipart_upload().times(2).returning(|_| {
+        Ok(CreateMultipartUploadOutput {
+            upload_id: Some("test-upload-id".to_string()),
+            ..Default::default()
+        })
+    });
+    client.expect_upload_part().returning(|request| {
+        if request.part_number == 2 {
+            Err(RusotoError::Validation("test-error".to_string()))
+        } else {
+            Ok(UploadPartOutput::default())
+        }
+    });
+    client.expect_complete_multipart_upload().never();
+    client
+        .expect_abort_multipart_upload()
+        .withf(|request| {
+            request.bucket == "test-bucket"
+                && request.key == "test-base/test-path/test-file1"
+                && request.upload_id == "test-upload-id"
+        })
+        .times(2)
+        .returning(|_| Ok(AbortMultipartUploadOutput::default()));
+    let storage = multipart_storage(client, 1);
+    assert!(storage
+        .put(
+            PutRequest::builder()
+                .path("test-path/test-file1")
+                .data(multipart_data())
+                .build(),
+        )
+        .await
+        .is_err());
+    let truncated = PutStreamRequest::builder()
+        .path("test-path/test-file1")
+        .reader(Box::pin(std::io::Cursor::new(b"0123".to_vec())) as StorageReader)
+        .length(10u64)
+        .build();
+    assert!(storage.put_stream(truncated).await.is_err());
+}
+
+#[tokio::test]
+async fn test_copy() {
+    let mut client = MockS3Client::new();
+    client
+        .expect_head_object()
+        .withf(|request| request.bucket == "test-bucket" && request.key == "test-base/test-path/test-file2")
+        .times(2)
+        .returning(|_| {
+            Err(RusotoError::Service(HeadObjectError::NoSuchKey(
+                "test-error".to_string(),
+            )))
+        });
+    client
+        .expect_head_object()
+        .withf(|request| request.bucket == "test-bucket" && request.key == "test-base/test-path/test-file3")
+        .times(1)
+        .returning(|_| Ok(HeadObjectOutput::default()));
+    client
+        .expect_head_object()
+        .withf(|request| request.bucket == "test-bucket" && request.key == "test-base/test-path/test-file1")
+        .times(2)
+        .returning(|_| {
+            Ok(HeadObjectOutput {
+                content_length: Some(12),
+                ..Default::default()
+            })
+        });
+    client
+        .expect_copy_object()
+        .withf(|request| {
+            request.bucket == "test-bucket"
+                && request.copy_source == "test-bucket/test-base/test-path/test-file1"
+                && request.key == "test-base/test-path/test-file2"
+        })
+        .times(2)
+        .returning(|_| {
+            Ok(CopyObjectOutput {
+                copy_object_result: Some(CopyObjectResult {
+                    e_tag: Some("test-etag".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        });
+    client
+        .expect_delete_object()
+        .withf(|request| request.bucket == "test-bucket" && request.key == "test-base/test-path/test-file1")
+        .times(1)
+        .returning(|_| Ok(DeleteObjectOutput::default()));
+    let storage = S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .build();
+    let response = storage
+        .copy(("test-path/test-file1", "test-path/test-file2").into())
+        .await
+        .unwrap();
+    assert_eq!(response.etag, Some("test-etag".to_string()));
+    let error = storage
+        .copy(("test-path/test-file1", "test-path/test-file3").into())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<StorageError>(),
+        Some(StorageError::AlreadyExists(_))
+    ));
+    storage
+        .rename(("test-path/test-file1", "test-path/test-file2").into())
+        .await
+        .unwrap();
+}
+
 #[tokio::test]
 async fn test_remove_files() {
     let mut client = MockS3Client::new();
@@ -396,7 +1158,7 @@ async fn test_remove_folder() {
         .expect_list_objects_v2()
         .withf(|request| {
             request.bucket == "test-bucket"
-                && request.prefix == Some("test-base/test-path".to_string())
+                && request.prefix == Some("test-base/test-path/".to_string())
                 && request.delimiter.is_none()
         })
         .times(1)
@@ -453,364 +1215,56 @@ async fn test_with_default() {
     storage.remove_file("test-path/test-file1".into()).await.unwrap();
 }
 
-mock! {
-    #[derive(Debug)]
-    S3Client {}
-
-    #[async_trait]
-    impl S3 for S3Client {
-        async fn abort_multipart_upload(
-            &self,
-            input: AbortMultipartUploadRequest,
-        ) -> Result<AbortMultipartUploadOutput, RusotoError<AbortMultipartUploadError>>;
-        async fn complete_multipart_upload(
-            &self,
-            input: CompleteMultipartUploadRequest,
-        ) -> Result<CompleteMultipartUploadOutput, RusotoError<CompleteMultipartUploadError>>;
-        async fn copy_object(&self, input: CopyObjectRequest) -> Result<CopyObjectOutput, RusotoError<CopyObjectError>>;
-        async fn create_bucket(
-            &self,
-            input: CreateBucketRequest,
-        ) -> Result<CreateBucketOutput, RusotoError<CreateBucketError>>;
-        async fn create_multipart_upload(
-            &self,
-            input: CreateMultipartUploadRequest,
-        ) -> Result<CreateMultipartUploadOutput, RusotoError<CreateMultipartUploadError>>;
-        async fn delete_bucket(&self, input: DeleteBucketRequest) -> Result<(), RusotoError<DeleteBucketError>>;
-        async fn delete_bucket_analytics_configuration(
-            &self,
-            input: DeleteBucketAnalyticsConfigurationRequest,
-        ) -> Result<(), RusotoError<DeleteBucketAnalyticsConfigurationError>>;
-        async fn delete_bucket_cors(
-            &self,
-            input: DeleteBucketCorsRequest,
-        ) -> Result<(), RusotoError<DeleteBucketCorsError>>;
-        async fn delete_bucket_encryption(
-            &self,
-            input: DeleteBucketEncryptionRequest,
-        ) -> Result<(), RusotoError<DeleteBucketEncryptionError>>;
-        async fn delete_bucket_intelligent_tiering_configuration(
-            &self,
-            input: DeleteBucketIntelligentTieringConfigurationRequest,
-        ) -> Result<(), RusotoError<DeleteBucketIntelligentTieringConfigurationError>>;
-        async fn delete_bucket_inventory_configuration(
-            &self,
-            input: DeleteBucketInventoryConfigurationRequest,
-        ) -> Result<(), RusotoError<DeleteBucketInventoryConfigurationError>>;
-        async fn delete_bucket_lifecycle(
-            &self,
-            input: DeleteBucketLifecycleRequest,
-        ) -> Result<(), RusotoError<DeleteBucketLifecycleError>>;
-        async fn delete_bucket_metrics_configuration(
-            &self,
-            input: DeleteBucketMetricsConfigurationRequest,
-        ) -> Result<(), RusotoError<DeleteBucketMetricsConfigurationError>>;
-        async fn delete_bucket_ownership_controls(
-            &self,
-            input: DeleteBucketOwnershipControlsRequest,
-        ) -> Result<(), RusotoError<DeleteBucketOwnershipControlsError>>;
-        async fn delete_bucket_policy(
-            &self,
-            input: DeleteBucketPolicyRequest,
-        ) -> Result<(), RusotoError<DeleteBucketPolicyError>>;
-        async fn delete_bucket_replication(
-            &self,
-            input: DeleteBucketReplicationRequest,
-        ) -> Result<(), RusotoError<DeleteBucketReplicationError>>;
-        async fn delete_bucket_tagging(
-            &self,
-            input: DeleteBucketTaggingRequest,
-        ) -> Result<(), RusotoError<DeleteBucketTaggingError>>;
-        async fn delete_bucket_website(
-            &self,
-            input: DeleteBucketWebsiteRequest,
-        ) -> Result<(), RusotoError<DeleteBucketWebsiteError>>;
-        async fn delete_object(
-            &self,
-            input: DeleteObjectRequest,
-        ) -> Result<DeleteObjectOutput, RusotoError<DeleteObjectError>>;
-        async fn delete_object_tagging(
-            &self,
-            input: DeleteObjectTaggingRequest,
-        ) -> Result<DeleteObjectTaggingOutput, RusotoError<DeleteObjectTaggingError>>;
-        async fn delete_objects(
-            &self,
-            input: DeleteObjectsRequest,
-        ) -> Result<DeleteObjectsOutput, RusotoError<DeleteObjectsError>>;
-        async fn delete_public_access_block(
-            &self,
-            input: DeletePublicAccessBlockRequest,
-        ) -> Result<(), RusotoError<DeletePublicAccessBlockError>>;
-        async fn get_bucket_accelerate_configuration(
-            &self,
-            input: GetBucketAccelerateConfigurationRequest,
-        ) -> Result<GetBucketAccelerateConfigurationOutput, RusotoError<GetBucketAccelerateConfigurationError>>;
-        async fn get_bucket_acl(
-            &self,
-            input: GetBucketAclRequest,
-        ) -> Result<GetBucketAclOutput, RusotoError<GetBucketAclError>>;
-        async fn get_bucket_analytics_configuration(
-            &self,
-            input: GetBucketAnalyticsConfigurationRequest,
-        ) -> Result<GetBucketAnalyticsConfigurationOutput, RusotoError<GetBucketAnalyticsConfigurationError>>;
-        async fn get_bucket_cors(
-            &self,
-            input: GetBucketCorsRequest,
-        ) -> Result<GetBucketCorsOutput, RusotoError<GetBucketCorsError>>;
-        async fn get_bucket_encryption(
-            &self,
-            input: GetBucketEncryptionRequest,
-        ) -> Result<GetBucketEncryptionOutput, RusotoError<GetBucketEncryptionError>>;
-        async fn get_bucket_intelligent_tiering_configuration(
-            &self,
-            input: GetBucketIntelligentTieringConfigurationRequest,
-        ) -> Result<
-            GetBucketIntelligentTieringConfigurationOutput,
-            RusotoError<GetBucketIntelligentTieringConfigurationError>,
-        >;
-        async fn get_bucket_inventory_configuration(
-            &self,
-            input: GetBucketInventoryConfigurationRequest,
-        ) -> Result<GetBucketInventoryConfigurationOutput, RusotoError<GetBucketInventoryConfigurationError>>;
-        async fn get_bucket_lifecycle(
-            &self,
-            input: GetBucketLifecycleRequest,
-        ) -> Result<GetBucketLifecycleOutput, RusotoError<GetBucketLifecycleError>>;
-        async fn get_bucket_lifecycle_configuration(
-            &self,
-            input: GetBucketLifecycleConfigurationRequest,
-        ) -> Result<GetBucketLifecycleConfigurationOutput, RusotoError<GetBucketLifecycleConfigurationError>>;
-        async fn get_bucket_location(
-            &self,
-            input: GetBucketLocationRequest,
-        ) -> Result<GetBucketLocationOutput, RusotoError<GetBucketLocationError>>;
-        async fn get_bucket_logging(
-            &self,
-            input: GetBucketLoggingRequest,
-        ) -> Result<GetBucketLoggingOutput, RusotoError<GetBucketLoggingError>>;
-        async fn get_bucket_metrics_configuration(
-            &self,
-            input: GetBucketMetricsConfigurationRequest,
-        ) -> Result<GetBucketMetricsConfigurationOutput, RusotoError<GetBucketMetricsConfigurationError>>;
-        async fn get_bucket_notification(
-            &self,
-            input: GetBucketNotificationConfigurationRequest,
-        ) -> Result<NotificationConfigurationDeprecated, RusotoError<GetBucketNotificationError>>;
-        async fn get_bucket_notification_configuration(
-            &self,
-            input: GetBucketNotificationConfigurationRequest,
-        ) -> Result<NotificationConfiguration, RusotoError<GetBucketNotificationConfigurationError>>;
-        async fn get_bucket_ownership_controls(
-            &self,
-            input: GetBucketOwnershipControlsRequest,
-        ) -> Result<GetBucketOwnershipControlsOutput, RusotoError<GetBucketOwnershipControlsError>>;
-        async fn get_bucket_policy(
-            &self,
-            input: GetBucketPolicyRequest,
-        ) -> Result<GetBucketPolicyOutput, RusotoError<GetBucketPolicyError>>;
-        async fn get_bucket_policy_status(
-            &self,
-            input: GetBucketPolicyStatusRequest,
-        ) -> Result<GetBucketPolicyStatusOutput, RusotoError<GetBucketPolicyStatusError>>;
-        async fn get_bucket_replication(
-            &self,
-            input: GetBucketReplicationRequest,
-        ) -> Result<GetBucketReplicationOutput, RusotoError<GetBucketReplicationError>>;
-        async fn get_bucket_request_payment(
-            &self,
-            input: GetBucketRequestPaymentRequest,
-        ) -> Result<GetBucketRequestPaymentOutput, RusotoError<GetBucketRequestPaymentError>>;
-        async fn get_bucket_tagging(
-            &self,
-            input: GetBucketTaggingRequest,
-        ) -> Result<GetBucketTaggingOutput, RusotoError<GetBucketTaggingError>>;
-        async fn get_bucket_versioning(
-            &self,
-            input: GetBucketVersioningRequest,
-        ) -> Result<GetBucketVersioningOutput, RusotoError<GetBucketVersioningError>>;
-        async fn get_bucket_website(
-            &self,
-            input: GetBucketWebsiteRequest,
-        ) -> Result<GetBucketWebsiteOutput, RusotoError<GetBucketWebsiteError>>;
-        async fn get_object(&self, input: GetObjectRequest) -> Result<GetObjectOutput, RusotoError<GetObjectError>>;
-        async fn get_object_acl(
-            &self,
-            input: GetObjectAclRequest,
-        ) -> Result<GetObjectAclOutput, RusotoError<GetObjectAclError>>;
-        async fn get_object_legal_hold(
-            &self,
-            input: GetObjectLegalHoldRequest,
-        ) -> Result<GetObjectLegalHoldOutput, RusotoError<GetObjectLegalHoldError>>;
-        async fn get_object_lock_configuration(
-            &self,
-            input: GetObjectLockConfigurationRequest,
-        ) -> Result<GetObjectLockConfigurationOutput, RusotoError<GetObjectLockConfigurationError>>;
-        async fn get_object_retention(
-            &self,
-            input: GetObjectRetentionRequest,
-        ) -> Result<GetObjectRetentionOutput, RusotoError<GetObjectRetentionError>>;
-        async fn get_object_tagging(
-            &self,
-            input: GetObjectTaggingRequest,
-        ) -> Result<GetObjectTaggingOutput, RusotoError<GetObjectTaggingError>>;
-        async fn get_object_torrent(
-            &self,
-            input: GetObjectTorrentRequest,
-        ) -> Result<GetObjectTorrentOutput, RusotoError<GetObjectTorrentError>>;
-        async fn get_public_access_block(
-            &self,
-            input: GetPublicAccessBlockRequest,
-        ) -> Result<GetPublicAccessBlockOutput, RusotoError<GetPublicAccessBlockError>>;
-        async fn head_bucket(&self, input: HeadBucketRequest) -> Result<(), RusotoError<HeadBucketError>>;
-        async fn head_object(&self, input: HeadObjectRequest) -> Result<HeadObjectOutput, RusotoError<HeadObjectError>>;
-        async fn list_bucket_analytics_configurations(
-            &self,
-            input: ListBucketAnalyticsConfigurationsRequest,
-        ) -> Result<ListBucketAnalyticsConfigurationsOutput, RusotoError<ListBucketAnalyticsConfigurationsError>>;
-        async fn list_bucket_intelligent_tiering_configurations(
-            &self,
-            input: ListBucketIntelligentTieringConfigurationsRequest,
-        ) -> Result<
-            ListBucketIntelligentTieringConfigurationsOutput,
-            RusotoError<ListBucketIntelligentTieringConfigurationsError>,
-        >;
-        async fn list_bucket_inventory_configurations(
-            &self,
-            input: ListBucketInventoryConfigurationsRequest,
-        ) -> Result<ListBucketInventoryConfigurationsOutput, RusotoError<ListBucketInventoryConfigurationsError>>;
-        async fn list_bucket_metrics_configurations(
-            &self,
-            input: ListBucketMetricsConfigurationsRequest,
-        ) -> Result<ListBucketMetricsConfigurationsOutput, RusotoError<ListBucketMetricsConfigurationsError>>;
-        async fn list_buckets(&self) -> Result<ListBucketsOutput, RusotoError<ListBucketsError>>;
-        async fn list_multipart_uploads(
-            &self,
-            input: ListMultipartUploadsRequest,
-        ) -> Result<ListMultipartUploadsOutput, RusotoError<ListMultipartUploadsError>>;
-        async fn list_object_versions(
-            &self,
-            input: ListObjectVersionsRequest,
-        ) -> Result<ListObjectVersionsOutput, RusotoError<ListObjectVersionsError>>;
-        async fn list_objects(&self, input: ListObjectsRequest)
-                              -> Result<ListObjectsOutput, RusotoError<ListObjectsError>>;
-        async fn list_objects_v2(
-            &self,
-            input: ListObjectsV2Request,
-        ) -> Result<ListObjectsV2Output, RusotoError<ListObjectsV2Error>>;
-        async fn list_parts(&self, input: ListPartsRequest) -> Result<ListPartsOutput, RusotoError<ListPartsError>>;
-        async fn put_bucket_accelerate_configuration(
-            &self,
-            input: PutBucketAccelerateConfigurationRequest,
-        ) -> Result<(), RusotoError<PutBucketAccelerateConfigurationError>>;
-        async fn put_bucket_acl(&self, input: PutBucketAclRequest) -> Result<(), RusotoError<PutBucketAclError>>;
-        async fn put_bucket_analytics_configuration(
-            &self,
-            input: PutBucketAnalyticsConfigurationRequest,
-        ) -> Result<(), RusotoError<PutBucketAnalyticsConfigurationError>>;
-        async fn put_bucket_cors(&self, input: PutBucketCorsRequest) -> Result<(), RusotoError<PutBucketCorsError>>;
-        async fn put_bucket_encryption(
-            &self,
-            input: PutBucketEncryptionRequest,
-        ) -> Result<(), RusotoError<PutBucketEncryptionError>>;
-        async fn put_bucket_intelligent_tiering_configuration(
-            &self,
-            input: PutBucketIntelligentTieringConfigurationRequest,
-        ) -> Result<(), RusotoError<PutBucketIntelligentTieringConfigurationError>>;
-        async fn put_bucket_inventory_configuration(
-            &self,
-            input: PutBucketInventoryConfigurationRequest,
-        ) -> Result<(), RusotoError<PutBucketInventoryConfigurationError>>;
-        async fn put_bucket_lifecycle(
-            &self,
-            input: PutBucketLifecycleRequest,
-        ) -> Result<(), RusotoError<PutBucketLifecycleError>>;
-        async fn put_bucket_lifecycle_configuration(
-            &self,
-            input: PutBucketLifecycleConfigurationRequest,
-        ) -> Result<(), RusotoError<PutBucketLifecycleConfigurationError>>;
-        async fn put_bucket_logging(
-            &self,
-            input: PutBucketLoggingRequest,
-        ) -> Result<(), RusotoError<PutBucketLoggingError>>;
-        async fn put_bucket_metrics_configuration(
-            &self,
-            input: PutBucketMetricsConfigurationRequest,
-        ) -> Result<(), RusotoError<PutBucketMetricsConfigurationError>>;
-        async fn put_bucket_notification(
-            &self,
-            input: PutBucketNotificationRequest,
-        ) -> Result<(), RusotoError<PutBucketNotificationError>>;
-        async fn put_bucket_notification_configuration(
-            &self,
-            input: PutBucketNotificationConfigurationRequest,
-        ) -> Result<(), RusotoError<PutBucketNotificationConfigurationError>>;
-        async fn put_bucket_ownership_controls(
-            &self,
-            input: PutBucketOwnershipControlsRequest,
-        ) -> Result<(), RusotoError<PutBucketOwnershipControlsError>>;
-        async fn put_bucket_policy(&self, input: PutBucketPolicyRequest) -> Result<(), RusotoError<PutBucketPolicyError>>;
-        async fn put_bucket_replication(
-            &self,
-            input: PutBucketReplicationRequest,
-        ) -> Result<(), RusotoError<PutBucketReplicationError>>;
-        async fn put_bucket_request_payment(
-            &self,
-            input: PutBucketRequestPaymentRequest,
-        ) -> Result<(), RusotoError<PutBucketRequestPaymentError>>;
-        async fn put_bucket_tagging(
-            &self,
-            input: PutBucketTaggingRequest,
-        ) -> Result<(), RusotoError<PutBucketTaggingError>>;
-        async fn put_bucket_versioning(
-            &self,
-            input: PutBucketVersioningRequest,
-        ) -> Result<(), RusotoError<PutBucketVersioningError>>;
-        async fn put_bucket_website(
-            &self,
-            input: PutBucketWebsiteRequest,
-        ) -> Result<(), RusotoError<PutBucketWebsiteError>>;
-        async fn put_object(&self, input: PutObjectRequest) -> Result<PutObjectOutput, RusotoError<PutObjectError>>;
-        async fn put_object_acl(
-            &self,
-            input: PutObjectAclRequest,
-        ) -> Result<PutObjectAclOutput, RusotoError<PutObjectAclError>>;
-        async fn put_object_legal_hold(
-            &self,
-            input: PutObjectLegalHoldRequest,
-        ) -> Result<PutObjectLegalHoldOutput, RusotoError<PutObjectLegalHoldError>>;
-        async fn put_object_lock_configuration(
-            &self,
-            input: PutObjectLockConfigurationRequest,
-        ) -> Result<PutObjectLockConfigurationOutput, RusotoError<PutObjectLockConfigurationError>>;
-        async fn put_object_retention(
-            &self,
-            input: PutObjectRetentionRequest,
-        ) -> Result<PutObjectRetentionOutput, RusotoError<PutObjectRetentionError>>;
-        async fn put_object_tagging(
-            &self,
-            input: PutObjectTaggingRequest,
-        ) -> Result<PutObjectTaggingOutput, RusotoError<PutObjectTaggingError>>;
-        async fn put_public_access_block(
-            &self,
-            input: PutPublicAccessBlockRequest,
-        ) -> Result<(), RusotoError<PutPublicAccessBlockError>>;
-        async fn restore_object(
-            &self,
-            input: RestoreObjectRequest,
-        ) -> Result<RestoreObjectOutput, RusotoError<RestoreObjectError>>;
-        async fn select_object_content(
-            &self,
-            input: SelectObjectContentRequest,
-        ) -> Result<SelectObjectContentOutput, RusotoError<SelectObjectContentError>>;
-        async fn upload_part(&self, input: UploadPartRequest) -> Result<UploadPartOutput, RusotoError<UploadPartError>>;
-        async fn upload_part_copy(
-            &self,
-            input: UploadPartCopyRequest,
-        ) -> Result<UploadPartCopyOutput, RusotoError<UploadPartCopyError>>;
-        async fn write_get_object_response(
-            &self,
-            input: WriteGetObjectResponseRequest,
-        ) -> Result<(), RusotoError<WriteGetObjectResponseError>>;
-    }
+const PART_SIZE: usize = 5 * 1024 * 1024;
 
+// two full parts and a short one, since s3 parts cannot be smaller than 5 MiB.
+fn multipart_data() -> Vec<u8> {
+    vec![7u8; 2 * PART_SIZE + 2]
+}
+
+fn multipart_storage(client: MockS3Client, max_retries: u32) -> S3Storage<MockS3Client> {
+    S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .multipart(
+            S3MultipartConfig::builder()
+                .threshold(4u64)
+                .part_size(4u64)
+                .concurrency(2usize)
+                .max_retries(max_retries)
+                .retry_backoff_ms(0u64)
+                .build(),
+        )
+        .build()
+}
+
+fn conditional_storage(dispatcher: MockRequestDispatcher) -> S3Storage<MockS3Client> {
+    let mut client = MockS3Client::new();
+    client.expect_head_object().never();
+    client.expect_put_object().never();
+    let raw_client = Client::new_with(MockCredentialsProvider, dispatcher);
+    S3Storage::<MockS3Client>::builder()
+        .base("test-base")
+        .s3_bucket("test-bucket")
+        .client(client)
+        .raw_client(S3RawClient::new(raw_client, Region::UsEast1))
+        .build()
+}
+
+#[test]
+fn test_from_config() {
+    let config = S3StorageConfig::builder()
+        .bucket("test-bucket")
+        .base("test-base")
+        .endpoint("http://localhost:9000")
+        .credentials(S3CredentialsConfig::Static {
+            access_key_id: "minio".to_string(),
+            secret_access_key: "minio123".to_string(),
+            session_token: None,
+        })
+        .build();
+    let storage = S3Storage::from_config(&config).unwrap();
+    assert_eq!(storage.s3_bucket, "test-bucket");
+    assert_eq!(storage.base, PathBuf::from("test-base"));
 }